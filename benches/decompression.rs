@@ -0,0 +1,32 @@
+// Decompression of a chunk of G2 points, batched by `DecodePoints` and one
+// at a time by `decode_each`, without and with the subgroup checks.
+//
+//   cargo bench --features bn254 --bench decompression
+
+use criterion::{ criterion_group, criterion_main, Criterion };
+use pairing::group::{ Curve, Group, GroupEncoding };
+use rand::thread_rng;
+
+use groth16::bn254::{ G2Affine, G2Projective };
+use groth16::source::{ decode_each, DecodePoints };
+
+const POINTS: usize = 64;
+
+fn g2_decompression(c: &mut Criterion) {
+    let points: Vec<G2Affine> = (0..POINTS).map(|_| G2Projective::random(thread_rng()).to_affine()).collect();
+    let bytes: Vec<u8> = points.iter().flat_map(|p| p.to_bytes().as_ref().to_vec()).collect();
+    let mut out = vec![G2Affine::default(); POINTS];
+
+    for checked in [false, true] {
+        let suffix = if checked { "checked" } else { "unchecked" };
+        c.bench_function(&format!("bn254 g2 batch {}", suffix), |b| {
+            b.iter(|| G2Affine::decode_points(&bytes, &mut out, true, checked).unwrap())
+        });
+        c.bench_function(&format!("bn254 g2 each {}", suffix), |b| {
+            b.iter(|| decode_each(&bytes, &mut out, true, checked).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, g2_decompression);
+criterion_main!(benches);
@@ -155,7 +155,7 @@ fn main(){
     println!("bellman proof: {:?}", bellproof);
 
     let grothparams = assignments::create_params(params);
-    let grothproof = prover::create_proof::<Bls12>(grothparams, inputsassign.as_ref(), auxassign.as_ref(), r, s, cap, m);
+    let grothproof = prover::create_proof::<Bls12, _>(grothparams, inputsassign.as_ref(), auxassign.as_ref(), r, s, cap, m).unwrap();
     println!("groth proof: {:?}", grothproof);
     let g2bproof = BellmanProof {
         a: grothproof.a.clone(),
@@ -0,0 +1,220 @@
+// Verification over whichever curve a key or proof is for, for services
+// that take proofs over both BLS12-381 and BN254 and would otherwise pick
+// the `Engine` themselves.
+//
+// `AnyProof` is an envelope, see `envelope`, whose curve number picks the
+// variant. `AnyVerificationKey` is encoded as that curve number followed by
+// the whole key:
+//
+//   curve u8 | alpha_g1 | beta_g1 | beta_g2 | gamma_g2 | delta_g1 | delta_g2
+//     | number of ic points u32 big-endian | ic
+//
+// with compressed points. There is a variant for each curve whose feature
+// is enabled, and the numbers of other curves decode with `Mismatch`. A
+// proof for one curve checked against a key for another fails with
+// `WrongCurve`, without any pairing.
+
+use pairing::Engine;
+
+#[cfg(any(test, feature = "bls12_381"))]
+use bls12_381::Bls12;
+
+#[cfg(any(test, feature = "bn254"))]
+use crate::bn254::Bn254;
+use crate::{ DecodingError, VerificationKey };
+use crate::encoding::{ Reader, Writer };
+use crate::envelope::{ self, decode_envelope, decode_header, encode_envelope, verify_envelope, Envelope, EnvelopeError };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::vec::Vec;
+
+// Keys are few and long-lived, so neither variant is boxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum AnyVerificationKey {
+    #[cfg(any(test, feature = "bls12_381"))]
+    Bls12(VerificationKey<Bls12>),
+    #[cfg(any(test, feature = "bn254"))]
+    Bn254(VerificationKey<Bn254>),
+}
+
+#[derive(Clone, Debug)]
+pub enum AnyProof {
+    #[cfg(any(test, feature = "bls12_381"))]
+    Bls12(Envelope<Bls12>),
+    #[cfg(any(test, feature = "bn254"))]
+    Bn254(Envelope<Bn254>),
+}
+
+fn write_vk<E: Engine>(w: &mut Writer, vk: &VerificationKey<E>) {
+    w.point(&vk.alpha_g1);
+    w.point(&vk.beta_g1);
+    w.point(&vk.beta_g2);
+    w.point(&vk.gamma_g2);
+    w.point(&vk.delta_g1);
+    w.point(&vk.delta_g2);
+    w.u32(vk.ic.len() as u32);
+    for p in vk.ic.iter() {
+        w.point(p);
+    }
+}
+
+fn read_vk<E: Engine>(rd: &mut Reader) -> Result<VerificationKey<E>, DecodingError> {
+    let mut vk = VerificationKey {
+        alpha_g1: rd.point()?,
+        beta_g1: rd.point()?,
+        beta_g2: rd.point()?,
+        gamma_g2: rd.point()?,
+        delta_g1: rd.point()?,
+        delta_g2: rd.point()?,
+        ic: Vec::new(),
+    };
+    for _ in 0..rd.u32()? {
+        vk.ic.push(rd.point()?);
+    }
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(vk)
+}
+
+impl AnyVerificationKey {
+    // Number of the curve, as in `envelope`
+    pub fn curve_id(&self) -> u8 {
+        match self {
+            #[cfg(any(test, feature = "bls12_381"))]
+            AnyVerificationKey::Bls12(_) => envelope::BLS12_381,
+            #[cfg(any(test, feature = "bn254"))]
+            AnyVerificationKey::Bn254(_) => envelope::BN254,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.u8(self.curve_id());
+        match self {
+            #[cfg(any(test, feature = "bls12_381"))]
+            AnyVerificationKey::Bls12(vk) => write_vk(&mut w, vk),
+            #[cfg(any(test, feature = "bn254"))]
+            AnyVerificationKey::Bn254(vk) => write_vk(&mut w, vk),
+        }
+        w.finish()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        match rd.u8()? {
+            #[cfg(any(test, feature = "bls12_381"))]
+            envelope::BLS12_381 => Ok(AnyVerificationKey::Bls12(read_vk(&mut rd)?)),
+            #[cfg(any(test, feature = "bn254"))]
+            envelope::BN254 => Ok(AnyVerificationKey::Bn254(read_vk(&mut rd)?)),
+            _ => Err(DecodingError::Mismatch),
+        }
+    }
+
+    // Decodes an envelope and verifies it with this key
+    pub fn verify_envelope(&self, bytes: &[u8]) -> Result<AnyProof, EnvelopeError> {
+        match self {
+            #[cfg(any(test, feature = "bls12_381"))]
+            AnyVerificationKey::Bls12(vk) => verify_envelope(bytes, vk).map(AnyProof::Bls12),
+            #[cfg(any(test, feature = "bn254"))]
+            AnyVerificationKey::Bn254(vk) => verify_envelope(bytes, vk).map(AnyProof::Bn254),
+        }
+    }
+
+    pub fn verify(&self, proof: &AnyProof) -> Result<(), EnvelopeError> {
+        // With a single curve enabled every pair matches
+        #[allow(unreachable_patterns)]
+        match (self, proof) {
+            #[cfg(any(test, feature = "bls12_381"))]
+            (AnyVerificationKey::Bls12(vk), AnyProof::Bls12(envelope)) => envelope.verify(vk),
+            #[cfg(any(test, feature = "bn254"))]
+            (AnyVerificationKey::Bn254(vk), AnyProof::Bn254(envelope)) => envelope.verify(vk),
+            _ => Err(EnvelopeError::WrongCurve(proof.curve_id())),
+        }
+    }
+}
+
+impl AnyProof {
+    // Number of the curve, as in `envelope`
+    pub fn curve_id(&self) -> u8 {
+        match self {
+            #[cfg(any(test, feature = "bls12_381"))]
+            AnyProof::Bls12(_) => envelope::BLS12_381,
+            #[cfg(any(test, feature = "bn254"))]
+            AnyProof::Bn254(_) => envelope::BN254,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let bytes = match self {
+            #[cfg(any(test, feature = "bls12_381"))]
+            AnyProof::Bls12(envelope) => encode_envelope(envelope),
+            #[cfg(any(test, feature = "bn254"))]
+            AnyProof::Bn254(envelope) => encode_envelope(envelope),
+        };
+        // Every variant is for a curve with a number
+        bytes.unwrap()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let (curve, _) = decode_header(&mut Reader::new(bytes))?;
+        match curve {
+            #[cfg(any(test, feature = "bls12_381"))]
+            envelope::BLS12_381 => Ok(AnyProof::Bls12(decode_envelope(bytes)?)),
+            #[cfg(any(test, feature = "bn254"))]
+            envelope::BN254 => Ok(AnyProof::Bn254(decode_envelope(bytes)?)),
+            _ => Err(DecodingError::Mismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{ cube_fixture, cube_fixture_on };
+    use super::*;
+
+    #[test]
+    fn dispatches_on_curve() {
+        let bls = cube_fixture(3);
+        let bn = cube_fixture_on::<Bn254>(3);
+        let bls_vk = AnyVerificationKey::Bls12(bls.params.vk.clone());
+        let bn_vk = AnyVerificationKey::Bn254(bn.params.vk.clone());
+        let bls_proof = encode_envelope(&Envelope::new(bls.prove(), bls.public_inputs(), &bls.params.vk)).unwrap();
+        let bn_proof = encode_envelope(&Envelope::new(bn.prove(), bn.public_inputs(), &bn.params.vk)).unwrap();
+
+        // Keys come back as the curve their tag names
+        let bytes = bn_vk.encode();
+        assert_eq!(bytes[0], envelope::BN254);
+        let decoded = AnyVerificationKey::decode(&bytes).unwrap();
+        assert_eq!(decoded.curve_id(), envelope::BN254);
+        let bls_decoded = AnyVerificationKey::decode(&bls_vk.encode()).unwrap();
+        assert_eq!(bls_decoded.curve_id(), envelope::BLS12_381);
+
+        assert!(decoded.verify_envelope(&bn_proof).is_ok());
+        assert!(bls_decoded.verify_envelope(&bls_proof).is_ok());
+        assert_eq!(decoded.verify_envelope(&bls_proof).err(), Some(EnvelopeError::WrongCurve(1)));
+
+        let proof = AnyProof::decode(&bn_proof).unwrap();
+        assert_eq!(proof.curve_id(), envelope::BN254);
+        assert_eq!(proof.encode(), bn_proof);
+        assert!(bn_vk.verify(&proof).is_ok());
+        assert_eq!(bls_vk.verify(&proof), Err(EnvelopeError::WrongCurve(2)));
+        let other = AnyVerificationKey::Bn254(cube_fixture_on::<Bn254>(3).params.vk);
+        assert_eq!(other.verify(&proof), Err(EnvelopeError::WrongKey));
+    }
+
+    #[test]
+    fn rejects_unknown_curves() {
+        let bytes = AnyVerificationKey::Bls12(cube_fixture(3).params.vk).encode();
+        let mut bad = bytes.clone();
+        bad[0] = 3;
+        assert!(matches!(AnyVerificationKey::decode(&bad), Err(DecodingError::Mismatch)));
+        assert!(matches!(AnyVerificationKey::decode(&[&bytes[..], &[0]].concat()), Err(DecodingError::InvalidHeader)));
+
+        let fixture = cube_fixture(3);
+        let mut proof = encode_envelope(&Envelope::new(fixture.prove(), fixture.public_inputs(), &fixture.params.vk)).unwrap();
+        proof[5] = 3;
+        assert!(matches!(AnyProof::decode(&proof), Err(DecodingError::Mismatch)));
+    }
+}
@@ -0,0 +1,492 @@
+// Keys and proofs of ark-groth16 over BLS12-381, in the byte layouts of
+// arkworks' `CanonicalSerialize`, without depending on arkworks.
+//
+//   Proof: a | b | c
+//   VerifyingKey: alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | gamma_abc_g1
+//   ProvingKey: VerifyingKey | beta_g1 | delta_g1 | a_query | b_g1_query
+//     | b_g2_query | h_query | l_query
+//
+// Vectors are a u64 little-endian length followed by the items. A base
+// field element is 48 bytes little-endian, and one of G2 is c0 | c1. A
+// compressed point is x, an uncompressed one x | y, and the flags go in the
+// top bits of the last byte. Bit 7 is set on compressed points when
+// y > -y, comparing c1 first in G2, and never on uncompressed ones. Bit 6
+// marks the identity, written with x = 0, and y = 1 when uncompressed.
+//
+// This is the generic short Weierstrass layout of ark-serialize, which
+// ark-bls12-381 uses up to 0.3, `Layout::Ark03`. From 0.4 on arkworks
+// writes BLS12-381 points like `bls12_381` does, big-endian with the flags
+// in the first byte, `Layout::Ark04`. The vectors and the order of the
+// fields are the same in both.
+//
+// Unlike `Parameters`, the A and B queries of arkworks have a point for
+// every variable, the identity where the polynomial is zero. Converting a
+// proving key therefore takes the QAP that says which points to keep.
+// The verifying key has no beta_g1 and delta_g1, which are left as the
+// identity when it is decoded alone.
+
+use bls12_381::{ Bls12, G1Affine, G2Affine };
+use pairing::group::prime::PrimeCurveAffine;
+
+use crate::{ DecodingError, Parameters, Proof, VerificationKey, QAP };
+use crate::encoding::{ Reader, Writer };
+use crate::snarkjs::select;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ vec, vec::Vec };
+
+// Encoding of points, which depends on the version of ark-bls12-381
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    // Up to 0.3, little-endian with the flags in the last byte
+    Ark03,
+    // From 0.4 on, the encodings of bls12_381
+    Ark04,
+}
+
+impl Layout {
+    // `encoded` is the bls12_381 encoding and `sign` the first byte of the
+    // compressed one
+    fn encode(self, encoded: &[u8], sign: u8, coordinate: usize) -> Vec<u8> {
+        match self {
+            Layout::Ark03 => to_ark(encoded, sign, coordinate),
+            Layout::Ark04 => encoded.to_vec(),
+        }
+    }
+
+    fn decode(self, bytes: &[u8], coordinate: usize) -> Result<Vec<u8>, DecodingError> {
+        match self {
+            Layout::Ark03 => from_ark(bytes, coordinate),
+            Layout::Ark04 => Ok(bytes.to_vec()),
+        }
+    }
+}
+
+// Size of a base field element
+const LEN: usize = 48;
+
+// Flags of arkworks, in the last byte
+const NEGATIVE: u8 = 1 << 7;
+const INFINITY: u8 = 1 << 6;
+
+// Flags of bls12_381, in the first byte
+const COMPRESSED: u8 = 1 << 7;
+const IDENTITY: u8 = 1 << 6;
+const LARGEST: u8 = 1 << 5;
+
+// The coordinates of bls12_381 are big-endian with G2 as c1 | c0, so each
+// coordinate of `coordinate` bytes is reversed as a whole. `sign` is the
+// first byte of the compressed encoding, which holds the sign of y.
+fn to_ark(encoded: &[u8], sign: u8, coordinate: usize) -> Vec<u8> {
+    let compressed = encoded.len() == coordinate;
+    let mut out = encoded.to_vec();
+    if encoded[0] & IDENTITY != 0 {
+        out.fill(0);
+        if !compressed {
+            out[coordinate] = 1;
+        }
+        *out.last_mut().unwrap() |= INFINITY;
+        return out;
+    }
+    out[0] &= !(COMPRESSED | IDENTITY | LARGEST);
+    out.chunks_mut(coordinate).for_each(|c| c.reverse());
+    if compressed && sign & LARGEST != 0 {
+        *out.last_mut().unwrap() |= NEGATIVE;
+    }
+    out
+}
+
+fn from_ark(bytes: &[u8], coordinate: usize) -> Result<Vec<u8>, DecodingError> {
+    let compressed = bytes.len() == coordinate;
+    let flags = bytes[bytes.len() - 1] & (NEGATIVE | INFINITY | LARGEST);
+    if flags & LARGEST != 0 || flags == NEGATIVE | INFINITY || (!compressed && flags == NEGATIVE) {
+        return Err(DecodingError::InvalidPoint);
+    }
+    let mut out = bytes.to_vec();
+    *out.last_mut().unwrap() &= !(NEGATIVE | INFINITY | LARGEST);
+    if flags & INFINITY != 0 {
+        // Only x = 0, and y = 1 when uncompressed
+        let identity = out.iter().enumerate().all(|(i, b)| *b == u8::from(!compressed && i == coordinate));
+        if !identity {
+            return Err(DecodingError::InvalidPoint);
+        }
+        out.fill(0);
+        out[0] = IDENTITY;
+    } else {
+        out.chunks_mut(coordinate).for_each(|c| c.reverse());
+        if flags & NEGATIVE != 0 {
+            out[0] |= LARGEST;
+        }
+    }
+    if compressed {
+        out[0] |= COMPRESSED;
+    }
+    Ok(out)
+}
+
+fn write_g1(w: &mut Writer, p: &G1Affine, layout: Layout, compressed: bool) {
+    let sign = p.to_compressed();
+    if compressed {
+        w.bytes(&layout.encode(&sign, sign[0], LEN));
+    } else {
+        w.bytes(&layout.encode(&p.to_uncompressed(), sign[0], LEN));
+    }
+}
+
+fn write_g2(w: &mut Writer, p: &G2Affine, layout: Layout, compressed: bool) {
+    let sign = p.to_compressed();
+    if compressed {
+        w.bytes(&layout.encode(&sign, sign[0], 2 * LEN));
+    } else {
+        w.bytes(&layout.encode(&p.to_uncompressed(), sign[0], 2 * LEN));
+    }
+}
+
+fn read_g1(rd: &mut Reader, layout: Layout, compressed: bool) -> Result<G1Affine, DecodingError> {
+    let b = layout.decode(rd.bytes(if compressed { LEN } else { 2 * LEN })?, LEN)?;
+    let p = if compressed {
+        G1Affine::from_compressed(&b[..].try_into().unwrap())
+    } else {
+        G1Affine::from_uncompressed(&b[..].try_into().unwrap())
+    };
+    Option::from(p).ok_or(DecodingError::InvalidPoint)
+}
+
+fn read_g2(rd: &mut Reader, layout: Layout, compressed: bool) -> Result<G2Affine, DecodingError> {
+    let b = layout.decode(rd.bytes(if compressed { 2 * LEN } else { 4 * LEN })?, 2 * LEN)?;
+    let p = if compressed {
+        G2Affine::from_compressed(&b[..].try_into().unwrap())
+    } else {
+        G2Affine::from_uncompressed(&b[..].try_into().unwrap())
+    };
+    Option::from(p).ok_or(DecodingError::InvalidPoint)
+}
+
+fn write_vec<T>(w: &mut Writer, items: &[T], mut write: impl FnMut(&mut Writer, &T)) {
+    w.bytes(&(items.len() as u64).to_le_bytes());
+    items.iter().for_each(|x| write(w, x));
+}
+
+fn read_vec<T>(
+    rd: &mut Reader,
+    mut read: impl FnMut(&mut Reader) -> Result<T, DecodingError>
+) -> Result<Vec<T>, DecodingError>
+{
+    let n = rd.u64_le()?;
+    (0..n).map(|_| read(rd)).collect()
+}
+
+fn decode_end<T>(rd: &Reader, value: T) -> Result<T, DecodingError> {
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(value)
+}
+
+// Points of all `num_vars` variables, the identity for those not in the
+// sorted `variables`
+fn expand<G: PrimeCurveAffine>(points: &[G], variables: &[usize], num_vars: usize) -> Vec<G> {
+    let mut out = vec![G::identity(); num_vars];
+    for (p, i) in points.iter().zip(variables) {
+        out[*i] = *p;
+    }
+    out
+}
+
+pub fn encode_proof(proof: &Proof<Bls12>, layout: Layout, compressed: bool) -> Vec<u8> {
+    let mut w = Writer::new();
+    write_g1(&mut w, &proof.a, layout, compressed);
+    write_g2(&mut w, &proof.b, layout, compressed);
+    write_g1(&mut w, &proof.c, layout, compressed);
+    w.finish()
+}
+
+pub fn decode_proof(bytes: &[u8], layout: Layout, compressed: bool) -> Result<Proof<Bls12>, DecodingError> {
+    let mut rd = Reader::new(bytes);
+    let proof = Proof {
+        a: read_g1(&mut rd, layout, compressed)?,
+        b: read_g2(&mut rd, layout, compressed)?,
+        c: read_g1(&mut rd, layout, compressed)?,
+    };
+    decode_end(&rd, proof)
+}
+
+fn write_vk(w: &mut Writer, vk: &VerificationKey<Bls12>, layout: Layout, compressed: bool) {
+    write_g1(w, &vk.alpha_g1, layout, compressed);
+    for p in [vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+        write_g2(w, &p, layout, compressed);
+    }
+    write_vec(w, &vk.ic, |w, p| write_g1(w, p, layout, compressed));
+}
+
+fn read_vk(rd: &mut Reader, layout: Layout, compressed: bool) -> Result<VerificationKey<Bls12>, DecodingError> {
+    Ok(VerificationKey {
+        alpha_g1: read_g1(rd, layout, compressed)?,
+        beta_g1: G1Affine::identity(),
+        beta_g2: read_g2(rd, layout, compressed)?,
+        gamma_g2: read_g2(rd, layout, compressed)?,
+        delta_g1: G1Affine::identity(),
+        delta_g2: read_g2(rd, layout, compressed)?,
+        ic: read_vec(rd, |rd| read_g1(rd, layout, compressed))?,
+    })
+}
+
+pub fn encode_vk(vk: &VerificationKey<Bls12>, layout: Layout, compressed: bool) -> Vec<u8> {
+    let mut w = Writer::new();
+    write_vk(&mut w, vk, layout, compressed);
+    w.finish()
+}
+
+pub fn decode_vk(bytes: &[u8], layout: Layout, compressed: bool) -> Result<VerificationKey<Bls12>, DecodingError> {
+    let mut rd = Reader::new(bytes);
+    let vk = read_vk(&mut rd, layout, compressed)?;
+    decode_end(&rd, vk)
+}
+
+// The proving key of arkworks for `params` of `qap`
+pub fn encode_parameters(params: &Parameters<Bls12>, qap: &QAP<bls12_381::Scalar>, layout: Layout, compressed: bool) -> Vec<u8> {
+    let num_vars = params.vk.ic.len() + params.l.len();
+    let g1 = |w: &mut Writer, p: &G1Affine| write_g1(w, p, layout, compressed);
+
+    let mut w = Writer::new();
+    write_vk(&mut w, &params.vk, layout, compressed);
+    g1(&mut w, &params.vk.beta_g1);
+    g1(&mut w, &params.vk.delta_g1);
+    write_vec(&mut w, &expand(&params.a_g1, &qap.a_constraints, num_vars), g1);
+    write_vec(&mut w, &expand(&params.b_g1, &qap.b_constraints, num_vars), g1);
+    write_vec(&mut w, &expand(&params.b_g2, &qap.b_constraints, num_vars), |w, p| write_g2(w, p, layout, compressed));
+    write_vec(&mut w, &params.h, g1);
+    write_vec(&mut w, &params.l, g1);
+    w.finish()
+}
+
+// `Parameters` for `qap` from a proving key of arkworks, whose A and B
+// queries have to be the identity outside of the variables of `qap`
+pub fn decode_parameters(bytes: &[u8], qap: &QAP<bls12_381::Scalar>, layout: Layout, compressed: bool) -> Result<Parameters<Bls12>, DecodingError> {
+    let mut rd = Reader::new(bytes);
+    let g1 = |rd: &mut Reader| read_g1(rd, layout, compressed);
+
+    let mut vk = read_vk(&mut rd, layout, compressed)?;
+    vk.beta_g1 = g1(&mut rd)?;
+    vk.delta_g1 = g1(&mut rd)?;
+    let a_g1 = read_vec(&mut rd, g1)?;
+    let b_g1 = read_vec(&mut rd, g1)?;
+    let b_g2 = read_vec(&mut rd, |rd| read_g2(rd, layout, compressed))?;
+    let h = read_vec(&mut rd, g1)?;
+    let l = read_vec(&mut rd, g1)?;
+
+    let num_vars = vk.ic.len() + l.len();
+    let out_of_range = [&qap.a_constraints, &qap.b_constraints].iter().any(|v| v.last().is_some_and(|i| *i >= num_vars));
+    if out_of_range || [a_g1.len(), b_g1.len(), b_g2.len()].iter().any(|n| *n != num_vars) {
+        return Err(DecodingError::Mismatch);
+    }
+    let params = Parameters {
+        vk,
+        h,
+        l,
+        a_g1: select(a_g1, &qap.a_constraints)?,
+        b_g1: select(b_g1, &qap.b_constraints)?,
+        b_g2: select(b_g2, &qap.b_constraints)?,
+    };
+    decode_end(&rd, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::Neg;
+
+    use bls12_381::Scalar;
+
+    use crate::prover::create_proof;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn arkworks_point_layout() {
+        let g = G1Affine::generator();
+        let mut w = Writer::new();
+        write_g1(&mut w, &g, Layout::Ark03, true);
+        let b = w.finish();
+        // x of the generator, little-endian, y < -y
+        let mut x = g.to_uncompressed()[..LEN].to_vec();
+        x.reverse();
+        assert_eq!(b, x);
+
+        let mut w = Writer::new();
+        write_g1(&mut w, &g.neg(), Layout::Ark03, true);
+        write_g1(&mut w, &G1Affine::identity(), Layout::Ark03, true);
+        write_g1(&mut w, &G1Affine::identity(), Layout::Ark03, false);
+        let b = w.finish();
+        assert_eq!(b[LEN - 1], 0x17 | NEGATIVE);
+        assert_eq!(&b[LEN..2 * LEN - 1], &[0; LEN - 1]);
+        assert_eq!(b[2 * LEN - 1], INFINITY);
+        assert_eq!(b[4 * LEN - 1], INFINITY);
+
+        let mut rd = Reader::new(&b);
+        assert_eq!(read_g1(&mut rd, Layout::Ark03, true), Ok(g.neg()));
+        assert_eq!(read_g1(&mut rd, Layout::Ark03, true), Ok(G1Affine::identity()));
+        assert_eq!(read_g1(&mut rd, Layout::Ark03, false), Ok(G1Affine::identity()));
+
+        for p in [G2Affine::generator(), G2Affine::generator().neg()] {
+            for compressed in [true, false] {
+                let mut w = Writer::new();
+                write_g2(&mut w, &p, Layout::Ark03, compressed);
+                let b = w.finish();
+                // c0 of x comes first
+                assert_eq!(b[..LEN].iter().rev().take(4).copied().collect::<Vec<_>>(), [0x02, 0x4a, 0xa2, 0xb2]);
+                assert_eq!(read_g2(&mut Reader::new(&b), Layout::Ark03, compressed), Ok(p));
+            }
+        }
+    }
+
+    // Written by ark-serialize 0.3 with ark-bls12-381 0.3: the generators
+    // of G1, their negations and the identity, the same for G2, compressed
+    // and then uncompressed. The proof and keys are of ark-groth16 0.3 for
+    // x * x = y with y = 9 public. The files in 0.4 are the same, written
+    // with ark-serialize 0.4.2, ark-bls12-381 0.4 and ark-groth16 0.4.
+    const POINTS: &[u8] = include_bytes!("testdata/arkworks/points.bin");
+    const PROOF: &[u8] = include_bytes!("testdata/arkworks/proof.bin");
+    const PROOF_UNCOMPRESSED: &[u8] = include_bytes!("testdata/arkworks/proof_uncompressed.bin");
+    const VK: &[u8] = include_bytes!("testdata/arkworks/vk.bin");
+    const VK_UNCOMPRESSED: &[u8] = include_bytes!("testdata/arkworks/vk_uncompressed.bin");
+    const PK: &[u8] = include_bytes!("testdata/arkworks/pk.bin");
+    const PK_UNCOMPRESSED: &[u8] = include_bytes!("testdata/arkworks/pk_uncompressed.bin");
+    const POINTS_04: &[u8] = include_bytes!("testdata/arkworks/0.4/points.bin");
+    const PROOF_04: &[u8] = include_bytes!("testdata/arkworks/0.4/proof.bin");
+    const PROOF_UNCOMPRESSED_04: &[u8] = include_bytes!("testdata/arkworks/0.4/proof_uncompressed.bin");
+    const VK_04: &[u8] = include_bytes!("testdata/arkworks/0.4/vk.bin");
+    const VK_UNCOMPRESSED_04: &[u8] = include_bytes!("testdata/arkworks/0.4/vk_uncompressed.bin");
+    const PK_04: &[u8] = include_bytes!("testdata/arkworks/0.4/pk.bin");
+    const PK_UNCOMPRESSED_04: &[u8] = include_bytes!("testdata/arkworks/0.4/pk_uncompressed.bin");
+
+    // x * x = y over the variables one, y and x. ark-relations appends a
+    // constraint with A = v for each input v, so B is zero for them.
+    fn square_qap() -> QAP<Scalar> {
+        let one = Scalar::one();
+        QAP {
+            a: vec![(0, vec![(one, 1)]), (1, vec![(one, 2)]), (2, vec![(one, 0)])],
+            b: vec![(2, vec![(one, 0)])],
+            c: vec![(1, vec![(one, 0)])],
+            a_constraints: vec![0, 1, 2],
+            b_constraints: vec![2],
+        }
+    }
+
+    // `files` are the proof, verifying key and proving key, compressed and
+    // then uncompressed
+    fn check_reference_bytes(layout: Layout, points: &[u8], files: [[&[u8]; 3]; 2]) {
+        let g1 = [G1Affine::generator(), G1Affine::generator().neg(), G1Affine::identity()];
+        let g2 = [G2Affine::generator(), G2Affine::generator().neg(), G2Affine::identity()];
+        let mut w = Writer::new();
+        for compressed in [true, false] {
+            g1.iter().for_each(|p| write_g1(&mut w, p, layout, compressed));
+            g2.iter().for_each(|p| write_g2(&mut w, p, layout, compressed));
+        }
+        assert_eq!(w.finish(), points);
+
+        let mut rd = Reader::new(points);
+        for compressed in [true, false] {
+            for p in g1.iter() {
+                assert_eq!(read_g1(&mut rd, layout, compressed).as_ref(), Ok(p));
+            }
+            for p in g2.iter() {
+                assert_eq!(read_g2(&mut rd, layout, compressed).as_ref(), Ok(p));
+            }
+        }
+
+        let qap = square_qap();
+        for ([proof, vk, pk], compressed) in files.into_iter().zip([true, false]) {
+            let decoded = decode_proof(proof, layout, compressed).unwrap();
+            let key = decode_vk(vk, layout, compressed).unwrap();
+            assert_eq!(encode_proof(&decoded, layout, compressed), proof);
+            assert_eq!(encode_vk(&key, layout, compressed), vk);
+            assert!(verify_proof(&decoded, &[Scalar::from(9)], key.clone()).is_ok());
+            assert!(verify_proof(&decoded, &[Scalar::from(4)], key).is_err());
+
+            // The B queries of the proving key hold the identity for one and y
+            let params = decode_parameters(pk, &qap, layout, compressed).unwrap();
+            assert_eq!(encode_parameters(&params, &qap, layout, compressed), pk);
+            assert_eq!(encode_vk(&params.vk, layout, compressed), vk);
+            let (inputs, aux) = ([Scalar::one(), Scalar::from(9)], [Scalar::from(3)]);
+            let proof = create_proof::<Bls12, _>(&params, &inputs, &aux, Scalar::from(5), Scalar::from(7), &qap, 3).unwrap();
+            assert!(verify_proof(&proof, &inputs[1..], params.vk.clone()).is_ok());
+        }
+    }
+
+    #[test]
+    fn arkworks_reference_bytes() {
+        check_reference_bytes(Layout::Ark03, POINTS, [
+            [PROOF, VK, PK],
+            [PROOF_UNCOMPRESSED, VK_UNCOMPRESSED, PK_UNCOMPRESSED],
+        ]);
+
+        // Uncompressed points carry no sign, and the identity only x = 0, y = 1
+        let offset = 3 * LEN + 6 * LEN;
+        let mut bad = POINTS.to_vec();
+        bad[offset + 2 * LEN - 1] |= NEGATIVE;
+        assert_eq!(read_g1(&mut Reader::new(&bad[offset..]), Layout::Ark03, false), Err(DecodingError::InvalidPoint));
+        let mut bad = POINTS.to_vec();
+        bad[offset + 5 * LEN] = 0;
+        assert_eq!(read_g1(&mut Reader::new(&bad[offset + 4 * LEN..]), Layout::Ark03, false), Err(DecodingError::InvalidPoint));
+        let mut bad = POINTS.to_vec();
+        bad[2 * LEN] = 1;
+        assert_eq!(read_g1(&mut Reader::new(&bad[2 * LEN..]), Layout::Ark03, true), Err(DecodingError::InvalidPoint));
+    }
+
+    #[test]
+    fn arkworks_04_reference_bytes() {
+        check_reference_bytes(Layout::Ark04, POINTS_04, [
+            [PROOF_04, VK_04, PK_04],
+            [PROOF_UNCOMPRESSED_04, VK_UNCOMPRESSED_04, PK_UNCOMPRESSED_04],
+        ]);
+
+        // The layouts differ in every point, but not in the lengths
+        assert_eq!(POINTS_04.len(), POINTS.len());
+        assert_ne!(&POINTS_04[..LEN], &POINTS[..LEN]);
+        assert!(decode_proof(PROOF_04, Layout::Ark03, true).is_err());
+    }
+
+    #[test]
+    fn arkworks_roundtrip_verifies() {
+        let fixture = cube_fixture(3);
+        let proof = fixture.prove();
+        for (layout, compressed) in [(Layout::Ark03, true), (Layout::Ark03, false), (Layout::Ark04, true), (Layout::Ark04, false)] {
+            let bytes = encode_proof(&proof, layout, compressed);
+            assert_eq!(bytes.len(), if compressed { 4 * LEN } else { 8 * LEN });
+            let decoded = decode_proof(&bytes, layout, compressed).unwrap();
+
+            let vk = decode_vk(&encode_vk(&fixture.params.vk, layout, compressed), layout, compressed).unwrap();
+            assert_eq!(vk.ic, fixture.params.vk.ic);
+            assert!(verify_proof(&decoded, fixture.public_inputs(), vk).is_ok());
+
+            let bytes = encode_parameters(&fixture.params, &fixture.qap, layout, compressed);
+            let params = decode_parameters(&bytes, &fixture.qap, layout, compressed).unwrap();
+            assert_eq!(params.a_g1, fixture.params.a_g1);
+            assert_eq!(params.b_g2, fixture.params.b_g2);
+            assert_eq!(params.vk.delta_g1, fixture.params.vk.delta_g1);
+            let proof = create_proof::<Bls12, _>(&params, &fixture.inputs, &fixture.aux, Scalar::from(5), Scalar::from(7), &fixture.qap, fixture.num_constraints).unwrap();
+            assert!(verify_proof(&proof, fixture.public_inputs(), params.vk.clone()).is_ok());
+        }
+    }
+
+    #[test]
+    fn arkworks_rejects_bad_bytes() {
+        let fixture = cube_fixture(3);
+        let bytes = encode_proof(&fixture.prove(), Layout::Ark03, true);
+        assert_eq!(decode_proof(&bytes[..bytes.len() - 1], Layout::Ark03, true).err(), Some(DecodingError::UnexpectedEof));
+        assert_eq!(decode_proof(&[&bytes[..], &[0]].concat(), Layout::Ark03, true).err(), Some(DecodingError::InvalidHeader));
+        for flags in [LARGEST, NEGATIVE | INFINITY] {
+            let mut bad = bytes.clone();
+            bad[LEN - 1] |= flags;
+            assert_eq!(decode_proof(&bad, Layout::Ark03, true).err(), Some(DecodingError::InvalidPoint));
+        }
+
+        // A variable outside of the QAP with a point in the A query
+        let mut qap = fixture.qap.clone();
+        let unused = qap.a_constraints.remove(0);
+        qap.a.retain(|(i, _)| *i != unused);
+        let bytes = encode_parameters(&fixture.params, &fixture.qap, Layout::Ark03, false);
+        assert_eq!(decode_parameters(&bytes, &qap, Layout::Ark03, false).err(), Some(DecodingError::Mismatch));
+    }
+}
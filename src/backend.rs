@@ -0,0 +1,233 @@
+// Curve and field arithmetic behind the prover and the verifier, as an
+// extension point for hardware accelerators.
+//
+// A `Backend` provides scalar multiplication and MSMs in G1 and G2, the
+// radix-2 FFT over `Fr` and the product of pairings. Every method has a
+// pure-software default on top of the `Engine`, which `Software` uses
+// unchanged, so an implementation only overrides what its hardware speeds
+// up. `prover::create_proof_with_backend`,
+// `verifier::verify_proof_with_backend` and the `_with_backend` variants of
+// the batch, streaming, split and task provers dispatch to a backend; the
+// plain functions use `Software`.
+//
+// The prover hands MSMs to the backend in batches of at most `MSM_BATCH`
+// points, so that the scalars fit in a buffer on the stack.
+
+use core::ops::{AddAssign, Mul};
+
+use pairing::Engine;
+use pairing::group::Group;
+
+use crate::poly;
+
+// Largest number of points in one call to `msm_g1` or `msm_g2` from the prover
+pub const MSM_BATCH: usize = 64;
+
+pub trait Backend<E: Engine> {
+    fn mul_g1(&self, p: E::G1, s: &E::Fr) -> E::G1 {
+        p.mul(s)
+    }
+
+    fn mul_g2(&self, p: E::G2, s: &E::Fr) -> E::G2 {
+        p.mul(s)
+    }
+
+    // Sum of bases[i] * scalars[i], both of the same length
+    fn msm_g1(&self, bases: &[E::G1Affine], scalars: &[E::Fr]) -> E::G1 {
+        assert_eq!(bases.len(), scalars.len());
+        let mut acc = E::G1::identity();
+        for (p, s) in bases.iter().zip(scalars.iter()) {
+            acc.add_assign(self.mul_g1((*p).into(), s));
+        }
+        acc
+    }
+
+    fn msm_g2(&self, bases: &[E::G2Affine], scalars: &[E::Fr]) -> E::G2 {
+        assert_eq!(bases.len(), scalars.len());
+        let mut acc = E::G2::identity();
+        for (p, s) in bases.iter().zip(scalars.iter()) {
+            acc.add_assign(self.mul_g2((*p).into(), s));
+        }
+        acc
+    }
+
+    // In-place FFT of `a`, of length 2^exp, where omega is a primitive
+    // 2^exp-th root of unity
+    fn fft(&self, a: &mut [E::Fr], omega: &E::Fr, exp: u32) {
+        poly::fft(a, omega, exp)
+    }
+
+    // Product of the pairings of each pair, written additively as `E::Gt` is
+    fn pairing_product(&self, terms: &[(E::G1Affine, E::G2Affine)]) -> E::Gt {
+        let mut acc = E::Gt::identity();
+        for (p, q) in terms {
+            acc.add_assign(E::pairing(p, q));
+        }
+        acc
+    }
+}
+
+// The Engine's own arithmetic, with whatever timing guarantees it comes with
+pub struct Software;
+
+impl<E: Engine> Backend<E> for Software {}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use bls12_381::{ Bls12, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::batch::{ create_proofs_batch_with_backend, Witness };
+    use crate::prover::{ create_proof, create_proof_with_backend };
+    use crate::split::{ assemble_with_backend, evaluate, quotient_with_backend, run_msm_with_backend, MsmRequest };
+    use crate::task::ProverTask;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof_with_backend;
+    use crate::witness::create_proof_streaming_with_backend;
+    use super::*;
+
+    // Counts the calls it receives and forwards them to `Software`
+    #[derive(Default)]
+    struct Mock {
+        mul: Cell<usize>,
+        msm_g1: Cell<usize>,
+        msm_g2: Cell<usize>,
+        fft: Cell<usize>,
+        pairings: Cell<usize>,
+    }
+
+    impl Backend<Bls12> for Mock {
+        fn mul_g1(&self, p: bls12_381::G1Projective, s: &Scalar) -> bls12_381::G1Projective {
+            self.mul.set(self.mul.get() + 1);
+            Backend::<Bls12>::mul_g1(&Software, p, s)
+        }
+
+        fn mul_g2(&self, p: bls12_381::G2Projective, s: &Scalar) -> bls12_381::G2Projective {
+            self.mul.set(self.mul.get() + 1);
+            Backend::<Bls12>::mul_g2(&Software, p, s)
+        }
+
+        fn msm_g1(&self, bases: &[bls12_381::G1Affine], scalars: &[Scalar]) -> bls12_381::G1Projective {
+            self.msm_g1.set(self.msm_g1.get() + 1);
+            assert!(bases.len() <= MSM_BATCH);
+            Backend::<Bls12>::msm_g1(&Software, bases, scalars)
+        }
+
+        fn msm_g2(&self, bases: &[bls12_381::G2Affine], scalars: &[Scalar]) -> bls12_381::G2Projective {
+            self.msm_g2.set(self.msm_g2.get() + 1);
+            Backend::<Bls12>::msm_g2(&Software, bases, scalars)
+        }
+
+        fn fft(&self, a: &mut [Scalar], omega: &Scalar, exp: u32) {
+            self.fft.set(self.fft.get() + 1);
+            Backend::<Bls12>::fft(&Software, a, omega, exp)
+        }
+
+        fn pairing_product(&self, terms: &[(bls12_381::G1Affine, bls12_381::G2Affine)]) -> bls12_381::Gt {
+            self.pairings.set(self.pairings.get() + terms.len());
+            Backend::<Bls12>::pairing_product(&Software, terms)
+        }
+    }
+
+    #[test]
+    fn mock_backend_dispatch() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let mock = Mock::default();
+
+        let proof = create_proof_with_backend::<Bls12, _, _>(
+            &mock,
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        let expected = create_proof::<Bls12, _>(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+
+        // H, L, A and B in G1, B in G2, and the FFTs of the quotient
+        assert!(mock.msm_g1.get() >= 4);
+        assert_eq!(mock.msm_g2.get(), 1);
+        assert_eq!(mock.fft.get(), 7);
+
+        let vk = &fixture.params.vk;
+        assert!(verify_proof_with_backend(&mock, &proof, fixture.public_inputs(), vk).is_ok());
+        assert_eq!(mock.pairings.get(), 4);
+        assert!(verify_proof_with_backend(&mock, &proof, &[Scalar::one()], vk).is_err());
+    }
+
+    #[test]
+    fn other_provers_dispatch() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let (params, qap, inputs, aux) = (&fixture.params, &fixture.qap, &fixture.inputs, &fixture.aux);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let expected = create_proof::<Bls12, _>(params, inputs, aux, r, s, qap, fixture.num_constraints).unwrap();
+        let check = |proof: crate::Proof<Bls12>| {
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+        };
+
+        let mock = Mock::default();
+        let witnesses = [Witness { inputs, aux, r, s }; 2];
+        let proofs = create_proofs_batch_with_backend::<Bls12, _, _>(&mock, params, &witnesses, qap, fixture.num_constraints).unwrap();
+        proofs.into_iter().for_each(check);
+        assert!(mock.msm_g1.get() >= 8);
+        assert_eq!(mock.msm_g2.get(), 2);
+        assert_eq!(mock.fft.get(), 14);
+
+        let mock = Mock::default();
+        let witness: Vec<Scalar> = inputs.iter().chain(aux.iter()).copied().collect();
+        check(create_proof_streaming_with_backend::<Bls12, _, _, _>(&mock, &mut &*params, &witness[..], r, s, qap).unwrap());
+        assert!(mock.msm_g1.get() >= 1);
+        assert_eq!(mock.fft.get(), 7);
+
+        let mock = Mock::default();
+        let quotient = quotient_with_backend::<Bls12, _>(&mock, evaluate(qap, inputs, aux).unwrap());
+        let requests = [
+            MsmRequest::h(&quotient),
+            MsmRequest::l(aux),
+            MsmRequest::a(qap, inputs, aux),
+            MsmRequest::b_g1(qap, inputs, aux),
+            MsmRequest::b_g2(qap, inputs, aux),
+        ];
+        let results: Vec<_> = requests.iter()
+            .map(|x| run_msm_with_backend::<Bls12, _, _>(&mock, &mut &*params, x).unwrap())
+            .collect();
+        check(assemble_with_backend(&mock, &params.vk, &results, r, s).unwrap());
+        assert_eq!(mock.fft.get(), 7);
+        assert!(mock.msm_g1.get() >= 4);
+        assert_eq!(mock.msm_g2.get(), 1);
+        assert!(mock.mul.get() > 0);
+
+        let mock = Mock::default();
+        let mut task = ProverTask::with_backend(&mock, params, inputs, aux, r, s, qap, fixture.num_constraints).unwrap();
+        let proof = loop {
+            if let core::task::Poll::Ready(proof) = task.step(usize::MAX) {
+                break proof;
+            }
+        };
+        check(proof);
+        assert!(mock.mul.get() >= aux.len());
+    }
+}
@@ -0,0 +1,326 @@
+// Proving many witnesses for the same circuit in one call.
+//
+// `create_proofs_batch` computes the same proofs as calling
+// `prover::create_proof` once per witness, but shares the work that only
+// depends on the circuit and the key:
+// - the QAP is walked once per polynomial, each term updating the
+//   evaluations of every witness
+// - each query point is read from the source once and decoded once, and
+//   while a chunk of points is in memory it goes to the backend's MSM once
+//   per witness
+//
+// Witnesses are proved in groups of at most `GROUP`, each group reading
+// the queries once, so that memory does not grow with the batch: three
+// evaluation vectors of the domain size per witness of a group.
+
+use core::convert::Infallible;
+use core::ops::{AddAssign, MulAssign};
+
+use ff::{Field, PrimeField};
+use pairing::Engine;
+use pairing::group::Group;
+
+use crate::Proof;
+use crate::poly::{ coset_mul_assign_with, fft_params };
+use crate::backend::{ Backend, Software, MSM_BATCH };
+use crate::prover::{ assemble, divide_by_z, for_each_g1, for_each_g2, ProvingError };
+use crate::source::{ ParameterSource, Query };
+use crate::view::{ Constraints, Poly };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::wipe_slice;
+
+// Largest number of witnesses proved together
+const GROUP: usize = 8;
+
+// One assignment to prove, with its blinding factors
+#[derive(Clone, Copy)]
+pub struct Witness<'a, S: PrimeField> {
+    pub inputs: &'a [S],
+    pub aux: &'a [S],
+    pub r: S,
+    pub s: S,
+}
+
+impl<'a, S: PrimeField> Witness<'a, S> {
+    fn get(&self, i: usize) -> S {
+        if i < self.inputs.len() { self.inputs[i] } else { self.aux[i - self.inputs.len()] }
+    }
+}
+
+// Sums of p_i * scalar(w, i) over a whole query, for every witness w
+fn msm_g1<E, B, P>(
+    backend: &B,
+    source: &mut P,
+    query: Query,
+    k: usize,
+    scalar: impl Fn(usize, usize) -> E::Fr
+) -> Result<Vec<E::G1>, P::Error>
+where
+    E: Engine,
+    B: Backend<E>,
+    P: ParameterSource<E>,
+{
+    let mut acc = vec![E::G1::identity(); k];
+
+    if let Some(tables) = source.fixed_base().filter(|t| t.covers(query)) {
+        for i in 0..source.len(query) {
+            for (w, acc) in acc.iter_mut().enumerate() {
+                acc.add_assign(tables.mul_query(query, i, &scalar(w, i)));
+            }
+        }
+        return Ok(acc);
+    }
+
+    let mut scalars = [E::Fr::zero(); MSM_BATCH];
+    for_each_g1(source, query, |i, bases| {
+        for (w, acc) in acc.iter_mut().enumerate() {
+            for (j, x) in scalars[..bases.len()].iter_mut().enumerate() {
+                *x = scalar(w, i + j);
+            }
+            acc.add_assign(backend.msm_g1(bases, &scalars[..bases.len()]));
+        }
+    })?;
+
+    #[cfg(any(test, feature = "zeroize"))]
+    wipe_slice(&mut scalars, E::Fr::zero());
+
+    Ok(acc)
+}
+
+fn msm_g2<E, B, P>(backend: &B, source: &mut P, k: usize, scalar: impl Fn(usize, usize) -> E::Fr) -> Result<Vec<E::G2>, P::Error>
+where
+    E: Engine,
+    B: Backend<E>,
+    P: ParameterSource<E>,
+{
+    let mut acc = vec![E::G2::identity(); k];
+    let mut scalars = [E::Fr::zero(); MSM_BATCH];
+    for_each_g2(source, |i, bases| {
+        for (w, acc) in acc.iter_mut().enumerate() {
+            for (j, x) in scalars[..bases.len()].iter_mut().enumerate() {
+                *x = scalar(w, i + j);
+            }
+            acc.add_assign(backend.msm_g2(bases, &scalars[..bases.len()]));
+        }
+    })?;
+
+    #[cfg(any(test, feature = "zeroize"))]
+    wipe_slice(&mut scalars, E::Fr::zero());
+
+    Ok(acc)
+}
+
+// Proves every witness against the same parameters and QAP. The proofs
+// are returned in the order of `witnesses`. `Mismatch` if the lengths of
+// the queries, the QAP and the assignments disagree.
+pub fn create_proofs_batch<E: Engine>(
+    params: impl ParameterSource<E, Error = Infallible>,
+    witnesses: &[Witness<E::Fr>],
+    qap: impl Constraints<E::Fr>,
+    num_constraints: usize
+) -> Result<Vec<Proof<E>>, ProvingError<Infallible>>
+{
+    create_proofs_batch_with_backend(&Software, params, witnesses, qap, num_constraints)
+}
+
+// Same as `create_proofs_batch`, with the MSMs, FFTs and scalar
+// multiplications performed by `backend`
+pub fn create_proofs_batch_with_backend<E: Engine, B: Backend<E>, P: ParameterSource<E>>(
+    backend: &B,
+    mut params: P,
+    witnesses: &[Witness<E::Fr>],
+    qap: impl Constraints<E::Fr>,
+    num_constraints: usize
+) -> Result<Vec<Proof<E>>, ProvingError<P::Error>>
+{
+    let mut proofs = Vec::with_capacity(witnesses.len());
+    for group in witnesses.chunks(GROUP) {
+        proofs.extend(prove_batch(backend, &mut params, group, &qap, num_constraints)?);
+    }
+    Ok(proofs)
+}
+
+#[allow(unused_mut)]
+fn prove_batch<E: Engine, B: Backend<E>, P: ParameterSource<E>>(
+    backend: &B,
+    source: &mut P,
+    witnesses: &[Witness<E::Fr>],
+    qap: &impl Constraints<E::Fr>,
+    num_constraints: usize
+) -> Result<Vec<Proof<E>>, ProvingError<P::Error>>
+{
+    let k = witnesses.len();
+    let (omega, m, exp): (E::Fr, usize, u32) = fft_params(num_constraints).ok_or(ProvingError::DomainTooLarge)?;
+
+    // h(X) has degree m - 2, the top coefficient is zero
+    if source.len(Query::H) < m - 1
+        || qap.num_constraints() > m
+        || source.len(Query::A) != qap.num_variables(Poly::A)
+        || source.len(Query::BG1) != qap.num_variables(Poly::B)
+        || source.len(Query::BG2) != qap.num_variables(Poly::B)
+        || witnesses.iter().any(|w| w.inputs.len() != source.vk().ic.len() || w.aux.len() != source.len(Query::L))
+    {
+        return Err(ProvingError::Mismatch);
+    }
+
+    let eval = |output: &mut [E::Fr], poly: Poly| {
+        qap.for_each_term(poly, &mut |i, x, u| {
+            for (w, witness) in witnesses.iter().enumerate() {
+                let mut t = x;
+                t.mul_assign(witness.get(i));
+                output[w * m + u].add_assign(t);
+            }
+        });
+    };
+
+    let mut at = vec![E::Fr::zero(); k * m];
+    let mut bt = vec![E::Fr::zero(); k * m];
+    let mut ct = vec![E::Fr::zero(); k * m];
+    eval(&mut at, Poly::A);
+    eval(&mut bt, Poly::B);
+    eval(&mut ct, Poly::C);
+
+    let fft = |a: &mut [E::Fr], omega: &E::Fr, exp| backend.fft(a, omega, exp);
+    for ((a, b), c) in at.chunks_mut(m).zip(bt.chunks_mut(m)).zip(ct.chunks_mut(m)) {
+        coset_mul_assign_with(a, b, &fft);
+        divide_by_z(a, c, &omega, exp, &fft);
+    }
+
+    let mut h = msm_g1(backend, source, Query::H, k, |w, i| if i < m - 1 { at[w * m + i] } else { E::Fr::zero() })?;
+
+    #[cfg(any(test, feature = "zeroize"))]
+    for v in [&mut at, &mut bt, &mut ct] {
+        wipe_slice(v, E::Fr::zero());
+    }
+
+    let mut l = msm_g1(backend, source, Query::L, k, |w, i| witnesses[w].aux[i])?;
+
+    let mut a_g1 = msm_g1(backend, source, Query::A, k, |w, i| witnesses[w].get(qap.variable(Poly::A, i)))?;
+
+    let mut b_g1 = msm_g1(backend, source, Query::BG1, k, |w, i| witnesses[w].get(qap.variable(Poly::B, i)))?;
+
+    let mut b_g2 = msm_g2(backend, source, k, |w, i| witnesses[w].get(qap.variable(Poly::B, i)))?;
+
+    let proofs = witnesses.iter().enumerate()
+        .map(|(w, witness)| assemble(
+            backend,
+            source.vk(),
+            source.fixed_base(),
+            h[w],
+            l[w],
+            a_g1[w],
+            b_g1[w],
+            b_g2[w],
+            &witness.r,
+            &witness.s,
+        ))
+        .collect();
+
+    #[cfg(any(test, feature = "zeroize"))]
+    {
+        for v in [&mut h, &mut l, &mut a_g1, &mut b_g1] {
+            wipe_slice(v, E::G1::identity());
+        }
+        wipe_slice(&mut b_g2, E::G2::identity());
+    }
+
+    Ok(proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+    use rand::thread_rng;
+
+    use crate::fixed::PrecomputedParameters;
+    use crate::prover::create_proof;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn batch_matches_single_proofs() {
+        let mut rng = thread_rng();
+        let fixtures: Vec<_> = [3, 5, 7].into_iter().map(cube_fixture).collect();
+        // Same circuit for every witness, with the key of the first fixture
+        let params = &fixtures[0].params;
+        let qap = &fixtures[0].qap;
+        let num_constraints = fixtures[0].num_constraints;
+
+        let witnesses: Vec<_> = fixtures.iter()
+            .map(|f| Witness {
+                inputs: &f.inputs,
+                aux: &f.aux,
+                r: Scalar::random(&mut rng),
+                s: Scalar::random(&mut rng),
+            })
+            .collect();
+
+        let proofs = create_proofs_batch::<Bls12>(params, &witnesses, qap, num_constraints).unwrap();
+        let precomputed = PrecomputedParameters::new(params.clone(), 3);
+        let precomputed_proofs = create_proofs_batch::<Bls12>(&precomputed, &witnesses, qap, num_constraints).unwrap();
+        assert_eq!(proofs.len(), witnesses.len());
+
+        for ((proof, other), (witness, fixture)) in proofs.iter().zip(precomputed_proofs.iter()).zip(witnesses.iter().zip(fixtures.iter())) {
+            let expected = create_proof::<Bls12, _>(
+                params,
+                witness.inputs,
+                witness.aux,
+                witness.r,
+                witness.s,
+                qap,
+                num_constraints,
+            ).unwrap();
+            for p in [proof, other] {
+                assert_eq!(p.a, expected.a);
+                assert_eq!(p.b, expected.b);
+                assert_eq!(p.c, expected.c);
+            }
+            assert!(verify_proof(proof, fixture.public_inputs(), params.vk.clone()).is_ok());
+        }
+
+        assert!(create_proofs_batch::<Bls12>(params, &[], qap, num_constraints).unwrap().is_empty());
+    }
+
+    #[test]
+    fn batch_spans_groups() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let (params, qap, n) = (&fixture.params, &fixture.qap, fixture.num_constraints);
+        let witnesses: Vec<_> = (0..GROUP + 1)
+            .map(|_| Witness { inputs: &fixture.inputs, aux: &fixture.aux, r: Scalar::random(&mut rng), s: Scalar::random(&mut rng) })
+            .collect();
+
+        let proofs = create_proofs_batch::<Bls12>(params, &witnesses, qap, n).unwrap();
+        assert_eq!(proofs.len(), witnesses.len());
+        for (proof, witness) in proofs.iter().zip(witnesses.iter()).skip(GROUP - 1) {
+            let expected = create_proof::<Bls12, _>(params, witness.inputs, witness.aux, witness.r, witness.s, qap, n).unwrap();
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+        }
+    }
+
+    #[test]
+    fn batch_mismatch() {
+        let fixture = cube_fixture(3);
+        let (qap, n) = (&fixture.qap, fixture.num_constraints);
+        let one = Scalar::one();
+        let witness = Witness { inputs: &fixture.inputs, aux: &fixture.aux, r: one, s: one };
+        let short = Witness { aux: &fixture.aux[1..], ..witness };
+
+        let prove = |params, witnesses: &[_]| create_proofs_batch::<Bls12>(params, witnesses, qap, n).err();
+        assert_eq!(prove(&fixture.params, &[witness, short]), Some(ProvingError::Mismatch));
+
+        let mut params = fixture.params.clone();
+        params.h.truncate(1);
+        assert_eq!(prove(&params, &[witness]), Some(ProvingError::Mismatch));
+        let mut params = fixture.params.clone();
+        params.b_g2.pop();
+        assert_eq!(prove(&params, &[witness]), Some(ProvingError::Mismatch));
+    }
+}
@@ -0,0 +1,707 @@
+// G1: y^2 = x^3 + 3 over Fq, and G2: y^2 = x^3 + 3 / xi over Fq2, the
+// sextic twist that the pairing maps back into Fq12.
+//
+// Projective points use the complete formulas of Renes, Costello and
+// Batina (https://eprint.iacr.org/2015/1060.pdf, algorithms 7 and 9), so
+// additions involving the identity or equal points take the same path as
+// any other. G1 has cofactor one. G2 does not, so decoding checks that
+// r times the point is the identity.
+//
+// Points are encoded as gnark does: big-endian coordinates, with G2
+// coordinates as c1 | c0. A compressed point is x, with 0b10 in the top
+// two bits of the first byte when y is the smaller of y and -y, 0b11 when
+// it is the larger, and 0b01 with all other bits clear for the identity.
+// An uncompressed point is x | y with those two bits clear, and the
+// identity is all zeros.
+
+use core::fmt;
+use core::iter::Sum;
+use core::ops::{ Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+
+use ff::{ Field, PrimeField };
+use pairing::group::{ Curve, Group, GroupEncoding, UncompressedEncoding };
+// Wnaf needs the allocations that group only enables for bellman
+#[cfg(any(test, feature = "std"))]
+use pairing::group::WnafGroup;
+use pairing::group::prime::{ PrimeCurve, PrimeCurveAffine, PrimeGroup };
+use rand_core::RngCore;
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq, CtOption };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::vec::Vec;
+
+use crate::DecodingError;
+use crate::source::{ decode_each, DecodePoints };
+use super::Coordinate;
+use super::fp::{ Fq, Fr };
+use super::fp2::Fq2;
+
+const FLAGS: u8 = 0b11 << 6;
+const COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const COMPRESSED_LARGEST: u8 = 0b11 << 6;
+const COMPRESSED_INFINITY: u8 = 0b01 << 6;
+
+// b of G2, 3 / xi
+const G2_B: Fq2 = Fq2::new(
+    Fq::from_raw([0x3267e6dc24a138e5, 0xb5b4c5e559dbefa3, 0x81be18991be06ac3, 0x2b149d40ceb8aaae]),
+    Fq::from_raw([0xe4a2bd0685c315d2, 0xa74fa084e52d1852, 0xcd2cafadeed8fdf4, 0x009713b03af0fed4]),
+);
+
+macro_rules! encoding {
+    ($name:ident, $len:expr) => {
+        #[derive(Clone, Copy)]
+        pub struct $name([u8; $len]);
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name([0; $len])
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsMut<[u8]> for $name {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0[..].fmt(f)
+            }
+        }
+    };
+}
+
+encoding!(G1Compressed, 32);
+encoding!(G1Uncompressed, 64);
+encoding!(G2Compressed, 64);
+encoding!(G2Uncompressed, 128);
+
+macro_rules! curve {
+    (
+        $affine:ident, $projective:ident, $base:ty,
+        $compressed:ident, $uncompressed:ident,
+        $b:expr, $generator:expr, $torsion_free:expr
+    ) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $affine {
+            pub(crate) x: $base,
+            pub(crate) y: $base,
+            pub(crate) infinity: Choice,
+        }
+
+        #[derive(Clone, Copy, Debug)]
+        pub struct $projective {
+            x: $base,
+            y: $base,
+            z: $base,
+        }
+
+        impl $affine {
+            pub fn identity() -> Self {
+                $affine { x: <$base>::zero(), y: <$base>::one(), infinity: Choice::from(1) }
+            }
+
+            pub fn generator() -> Self {
+                let (x, y) = $generator;
+                $affine { x, y, infinity: Choice::from(0) }
+            }
+
+            pub fn is_identity(&self) -> Choice {
+                self.infinity
+            }
+
+            pub fn is_on_curve(&self) -> Choice {
+                (self.y.square() - self.x.square() * self.x).ct_eq(&$b) | self.infinity
+            }
+
+            pub fn is_torsion_free(&self) -> Choice {
+                $torsion_free(&$projective::from(*self))
+            }
+
+            pub fn to_compressed(&self) -> $compressed {
+                let mut out = $compressed::default();
+                let x = <$base>::conditional_select(&self.x, &<$base>::zero(), self.infinity);
+                x.write_be(&mut out.0);
+                let sign = u8::conditional_select(&COMPRESSED_SMALLEST, &COMPRESSED_LARGEST, self.y.lexicographically_largest());
+                out.0[0] |= u8::conditional_select(&sign, &COMPRESSED_INFINITY, self.infinity);
+                out
+            }
+
+            pub fn to_uncompressed(&self) -> $uncompressed {
+                let mut out = $uncompressed::default();
+                let zero = <$base>::zero();
+                let x = <$base>::conditional_select(&self.x, &zero, self.infinity);
+                let y = <$base>::conditional_select(&self.y, &zero, self.infinity);
+                x.write_be(&mut out.0[..<$base>::LEN]);
+                y.write_be(&mut out.0[<$base>::LEN..]);
+                out
+            }
+
+            pub fn from_compressed_unchecked(bytes: &$compressed) -> CtOption<Self> {
+                let flags = bytes.0[0] & FLAGS;
+                let mut b = bytes.0;
+                b[0] &= !FLAGS;
+                if flags == COMPRESSED_INFINITY {
+                    let zero = b.iter().all(|x| *x == 0);
+                    return CtOption::new(Self::identity(), Choice::from(zero as u8));
+                }
+                if flags != COMPRESSED_SMALLEST && flags != COMPRESSED_LARGEST {
+                    return CtOption::new(Self::identity(), Choice::from(0));
+                }
+                <$base>::read_be(&b).and_then(|x| {
+                    (x.square() * x + $b).sqrt().map(|y| {
+                        let flip = y.lexicographically_largest() ^ Choice::from((flags == COMPRESSED_LARGEST) as u8);
+                        $affine { x, y: <$base>::conditional_select(&y, &-y, flip), infinity: Choice::from(0) }
+                    })
+                })
+            }
+
+            pub fn from_compressed(bytes: &$compressed) -> CtOption<Self> {
+                Self::from_compressed_unchecked(bytes).and_then(|p| CtOption::new(p, p.is_torsion_free()))
+            }
+
+            pub fn from_uncompressed_unchecked(bytes: &$uncompressed) -> CtOption<Self> {
+                if bytes.0.iter().all(|x| *x == 0) {
+                    return CtOption::new(Self::identity(), Choice::from(1));
+                }
+                let flags = Choice::from((bytes.0[0] & FLAGS == 0) as u8);
+                let x = <$base>::read_be(&bytes.0[..<$base>::LEN]);
+                let y = <$base>::read_be(&bytes.0[<$base>::LEN..]);
+                x.and_then(|x| y.and_then(|y| CtOption::new($affine { x, y, infinity: Choice::from(0) }, flags)))
+            }
+
+            pub fn from_uncompressed(bytes: &$uncompressed) -> CtOption<Self> {
+                Self::from_uncompressed_unchecked(bytes)
+                    .and_then(|p| CtOption::new(p, p.is_on_curve() & p.is_torsion_free()))
+            }
+        }
+
+        impl Default for $affine {
+            fn default() -> Self {
+                Self::identity()
+            }
+        }
+
+        impl ConstantTimeEq for $affine {
+            fn ct_eq(&self, other: &Self) -> Choice {
+                (self.infinity & other.infinity)
+                    | (!self.infinity & !other.infinity & self.x.ct_eq(&other.x) & self.y.ct_eq(&other.y))
+            }
+        }
+
+        impl PartialEq for $affine {
+            fn eq(&self, other: &Self) -> bool {
+                self.ct_eq(other).into()
+            }
+        }
+
+        impl Eq for $affine {}
+
+        impl ConditionallySelectable for $affine {
+            fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+                $affine {
+                    x: <$base>::conditional_select(&a.x, &b.x, choice),
+                    y: <$base>::conditional_select(&a.y, &b.y, choice),
+                    infinity: Choice::from(u8::conditional_select(&a.infinity.unwrap_u8(), &b.infinity.unwrap_u8(), choice)),
+                }
+            }
+        }
+
+        impl Neg for &$affine {
+            type Output = $affine;
+
+            fn neg(self) -> $affine {
+                $affine {
+                    x: self.x,
+                    y: <$base>::conditional_select(&-self.y, &<$base>::one(), self.infinity),
+                    infinity: self.infinity,
+                }
+            }
+        }
+
+        impl Neg for $affine {
+            type Output = $affine;
+
+            fn neg(self) -> $affine {
+                -&self
+            }
+        }
+
+        impl From<$projective> for $affine {
+            fn from(p: $projective) -> $affine {
+                let zinv = p.z.invert().unwrap_or(<$base>::zero());
+                let affine = $affine { x: p.x * zinv, y: p.y * zinv, infinity: Choice::from(0) };
+                $affine::conditional_select(&affine, &$affine::identity(), zinv.is_zero())
+            }
+        }
+
+        impl From<&$projective> for $affine {
+            fn from(p: &$projective) -> $affine {
+                $affine::from(*p)
+            }
+        }
+
+        impl From<$affine> for $projective {
+            fn from(p: $affine) -> $projective {
+                let projective = $projective { x: p.x, y: p.y, z: <$base>::one() };
+                $projective::conditional_select(&projective, &$projective::identity(), p.infinity)
+            }
+        }
+
+        impl From<&$affine> for $projective {
+            fn from(p: &$affine) -> $projective {
+                $projective::from(*p)
+            }
+        }
+
+        impl $projective {
+            pub fn identity() -> Self {
+                $projective { x: <$base>::zero(), y: <$base>::one(), z: <$base>::zero() }
+            }
+
+            pub fn generator() -> Self {
+                $projective::from($affine::generator())
+            }
+
+            pub fn is_identity(&self) -> Choice {
+                self.z.is_zero()
+            }
+
+            fn mul_by_3b(x: &$base) -> $base {
+                let b3 = $b.double() + $b;
+                *x * b3
+            }
+
+            pub fn double(&self) -> Self {
+                let t0 = self.y.square();
+                let z3 = t0 + t0;
+                let z3 = z3 + z3;
+                let z3 = z3 + z3;
+                let t1 = self.y * self.z;
+                let t2 = self.z.square();
+                let t2 = Self::mul_by_3b(&t2);
+                let x3 = t2 * z3;
+                let y3 = t0 + t2;
+                let z3 = t1 * z3;
+                let t1 = t2 + t2;
+                let t2 = t1 + t2;
+                let t0 = t0 - t2;
+                let y3 = t0 * y3;
+                let y3 = x3 + y3;
+                let t1 = self.x * self.y;
+                let x3 = t0 * t1;
+                let x3 = x3 + x3;
+
+                let p = $projective { x: x3, y: y3, z: z3 };
+                $projective::conditional_select(&p, &$projective::identity(), self.is_identity())
+            }
+
+            fn add_points(&self, rhs: &Self) -> Self {
+                let t0 = self.x * rhs.x;
+                let t1 = self.y * rhs.y;
+                let t2 = self.z * rhs.z;
+                let t3 = self.x + self.y;
+                let t4 = rhs.x + rhs.y;
+                let t3 = t3 * t4;
+                let t4 = t0 + t1;
+                let t3 = t3 - t4;
+                let t4 = self.y + self.z;
+                let x3 = rhs.y + rhs.z;
+                let t4 = t4 * x3;
+                let x3 = t1 + t2;
+                let t4 = t4 - x3;
+                let x3 = self.x + self.z;
+                let y3 = rhs.x + rhs.z;
+                let x3 = x3 * y3;
+                let y3 = t0 + t2;
+                let y3 = x3 - y3;
+                let x3 = t0 + t0;
+                let t0 = x3 + t0;
+                let t2 = Self::mul_by_3b(&t2);
+                let z3 = t1 + t2;
+                let t1 = t1 - t2;
+                let y3 = Self::mul_by_3b(&y3);
+                let x3 = t4 * y3;
+                let t2 = t3 * t1;
+                let x3 = t2 - x3;
+                let y3 = y3 * t0;
+                let t1 = t1 * z3;
+                let y3 = t1 + y3;
+                let t0 = t0 * t3;
+                let z3 = z3 * t4;
+                let z3 = z3 + t0;
+
+                $projective { x: x3, y: y3, z: z3 }
+            }
+
+            // Double and add over every bit of the scalar
+            fn mul(&self, scalar: &Fr) -> Self {
+                let mut acc = $projective::identity();
+                for byte in scalar.to_repr().iter().rev() {
+                    for i in (0..8).rev() {
+                        acc = acc.double();
+                        let sum = acc.add_points(self);
+                        acc = $projective::conditional_select(&acc, &sum, Choice::from((byte >> i) & 1));
+                    }
+                }
+                acc
+            }
+
+            // Times an integer, in variable time
+            #[allow(dead_code)]
+            pub(crate) fn mul_raw(&self, k: &[u64; 4]) -> Self {
+                let mut acc = $projective::identity();
+                for limb in k.iter().rev() {
+                    for i in (0..64).rev() {
+                        acc = acc.double();
+                        if (limb >> i) & 1 == 1 {
+                            acc = acc.add_points(self);
+                        }
+                    }
+                }
+                acc
+            }
+        }
+
+        impl Default for $projective {
+            fn default() -> Self {
+                Self::identity()
+            }
+        }
+
+        impl ConstantTimeEq for $projective {
+            fn ct_eq(&self, other: &Self) -> Choice {
+                let (a, b) = (self.is_identity(), other.is_identity());
+                let x = (self.x * other.z).ct_eq(&(other.x * self.z));
+                let y = (self.y * other.z).ct_eq(&(other.y * self.z));
+                (a & b) | (!a & !b & x & y)
+            }
+        }
+
+        impl PartialEq for $projective {
+            fn eq(&self, other: &Self) -> bool {
+                self.ct_eq(other).into()
+            }
+        }
+
+        impl Eq for $projective {}
+
+        impl ConditionallySelectable for $projective {
+            fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+                $projective {
+                    x: <$base>::conditional_select(&a.x, &b.x, choice),
+                    y: <$base>::conditional_select(&a.y, &b.y, choice),
+                    z: <$base>::conditional_select(&a.z, &b.z, choice),
+                }
+            }
+        }
+
+        impl Neg for &$projective {
+            type Output = $projective;
+
+            fn neg(self) -> $projective {
+                $projective { x: self.x, y: -self.y, z: self.z }
+            }
+        }
+
+        impl Neg for $projective {
+            type Output = $projective;
+
+            fn neg(self) -> $projective {
+                -&self
+            }
+        }
+
+        impl Add<&$projective> for &$projective {
+            type Output = $projective;
+
+            fn add(self, rhs: &$projective) -> $projective {
+                $projective::add_points(self, rhs)
+            }
+        }
+
+        impl Sub<&$projective> for &$projective {
+            type Output = $projective;
+
+            fn sub(self, rhs: &$projective) -> $projective {
+                $projective::add_points(self, &-rhs)
+            }
+        }
+
+        impl Add<&$affine> for &$projective {
+            type Output = $projective;
+
+            fn add(self, rhs: &$affine) -> $projective {
+                $projective::add_points(self, &$projective::from(rhs))
+            }
+        }
+
+        impl Sub<&$affine> for &$projective {
+            type Output = $projective;
+
+            fn sub(self, rhs: &$affine) -> $projective {
+                $projective::add_points(self, &$projective::from(-rhs))
+            }
+        }
+
+        impl Mul<&Fr> for &$projective {
+            type Output = $projective;
+
+            fn mul(self, rhs: &Fr) -> $projective {
+                $projective::mul(self, rhs)
+            }
+        }
+
+        impl Mul<&Fr> for &$affine {
+            type Output = $projective;
+
+            fn mul(self, rhs: &Fr) -> $projective {
+                $projective::from(self).mul(rhs)
+            }
+        }
+
+        impl_binop_variants!($projective, $projective, $projective, Add, add);
+        impl_binop_variants!($projective, $projective, $projective, Sub, sub);
+        impl_binop_variants!($projective, $affine, $projective, Add, add);
+        impl_binop_variants!($projective, $affine, $projective, Sub, sub);
+        impl_binop_variants!($projective, Fr, $projective, Mul, mul);
+        impl_binop_variants!($affine, Fr, $projective, Mul, mul);
+        impl_assign_variants!($projective, $projective, AddAssign, add_assign, +);
+        impl_assign_variants!($projective, $projective, SubAssign, sub_assign, -);
+        impl_assign_variants!($projective, $affine, AddAssign, add_assign, +);
+        impl_assign_variants!($projective, $affine, SubAssign, sub_assign, -);
+        impl_assign_variants!($projective, Fr, MulAssign, mul_assign, *);
+
+        impl Sum for $projective {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::identity(), |acc, p| acc + p)
+            }
+        }
+
+        impl<'a> Sum<&'a $projective> for $projective {
+            fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+                iter.fold(Self::identity(), |acc, p| acc + p)
+            }
+        }
+
+        impl Group for $projective {
+            type Scalar = Fr;
+
+            fn random(mut rng: impl RngCore) -> Self {
+                $projective::generator() * Fr::random(&mut rng)
+            }
+
+            fn identity() -> Self {
+                $projective::identity()
+            }
+
+            fn generator() -> Self {
+                $projective::generator()
+            }
+
+            fn is_identity(&self) -> Choice {
+                $projective::is_identity(self)
+            }
+
+            fn double(&self) -> Self {
+                $projective::double(self)
+            }
+        }
+
+        #[cfg(any(test, feature = "std"))]
+        impl WnafGroup for $projective {
+            fn recommended_wnaf_for_num_scalars(num_scalars: usize) -> usize {
+                const RECOMMENDATIONS: [usize; 12] = [1, 3, 7, 20, 43, 120, 273, 563, 1630, 3128, 7933, 62569];
+                4 + RECOMMENDATIONS.iter().take_while(|r| num_scalars > **r).count()
+            }
+        }
+
+        impl Curve for $projective {
+            type AffineRepr = $affine;
+
+            fn to_affine(&self) -> $affine {
+                $affine::from(self)
+            }
+        }
+
+        impl PrimeGroup for $projective {}
+
+        impl PrimeCurve for $projective {
+            type Affine = $affine;
+        }
+
+        impl GroupEncoding for $projective {
+            type Repr = $compressed;
+
+            fn from_bytes(bytes: &$compressed) -> CtOption<Self> {
+                $affine::from_compressed(bytes).map($projective::from)
+            }
+
+            fn from_bytes_unchecked(bytes: &$compressed) -> CtOption<Self> {
+                $affine::from_compressed_unchecked(bytes).map($projective::from)
+            }
+
+            fn to_bytes(&self) -> $compressed {
+                $affine::from(self).to_compressed()
+            }
+        }
+
+        impl PrimeCurveAffine for $affine {
+            type Scalar = Fr;
+            type Curve = $projective;
+
+            fn identity() -> Self {
+                $affine::identity()
+            }
+
+            fn generator() -> Self {
+                $affine::generator()
+            }
+
+            fn is_identity(&self) -> Choice {
+                self.infinity
+            }
+
+            fn to_curve(&self) -> $projective {
+                $projective::from(self)
+            }
+        }
+
+        impl GroupEncoding for $affine {
+            type Repr = $compressed;
+
+            fn from_bytes(bytes: &$compressed) -> CtOption<Self> {
+                $affine::from_compressed(bytes)
+            }
+
+            fn from_bytes_unchecked(bytes: &$compressed) -> CtOption<Self> {
+                $affine::from_compressed_unchecked(bytes)
+            }
+
+            fn to_bytes(&self) -> $compressed {
+                self.to_compressed()
+            }
+        }
+
+        impl UncompressedEncoding for $affine {
+            type Uncompressed = $uncompressed;
+
+            fn from_uncompressed(bytes: &$uncompressed) -> CtOption<Self> {
+                $affine::from_uncompressed(bytes)
+            }
+
+            fn from_uncompressed_unchecked(bytes: &$uncompressed) -> CtOption<Self> {
+                $affine::from_uncompressed_unchecked(bytes)
+            }
+
+            fn to_uncompressed(&self) -> $uncompressed {
+                $affine::to_uncompressed(self)
+            }
+        }
+    };
+}
+
+curve!(
+    G1Affine, G1Projective, Fq,
+    G1Compressed, G1Uncompressed,
+    Fq::from_raw([3, 0, 0, 0]),
+    (Fq::from_raw([1, 0, 0, 0]), Fq::from_raw([2, 0, 0, 0])),
+    |_: &G1Projective| Choice::from(1)
+);
+
+curve!(
+    G2Affine, G2Projective, Fq2,
+    G2Compressed, G2Uncompressed,
+    G2_B,
+    (
+        Fq2::new(
+            Fq::from_raw([0x46debd5cd992f6ed, 0x674322d4f75edadd, 0x426a00665e5c4479, 0x1800deef121f1e76]),
+            Fq::from_raw([0x97e485b7aef312c2, 0xf1aa493335a9e712, 0x7260bfb731fb5d25, 0x198e9393920d483a]),
+        ),
+        Fq2::new(
+            Fq::from_raw([0x4ce6cc0166fa7daa, 0xe3d1e7690c43d37b, 0x4aab71808dcb408f, 0x12c85ea5db8c6deb]),
+            Fq::from_raw([0x55acdadcd122975b, 0xbc4b313370b38ef3, 0xec9e99ad690c3395, 0x090689d0585ff075]),
+        ),
+    ),
+    |p: &G2Projective| p.mul_raw(&Fr::MODULUS).is_identity()
+);
+
+// A G1 root is a single exponentiation, there is nothing to share
+impl DecodePoints for G1Affine {}
+
+// Compressed G2 points are decompressed with `Fq2::sqrt_parts`, and the
+// divisions it leaves are shared by the run as one inversion, by
+// Montgomery's trick. That is two exponentiations in Fq per point against
+// two in Fq2 point by point. benches/decompression.rs measured 1.6 ms for
+// 64 points against 4.9 ms one at a time in a release build, and 45 ms
+// against 50 ms with the subgroup checks, which both pay in full.
+impl DecodePoints for G2Affine {
+    fn decode_points(bytes: &[u8], out: &mut [Self], compressed: bool, checked: bool) -> Result<(), DecodingError> {
+        if !compressed {
+            return decode_each(bytes, out, compressed, checked);
+        }
+
+        // The parts of each root and whether y is the larger root, with
+        // c = 1 for the identity
+        let mut parts = Vec::with_capacity(out.len());
+        for (p, b) in out.iter_mut().zip(bytes.chunks(G2Compressed::default().0.len())) {
+            let flags = b[0] & FLAGS;
+            let mut x = [0u8; 64];
+            x.copy_from_slice(b);
+            x[0] &= !FLAGS;
+            if flags == COMPRESSED_INFINITY {
+                if x.iter().any(|x| *x != 0) {
+                    return Err(DecodingError::InvalidPoint);
+                }
+                *p = G2Affine::identity();
+                parts.push((Fq::one(), Fq::zero(), false, false));
+                continue;
+            }
+            if flags != COMPRESSED_SMALLEST && flags != COMPRESSED_LARGEST {
+                return Err(DecodingError::InvalidPoint);
+            }
+            let x = Option::<Fq2>::from(Fq2::read_be(&x)).ok_or(DecodingError::InvalidPoint)?;
+            let (c, n, swap) = (x.square() * x + G2_B).sqrt_parts().ok_or(DecodingError::InvalidPoint)?;
+            *p = G2Affine { x, y: Fq2::zero(), infinity: Choice::from(0) };
+            parts.push((c, n, swap, flags == COMPRESSED_LARGEST));
+        }
+
+        let divisor = |c: &Fq| if bool::from(c.is_zero()) { Fq::one() } else { *c };
+        let mut prefix = Vec::with_capacity(parts.len());
+        let mut acc = Fq::one();
+        for (c, ..) in parts.iter() {
+            prefix.push(acc);
+            acc *= divisor(c);
+        }
+        let mut inv = acc.invert().unwrap();
+
+        for ((p, (c, n, swap, largest)), before) in out.iter_mut().zip(parts.iter()).zip(prefix.iter()).rev() {
+            // inv is the inverse of the product of the divisors up to this one
+            let c_inv = inv * before;
+            inv *= divisor(c);
+            if bool::from(p.infinity) {
+                continue;
+            }
+
+            let m = n * c_inv;
+            let y = if *swap { Fq2::new(m, *c) } else { Fq2::new(*c, m) };
+            if y.square() != p.x.square() * p.x + G2_B {
+                return Err(DecodingError::InvalidPoint);
+            }
+            let flip = y.lexicographically_largest() ^ Choice::from(*largest as u8);
+            p.y = Fq2::conditional_select(&y, &-y, flip);
+            if checked && !bool::from(p.is_torsion_free()) {
+                return Err(DecodingError::InvalidPoint);
+            }
+        }
+
+        Ok(())
+    }
+}
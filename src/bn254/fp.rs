@@ -0,0 +1,395 @@
+// The base field Fq and the scalar field Fr of BN254, as four 64-bit limbs
+// in Montgomery form. Both moduli are below 2^254, so sums of two reduced
+// elements fit the limbs. The Montgomery constants are computed at compile
+// time from the modulus.
+
+use core::fmt;
+use core::ops::{ Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+
+use ff::{ Field, FieldBits, PrimeField, PrimeFieldBits };
+use rand_core::RngCore;
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq, CtOption };
+
+use super::Coordinate;
+
+const fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+// Borrow is all ones when set
+const fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + (borrow >> 63) as u128);
+    (t as u64, (t >> 64) as u64)
+}
+
+const fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 * c as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+// a - p if a >= p, for a < 2p
+const fn reduce(a: [u64; 4], p: &[u64; 4]) -> [u64; 4] {
+    let (d0, b) = sbb(a[0], p[0], 0);
+    let (d1, b) = sbb(a[1], p[1], b);
+    let (d2, b) = sbb(a[2], p[2], b);
+    let (d3, b) = sbb(a[3], p[3], b);
+    let (d0, c) = adc(d0, p[0] & b, 0);
+    let (d1, c) = adc(d1, p[1] & b, c);
+    let (d2, c) = adc(d2, p[2] & b, c);
+    let (d3, _) = adc(d3, p[3] & b, c);
+    [d0, d1, d2, d3]
+}
+
+const fn add(a: &[u64; 4], b: &[u64; 4], p: &[u64; 4]) -> [u64; 4] {
+    let (d0, c) = adc(a[0], b[0], 0);
+    let (d1, c) = adc(a[1], b[1], c);
+    let (d2, c) = adc(a[2], b[2], c);
+    let (d3, _) = adc(a[3], b[3], c);
+    reduce([d0, d1, d2, d3], p)
+}
+
+const fn sub(a: &[u64; 4], b: &[u64; 4], p: &[u64; 4]) -> [u64; 4] {
+    let (d0, b0) = sbb(a[0], b[0], 0);
+    let (d1, b0) = sbb(a[1], b[1], b0);
+    let (d2, b0) = sbb(a[2], b[2], b0);
+    let (d3, b0) = sbb(a[3], b[3], b0);
+    let (d0, c) = adc(d0, p[0] & b0, 0);
+    let (d1, c) = adc(d1, p[1] & b0, c);
+    let (d2, c) = adc(d2, p[2] & b0, c);
+    let (d3, _) = adc(d3, p[3] & b0, c);
+    [d0, d1, d2, d3]
+}
+
+// a * b / 2^256 mod p, with inv = -p^-1 mod 2^64
+const fn mont_mul(a: &[u64; 4], b: &[u64; 4], p: &[u64; 4], inv: u64) -> [u64; 4] {
+    let mut t = [0u64; 6];
+    let mut i = 0;
+    while i < 4 {
+        let mut carry = 0;
+        let mut j = 0;
+        while j < 4 {
+            (t[j], carry) = mac(t[j], a[j], b[i], carry);
+            j += 1;
+        }
+        let (t4, c) = adc(t[4], carry, 0);
+        t[4] = t4;
+        t[5] = c;
+
+        let m = t[0].wrapping_mul(inv);
+        let (_, mut carry) = mac(t[0], m, p[0], 0);
+        let mut j = 1;
+        while j < 4 {
+            (t[j - 1], carry) = mac(t[j], m, p[j], carry);
+            j += 1;
+        }
+        let (t3, c) = adc(t[4], carry, 0);
+        t[3] = t3;
+        t[4] = t[5] + c;
+        i += 1;
+    }
+    reduce([t[0], t[1], t[2], t[3]], p)
+}
+
+const fn inv(p0: u64) -> u64 {
+    let mut inv = 1u64;
+    let mut i = 0;
+    while i < 6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(p0.wrapping_mul(inv)));
+        i += 1;
+    }
+    inv.wrapping_neg()
+}
+
+// 2^k mod p
+const fn pow2(k: u32, p: &[u64; 4]) -> [u64; 4] {
+    let mut x = [1, 0, 0, 0];
+    let mut i = 0;
+    while i < k {
+        x = add(&x, &x, p);
+        i += 1;
+    }
+    x
+}
+
+// (p - 1) >> k
+const fn shr_pred(p: &[u64; 4], k: u32) -> [u64; 4] {
+    let mut x = [p[0] - 1, p[1], p[2], p[3]];
+    let mut i = 0;
+    while i < k {
+        x = [
+            (x[0] >> 1) | (x[1] << 63),
+            (x[1] >> 1) | (x[2] << 63),
+            (x[2] >> 1) | (x[3] << 63),
+            x[3] >> 1,
+        ];
+        i += 1;
+    }
+    x
+}
+
+fn from_le_bytes(b: &[u8; 32]) -> [u64; 4] {
+    let mut x = [0u64; 4];
+    for (limb, chunk) in x.iter_mut().zip(b.chunks(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    x
+}
+
+// Whether x < p
+fn is_reduced(x: &[u64; 4], p: &[u64; 4]) -> Choice {
+    let (_, b) = sbb(x[0], p[0], 0);
+    let (_, b) = sbb(x[1], p[1], b);
+    let (_, b) = sbb(x[2], p[2], b);
+    let (_, b) = sbb(x[3], p[3], b);
+    Choice::from((b & 1) as u8)
+}
+
+macro_rules! prime_field {
+    ($name:ident, $modulus:expr, $generator:expr, $s:expr) => {
+        #[derive(Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name(pub(crate) [u64; 4]);
+
+        impl $name {
+            pub(crate) const MODULUS: [u64; 4] = $modulus;
+            const INV: u64 = inv(Self::MODULUS[0]);
+            const R: [u64; 4] = pow2(256, &Self::MODULUS);
+            const R2: [u64; 4] = pow2(512, &Self::MODULUS);
+            // (t - 1) / 2 for the odd part t of p - 1
+            const T_MINUS_1_OVER_2: [u64; 4] = shr_pred(&Self::MODULUS, $s + 1);
+            const T: [u64; 4] = shr_pred(&Self::MODULUS, $s);
+
+            // From an integer below the modulus
+            pub(crate) const fn from_raw(x: [u64; 4]) -> Self {
+                $name(mont_mul(&x, &Self::R2, &Self::MODULUS, Self::INV))
+            }
+
+            // The integer this is, below the modulus
+            pub(crate) const fn to_raw(self) -> [u64; 4] {
+                mont_mul(&self.0, &[1, 0, 0, 0], &Self::MODULUS, Self::INV)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "0x")?;
+                for limb in self.to_raw().iter().rev() {
+                    write!(f, "{:016x}", limb)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> Choice {
+                self.0[0].ct_eq(&other.0[0])
+                    & self.0[1].ct_eq(&other.0[1])
+                    & self.0[2].ct_eq(&other.0[2])
+                    & self.0[3].ct_eq(&other.0[3])
+            }
+        }
+
+        impl ConditionallySelectable for $name {
+            fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+                $name([
+                    u64::conditional_select(&a.0[0], &b.0[0], choice),
+                    u64::conditional_select(&a.0[1], &b.0[1], choice),
+                    u64::conditional_select(&a.0[2], &b.0[2], choice),
+                    u64::conditional_select(&a.0[3], &b.0[3], choice),
+                ])
+            }
+        }
+
+        impl Add<&$name> for &$name {
+            type Output = $name;
+
+            fn add(self, rhs: &$name) -> $name {
+                $name(add(&self.0, &rhs.0, &$name::MODULUS))
+            }
+        }
+
+        impl Sub<&$name> for &$name {
+            type Output = $name;
+
+            fn sub(self, rhs: &$name) -> $name {
+                $name(sub(&self.0, &rhs.0, &$name::MODULUS))
+            }
+        }
+
+        impl Mul<&$name> for &$name {
+            type Output = $name;
+
+            fn mul(self, rhs: &$name) -> $name {
+                $name(mont_mul(&self.0, &rhs.0, &$name::MODULUS, $name::INV))
+            }
+        }
+
+        impl Neg for &$name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name(sub(&[0; 4], &self.0, &$name::MODULUS))
+            }
+        }
+
+        impl_field_ops!($name);
+
+        impl From<u64> for $name {
+            fn from(x: u64) -> Self {
+                $name::from_raw([x, 0, 0, 0])
+            }
+        }
+
+        impl Field for $name {
+            fn random(mut rng: impl RngCore) -> Self {
+                loop {
+                    let mut x = [0u64; 4];
+                    x.iter_mut().for_each(|l| *l = rng.next_u64());
+                    x[3] &= u64::MAX >> 2;
+                    if bool::from(is_reduced(&x, &Self::MODULUS)) {
+                        return $name(x);
+                    }
+                }
+            }
+
+            fn zero() -> Self {
+                $name([0; 4])
+            }
+
+            fn one() -> Self {
+                $name(Self::R)
+            }
+
+            fn square(&self) -> Self {
+                self * self
+            }
+
+            fn double(&self) -> Self {
+                self + self
+            }
+
+            fn invert(&self) -> CtOption<Self> {
+                let mut exp = Self::MODULUS;
+                exp[0] -= 2;
+                CtOption::new(self.pow_vartime(exp), !self.is_zero())
+            }
+
+            // Tonelli-Shanks
+            fn sqrt(&self) -> CtOption<Self> {
+                if bool::from(self.is_zero()) {
+                    return CtOption::new(*self, Choice::from(1));
+                }
+                let w = self.pow_vartime(Self::T_MINUS_1_OVER_2);
+                let mut v = Self::S;
+                let mut x = self * &w;
+                let mut b = x * w;
+                let mut z = Self::root_of_unity();
+                while b != Self::one() {
+                    let mut k = 0;
+                    let mut b2k = b;
+                    while b2k != Self::one() {
+                        b2k = b2k.square();
+                        k += 1;
+                        if k == v {
+                            return CtOption::new(Self::zero(), Choice::from(0));
+                        }
+                    }
+                    let mut w = z;
+                    for _ in 0..v - k - 1 {
+                        w = w.square();
+                    }
+                    z = w.square();
+                    b *= z;
+                    x *= w;
+                    v = k;
+                }
+                CtOption::new(x, x.square().ct_eq(self))
+            }
+        }
+
+        impl PrimeField for $name {
+            type Repr = [u8; 32];
+
+            fn from_repr(repr: [u8; 32]) -> CtOption<Self> {
+                let x = from_le_bytes(&repr);
+                CtOption::new(Self::from_raw(x), is_reduced(&x, &Self::MODULUS))
+            }
+
+            fn to_repr(&self) -> [u8; 32] {
+                let mut out = [0u8; 32];
+                for (chunk, limb) in out.chunks_mut(8).zip(self.to_raw().iter()) {
+                    chunk.copy_from_slice(&limb.to_le_bytes());
+                }
+                out
+            }
+
+            fn is_odd(&self) -> Choice {
+                Choice::from((self.to_raw()[0] & 1) as u8)
+            }
+
+            const NUM_BITS: u32 = 254;
+            const CAPACITY: u32 = 253;
+            const S: u32 = $s;
+
+            fn multiplicative_generator() -> Self {
+                Self::from($generator)
+            }
+
+            fn root_of_unity() -> Self {
+                Self::multiplicative_generator().pow_vartime(Self::T)
+            }
+        }
+
+        impl PrimeFieldBits for $name {
+            type ReprBits = [u64; 4];
+
+            fn to_le_bits(&self) -> FieldBits<[u64; 4]> {
+                FieldBits::new(self.to_raw())
+            }
+
+            fn char_le_bits() -> FieldBits<[u64; 4]> {
+                FieldBits::new(Self::MODULUS)
+            }
+        }
+    };
+}
+
+prime_field!(
+    Fq,
+    [0x3c208c16d87cfd47, 0x97816a916871ca8d, 0xb85045b68181585d, 0x30644e72e131a029],
+    3,
+    1
+);
+
+prime_field!(
+    Fr,
+    [0x43e1f593f0000001, 0x2833e84879b97091, 0xb85045b68181585d, 0x30644e72e131a029],
+    5,
+    28
+);
+
+impl Fq {
+    // (q - 1) / 2, the largest element that is not lexicographically largest
+    const HALF: [u64; 4] = shr_pred(&Fq::MODULUS, 1);
+}
+
+impl Coordinate for Fq {
+    const LEN: usize = 32;
+
+    fn write_be(&self, out: &mut [u8]) {
+        for (chunk, limb) in out.chunks_mut(8).zip(self.to_raw().iter().rev()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+    }
+
+    fn read_be(b: &[u8]) -> CtOption<Self> {
+        let mut le = [0u8; 32];
+        le.copy_from_slice(b);
+        le.reverse();
+        Fq::from_repr(le)
+    }
+
+    fn lexicographically_largest(&self) -> Choice {
+        is_reduced(&Fq::HALF, &self.to_raw())
+    }
+}
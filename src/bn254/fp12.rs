@@ -0,0 +1,195 @@
+// The tower above Fq2 that hosts the target group:
+//   Fq6 = Fq2[v] / (v^3 - xi) with xi = 9 + u
+//   Fq12 = Fq6[w] / (w^2 - v)
+// Only what the pairing needs is here, so neither implements `Field`.
+
+use core::ops::{ Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+
+use ff::Field;
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq };
+
+use super::fp2::Fq2;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Fq6 {
+    pub c0: Fq2,
+    pub c1: Fq2,
+    pub c2: Fq2,
+}
+
+impl Fq6 {
+    pub fn zero() -> Self {
+        Fq6 { c0: Fq2::zero(), c1: Fq2::zero(), c2: Fq2::zero() }
+    }
+
+    pub fn one() -> Self {
+        Fq6 { c0: Fq2::one(), c1: Fq2::zero(), c2: Fq2::zero() }
+    }
+
+    // Times v
+    fn mul_by_nonresidue(&self) -> Self {
+        Fq6 { c0: self.c2.mul_by_nonresidue(), c1: self.c0, c2: self.c1 }
+    }
+
+    pub fn square(&self) -> Self {
+        self * self
+    }
+
+    pub fn invert(&self) -> Option<Self> {
+        let t0 = self.c0.square() - (self.c1 * self.c2).mul_by_nonresidue();
+        let t1 = self.c2.square().mul_by_nonresidue() - self.c0 * self.c1;
+        let t2 = self.c1.square() - self.c0 * self.c2;
+        let norm = self.c0 * t0 + (self.c2 * t1 + self.c1 * t2).mul_by_nonresidue();
+        Option::<Fq2>::from(norm.invert()).map(|n| Fq6 { c0: t0 * n, c1: t1 * n, c2: t2 * n })
+    }
+}
+
+impl Add<&Fq6> for &Fq6 {
+    type Output = Fq6;
+
+    fn add(self, rhs: &Fq6) -> Fq6 {
+        Fq6 { c0: self.c0 + rhs.c0, c1: self.c1 + rhs.c1, c2: self.c2 + rhs.c2 }
+    }
+}
+
+impl Sub<&Fq6> for &Fq6 {
+    type Output = Fq6;
+
+    fn sub(self, rhs: &Fq6) -> Fq6 {
+        Fq6 { c0: self.c0 - rhs.c0, c1: self.c1 - rhs.c1, c2: self.c2 - rhs.c2 }
+    }
+}
+
+impl Mul<&Fq6> for &Fq6 {
+    type Output = Fq6;
+
+    fn mul(self, rhs: &Fq6) -> Fq6 {
+        let (a, b) = (self, rhs);
+        Fq6 {
+            c0: a.c0 * b.c0 + (a.c1 * b.c2 + a.c2 * b.c1).mul_by_nonresidue(),
+            c1: a.c0 * b.c1 + a.c1 * b.c0 + (a.c2 * b.c2).mul_by_nonresidue(),
+            c2: a.c0 * b.c2 + a.c1 * b.c1 + a.c2 * b.c0,
+        }
+    }
+}
+
+impl Neg for &Fq6 {
+    type Output = Fq6;
+
+    fn neg(self) -> Fq6 {
+        Fq6 { c0: -self.c0, c1: -self.c1, c2: -self.c2 }
+    }
+}
+
+impl_field_ops!(Fq6);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fq12 {
+    pub c0: Fq6,
+    pub c1: Fq6,
+}
+
+impl Default for Fq12 {
+    fn default() -> Self {
+        Fq12::one()
+    }
+}
+
+impl Fq12 {
+    pub fn zero() -> Self {
+        Fq12 { c0: Fq6::zero(), c1: Fq6::zero() }
+    }
+
+    pub fn one() -> Self {
+        Fq12 { c0: Fq6::one(), c1: Fq6::zero() }
+    }
+
+    pub fn square(&self) -> Self {
+        self * self
+    }
+
+    // x^(q^6), the inverse of the elements of the target group
+    pub fn conjugate(&self) -> Self {
+        Fq12 { c0: self.c0, c1: -self.c1 }
+    }
+
+    pub fn invert(&self) -> Option<Self> {
+        let norm = self.c0.square() - self.c1.square().mul_by_nonresidue();
+        norm.invert().map(|n| Fq12 { c0: self.c0 * n, c1: -(self.c1 * n) })
+    }
+
+    // Square and multiply over little-endian limbs
+    pub fn pow_vartime(&self, exp: &[u64]) -> Self {
+        let mut acc = Fq12::one();
+        for limb in exp.iter().rev() {
+            for i in (0..64).rev() {
+                acc = acc.square();
+                if (limb >> i) & 1 == 1 {
+                    acc *= self;
+                }
+            }
+        }
+        acc
+    }
+}
+
+impl ConstantTimeEq for Fq12 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        [
+            (self.c0.c0, other.c0.c0), (self.c0.c1, other.c0.c1), (self.c0.c2, other.c0.c2),
+            (self.c1.c0, other.c1.c0), (self.c1.c1, other.c1.c1), (self.c1.c2, other.c1.c2),
+        ]
+            .iter()
+            .fold(Choice::from(1), |acc, (a, b)| acc & a.ct_eq(b))
+    }
+}
+
+impl ConditionallySelectable for Fq12 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let select6 = |a: &Fq6, b: &Fq6| Fq6 {
+            c0: Fq2::conditional_select(&a.c0, &b.c0, choice),
+            c1: Fq2::conditional_select(&a.c1, &b.c1, choice),
+            c2: Fq2::conditional_select(&a.c2, &b.c2, choice),
+        };
+        Fq12 { c0: select6(&a.c0, &b.c0), c1: select6(&a.c1, &b.c1) }
+    }
+}
+
+impl Add<&Fq12> for &Fq12 {
+    type Output = Fq12;
+
+    fn add(self, rhs: &Fq12) -> Fq12 {
+        Fq12 { c0: self.c0 + rhs.c0, c1: self.c1 + rhs.c1 }
+    }
+}
+
+impl Sub<&Fq12> for &Fq12 {
+    type Output = Fq12;
+
+    fn sub(self, rhs: &Fq12) -> Fq12 {
+        Fq12 { c0: self.c0 - rhs.c0, c1: self.c1 - rhs.c1 }
+    }
+}
+
+impl Mul<&Fq12> for &Fq12 {
+    type Output = Fq12;
+
+    fn mul(self, rhs: &Fq12) -> Fq12 {
+        let t0 = self.c0 * rhs.c0;
+        let t1 = self.c1 * rhs.c1;
+        Fq12 {
+            c0: t0 + t1.mul_by_nonresidue(),
+            c1: (self.c0 + self.c1) * (rhs.c0 + rhs.c1) - t0 - t1,
+        }
+    }
+}
+
+impl Neg for &Fq12 {
+    type Output = Fq12;
+
+    fn neg(self) -> Fq12 {
+        Fq12 { c0: -self.c0, c1: -self.c1 }
+    }
+}
+
+impl_field_ops!(Fq12);
@@ -0,0 +1,194 @@
+// Fq2 = Fq[u] / (u^2 + 1), the field G2 is defined over
+
+use core::ops::{ Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+
+use ff::Field;
+use rand_core::RngCore;
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq, CtOption };
+
+use super::Coordinate;
+use super::fp::Fq;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Fq2 {
+    pub c0: Fq,
+    pub c1: Fq,
+}
+
+impl Fq2 {
+    pub(crate) const fn new(c0: Fq, c1: Fq) -> Self {
+        Fq2 { c0, c1 }
+    }
+
+    // Times xi = 9 + u, the non-residue that Fq6 is built with
+    pub(crate) fn mul_by_nonresidue(&self) -> Self {
+        let nine = |x: &Fq| {
+            let x8 = x.double().double().double();
+            x8 + x
+        };
+        Fq2 {
+            c0: nine(&self.c0) - self.c1,
+            c1: self.c0 + nine(&self.c1),
+        }
+    }
+
+    pub(crate) fn mul_by_fq(&self, x: &Fq) -> Self {
+        Fq2 { c0: self.c0 * x, c1: self.c1 * x }
+    }
+
+    // Square root by the complex method, in two Fq exponentiations and
+    // one division, which is left to the caller so that it can be batched.
+    // Returns (c, n, swap): the root is c + n / c * u, or n / c + c * u when
+    // swap is set, with n = 0 whenever c = 0. None if self is no square.
+    pub(crate) fn sqrt_parts(&self) -> Option<(Fq, Fq, bool)> {
+        // (q + 1) / 4, x^((q + 1) / 4) is the root of x in Fq when there is
+        // one and the root of -x otherwise
+        const EXP: [u64; 4] = [0x4f082305b61f3f52, 0x65e05aa45a1c72a3, 0x6e14116da0605617, 0x0c19139cb84c680a];
+        // 1 / 2 = (q + 1) / 2
+        const HALF: Fq = Fq::from_raw([0x9e10460b6c3e7ea4, 0xcbc0b548b438e546, 0xdc2822db40c0ac2e, 0x183227397098d014]);
+        let root = |x: &Fq| {
+            let c = x.pow_vartime(EXP);
+            (c, c.square() == *x)
+        };
+
+        if bool::from(self.c1.is_zero()) {
+            let (c, square) = root(&self.c0);
+            return Some((c, Fq::zero(), !square));
+        }
+
+        // alpha = sqrt(c0^2 + c1^2) and delta = (c0 + alpha) / 2 != 0. When
+        // delta is no square, (c0 - alpha) / 2 = -c1^2 / (4 delta) is, and
+        // the roles of the two coefficients swap.
+        let (alpha, square) = root(&(self.c0.square() + self.c1.square()));
+        if !square {
+            return None;
+        }
+        let delta = (self.c0 + alpha) * HALF;
+        let (c, square) = root(&delta);
+        Some((c, self.c1 * HALF, !square))
+    }
+}
+
+impl ConstantTimeEq for Fq2 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.c0.ct_eq(&other.c0) & self.c1.ct_eq(&other.c1)
+    }
+}
+
+impl ConditionallySelectable for Fq2 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Fq2 {
+            c0: Fq::conditional_select(&a.c0, &b.c0, choice),
+            c1: Fq::conditional_select(&a.c1, &b.c1, choice),
+        }
+    }
+}
+
+impl Add<&Fq2> for &Fq2 {
+    type Output = Fq2;
+
+    fn add(self, rhs: &Fq2) -> Fq2 {
+        Fq2 { c0: self.c0 + rhs.c0, c1: self.c1 + rhs.c1 }
+    }
+}
+
+impl Sub<&Fq2> for &Fq2 {
+    type Output = Fq2;
+
+    fn sub(self, rhs: &Fq2) -> Fq2 {
+        Fq2 { c0: self.c0 - rhs.c0, c1: self.c1 - rhs.c1 }
+    }
+}
+
+impl Mul<&Fq2> for &Fq2 {
+    type Output = Fq2;
+
+    // Karatsuba
+    fn mul(self, rhs: &Fq2) -> Fq2 {
+        let t0 = self.c0 * rhs.c0;
+        let t1 = self.c1 * rhs.c1;
+        let t2 = (self.c0 + self.c1) * (rhs.c0 + rhs.c1);
+        Fq2 { c0: t0 - t1, c1: t2 - t0 - t1 }
+    }
+}
+
+impl Neg for &Fq2 {
+    type Output = Fq2;
+
+    fn neg(self) -> Fq2 {
+        Fq2 { c0: -self.c0, c1: -self.c1 }
+    }
+}
+
+impl_field_ops!(Fq2);
+
+impl Field for Fq2 {
+    fn random(mut rng: impl RngCore) -> Self {
+        Fq2 { c0: Fq::random(&mut rng), c1: Fq::random(&mut rng) }
+    }
+
+    fn zero() -> Self {
+        Fq2 { c0: Fq::zero(), c1: Fq::zero() }
+    }
+
+    fn one() -> Self {
+        Fq2 { c0: Fq::one(), c1: Fq::zero() }
+    }
+
+    fn square(&self) -> Self {
+        // (c0 + c1)(c0 - c1) + 2 c0 c1 u
+        let a = self.c0 + self.c1;
+        let b = self.c0 - self.c1;
+        Fq2 { c0: a * b, c1: (self.c0 * self.c1).double() }
+    }
+
+    fn double(&self) -> Self {
+        self + self
+    }
+
+    fn invert(&self) -> CtOption<Self> {
+        (self.c0.square() + self.c1.square()).invert().map(|t| Fq2 {
+            c0: self.c0 * t,
+            c1: -(self.c1 * t),
+        })
+    }
+
+    // Algorithm 9 of https://eprint.iacr.org/2012/685.pdf, for q = 3 mod 4
+    fn sqrt(&self) -> CtOption<Self> {
+        // (q - 3) / 4 and (q - 1) / 2
+        const EXP1: [u64; 4] = [0x4f082305b61f3f51, 0x65e05aa45a1c72a3, 0x6e14116da0605617, 0x0c19139cb84c680a];
+        const EXP2: [u64; 4] = [0x9e10460b6c3e7ea3, 0xcbc0b548b438e546, 0xdc2822db40c0ac2e, 0x183227397098d014];
+
+        let a1 = self.pow_vartime(EXP1);
+        let alpha = a1.square() * self;
+        let x0 = a1 * self;
+        let minus_one = -Fq2::one();
+        let x = if alpha == minus_one {
+            x0 * Fq2::new(Fq::zero(), Fq::one())
+        } else {
+            (alpha + Fq2::one()).pow_vartime(EXP2) * x0
+        };
+        CtOption::new(x, x.square().ct_eq(self))
+    }
+}
+
+impl Coordinate for Fq2 {
+    const LEN: usize = 2 * Fq::LEN;
+
+    // c1 | c0
+    fn write_be(&self, out: &mut [u8]) {
+        self.c1.write_be(&mut out[..Fq::LEN]);
+        self.c0.write_be(&mut out[Fq::LEN..]);
+    }
+
+    fn read_be(b: &[u8]) -> CtOption<Self> {
+        let c1 = Fq::read_be(&b[..Fq::LEN]);
+        let c0 = Fq::read_be(&b[Fq::LEN..]);
+        c0.and_then(|c0| c1.map(|c1| Fq2 { c0, c1 }))
+    }
+
+    fn lexicographically_largest(&self) -> Choice {
+        self.c1.lexicographically_largest()
+            | (self.c1.is_zero() & self.c0.lexicographically_largest())
+    }
+}
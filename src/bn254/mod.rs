@@ -0,0 +1,620 @@
+// BN254, the curve of Ethereum's pairing precompiles, of circom and of
+// gnark's default backend, as a `pairing::Engine`.
+//
+// The scalar field has 2-adicity 28, so circuits are limited to 2^28
+// constraints. The pairing is the ate pairing over the loop 6u^2 = t - 1,
+// evaluated with points on the twist in homogeneous coordinates. Its values
+// differ from those of the optimal ate pairing that other libraries
+// compute, but equations between products of pairings, which is all a
+// verifier checks, hold for one exactly when they hold for the other.
+//
+// Nothing here is constant time except scalar multiplication and the field
+// operations; pairings only ever take public inputs.
+
+use core::ops::{ Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign };
+use core::iter::Sum;
+
+use ff::Field;
+use pairing::{ Engine, MillerLoopResult, MultiMillerLoop, PairingCurveAffine };
+use pairing::group::Group;
+use rand_core::RngCore;
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq, CtOption };
+
+// Owned and mixed variants of a binary operator implemented on references
+macro_rules! impl_binop_variants {
+    ($lhs:ty, $rhs:ty, $output:ty, $trait:ident, $method:ident) => {
+        impl $trait<&$rhs> for $lhs {
+            type Output = $output;
+
+            fn $method(self, rhs: &$rhs) -> $output {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl $trait<$rhs> for &$lhs {
+            type Output = $output;
+
+            fn $method(self, rhs: $rhs) -> $output {
+                self.$method(&rhs)
+            }
+        }
+
+        impl $trait<$rhs> for $lhs {
+            type Output = $output;
+
+            fn $method(self, rhs: $rhs) -> $output {
+                (&self).$method(&rhs)
+            }
+        }
+    };
+}
+
+macro_rules! impl_assign_variants {
+    ($lhs:ty, $rhs:ty, $trait:ident, $method:ident, $op:tt) => {
+        impl $trait<$rhs> for $lhs {
+            fn $method(&mut self, rhs: $rhs) {
+                *self = &*self $op &rhs;
+            }
+        }
+
+        impl $trait<&$rhs> for $lhs {
+            fn $method(&mut self, rhs: &$rhs) {
+                *self = &*self $op rhs;
+            }
+        }
+    };
+}
+
+// Everything but `&T op &T` for the arithmetic of a field
+macro_rules! impl_field_ops {
+    ($t:ty) => {
+        impl_binop_variants!($t, $t, $t, Add, add);
+        impl_binop_variants!($t, $t, $t, Sub, sub);
+        impl_binop_variants!($t, $t, $t, Mul, mul);
+        impl_assign_variants!($t, $t, AddAssign, add_assign, +);
+        impl_assign_variants!($t, $t, SubAssign, sub_assign, -);
+        impl_assign_variants!($t, $t, MulAssign, mul_assign, *);
+
+        impl Neg for $t {
+            type Output = $t;
+
+            fn neg(self) -> $t {
+                -&self
+            }
+        }
+    };
+}
+
+mod fp;
+mod fp2;
+mod fp12;
+mod curve;
+
+pub use fp::{ Fq, Fr };
+pub use fp2::Fq2;
+pub use curve::{
+    G1Affine, G1Compressed, G1Projective, G1Uncompressed,
+    G2Affine, G2Compressed, G2Projective, G2Uncompressed,
+};
+
+use fp12::{ Fq6, Fq12 };
+
+// Base field elements as the big-endian integers that encodings hold
+pub(crate) trait Coordinate: Field {
+    const LEN: usize;
+
+    fn write_be(&self, out: &mut [u8]);
+
+    fn read_be(b: &[u8]) -> CtOption<Self>;
+
+    // Whether this is larger than its negation, as integers
+    fn lexicographically_largest(&self) -> Choice;
+}
+
+// 6u^2, the ate loop count, little-endian
+const ATE_LOOP: [u64; 2] = [0xf83e9682e87cfd46, 0x6f4d8248eeb859fb];
+
+// q^2, little-endian
+const Q_SQUARED: [u64; 8] = [
+    0x3b5458a2275d69b1, 0xa602072d09eac101, 0x4a50189c6d96cadc, 0x04689e957a1242c8,
+    0x26edfa5c34c6b38d, 0xb00b855116375606, 0x599a6f7c0348d21c, 0x0925c4b8763cbf9c,
+];
+
+// (q^4 - q^2 + 1) / r, little-endian
+const HARD_PART: [u64; 12] = [
+    0xe81bb482ccdf42b1, 0x5abf5cc4f49c36d4, 0xf1154e7e1da014fd, 0xdcc7b44c87cdbacf,
+    0xaaa441e3954bcf8a, 0x6b887d56d5095f23, 0x79581e16f3fd90c6, 0x3b1b1355d189227d,
+    0x4e529a5861876f6b, 0x6c0eb522d5b12278, 0x331ec15183177faf, 0x01baaa710b0759ad,
+];
+
+// The line a y_p + b x_p w + c w^3 on the twist, at p once both are
+// mapped into Fq12. Lines are only known up to a factor in Fq2, which the
+// final exponentiation removes.
+fn line(a: &Fq2, b: &Fq2, c: &Fq2, p: &G1Affine) -> Fq12 {
+    Fq12 {
+        c0: Fq6 { c0: a.mul_by_fq(&p.y), c1: Fq2::zero(), c2: Fq2::zero() },
+        c1: Fq6 { c0: b.mul_by_fq(&p.x), c1: *c, c2: Fq2::zero() },
+    }
+}
+
+// The tangent at t = (x, y, z) in homogeneous coordinates, of slope
+// 3x^2 / 2yz, times 2yz^2, and 2t
+fn double_step(t: &mut (Fq2, Fq2, Fq2), p: &G1Affine) -> Fq12 {
+    let (x, y, z) = *t;
+    let w = x.square().double() + x.square();
+    let l = line(&(y * z * z).double(), &-(w * z), &(w * x - (y.square() * z).double()), p);
+
+    let s = y * z;
+    let b = x * y * s;
+    let h = w.square() - b.double().double().double();
+    let s2 = s.square();
+    *t = (
+        (h * s).double(),
+        w * (b.double().double() - h) - (y.square() * s2).double().double().double(),
+        (s2 * s).double().double().double(),
+    );
+    l
+}
+
+// The line through t and q, of slope u / v, times v, and t + q
+fn add_step(t: &mut (Fq2, Fq2, Fq2), q: &G2Affine, p: &G1Affine) -> Fq12 {
+    let (x, y, z) = *t;
+    let u = q.y * z - y;
+    let v = q.x * z - x;
+    let l = line(&v, &-u, &(u * q.x - v * q.y), p);
+
+    let v2 = v.square();
+    let v3 = v2 * v;
+    let a = u.square() * z - v3 - (v2 * x).double();
+    *t = (v * a, u * (v2 * x - a) - v3 * y, v3 * z);
+    l
+}
+
+// Without inversions a step costs about 25 multiplications in Fq2 and one
+// or two in Fq12. In a release build one loop took 2.2 ms, against 4.6 ms
+// with affine steps and an inversion each, and a final exponentiation
+// 9 ms. `verify_proof` with one public input, four of each, took 53 ms.
+fn miller_loop(p: &G1Affine, q: &G2Affine) -> Fq12 {
+    if bool::from(p.is_identity() | q.is_identity()) {
+        return Fq12::one();
+    }
+
+    let mut t = (q.x, q.y, Fq2::one());
+    let mut f = Fq12::one();
+    let bits = 128 - ATE_LOOP[1].leading_zeros() as usize;
+    for i in (0..bits - 1).rev() {
+        f = f.square() * double_step(&mut t, p);
+        if (ATE_LOOP[i / 64] >> (i % 64)) & 1 == 1 {
+            f *= add_step(&mut t, q, p);
+        }
+    }
+
+    // For no prefix k of the loop count are k, 2k - 1 or 2k + 1 divisible
+    // by a divisor above 1 of the order of the twist, so for points on it
+    // the loop never meets the identity, nor ±q when adding q. Only points
+    // off the curve, which `from_uncompressed_unchecked` lets through, can
+    // give a zero line, and they pair like the identity.
+    if f == Fq12::zero() {
+        return Fq12::one();
+    }
+    f
+}
+
+fn final_exponentiation(f: &Fq12) -> Gt {
+    // f^((q^6 - 1)(q^2 + 1)) is in the cyclotomic subgroup, the rest maps
+    // it onto the r-torsion
+    let f = f.conjugate() * f.invert().unwrap();
+    let f = f.pow_vartime(&Q_SQUARED) * f;
+    Gt(f.pow_vartime(&HARD_PART))
+}
+
+// The target group, the r-th roots of unity of Fq12, written additively
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Gt(Fq12);
+
+impl ConstantTimeEq for Gt {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl ConditionallySelectable for Gt {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Gt(Fq12::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl Add<&Gt> for &Gt {
+    type Output = Gt;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: &Gt) -> Gt {
+        Gt(self.0 * rhs.0)
+    }
+}
+
+impl Sub<&Gt> for &Gt {
+    type Output = Gt;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: &Gt) -> Gt {
+        Gt(self.0 * rhs.0.conjugate())
+    }
+}
+
+impl Mul<&Fr> for &Gt {
+    type Output = Gt;
+
+    fn mul(self, rhs: &Fr) -> Gt {
+        Gt(self.0.pow_vartime(&rhs.to_raw()))
+    }
+}
+
+impl Neg for &Gt {
+    type Output = Gt;
+
+    fn neg(self) -> Gt {
+        Gt(self.0.conjugate())
+    }
+}
+
+impl Neg for Gt {
+    type Output = Gt;
+
+    fn neg(self) -> Gt {
+        -&self
+    }
+}
+
+impl_binop_variants!(Gt, Gt, Gt, Add, add);
+impl_binop_variants!(Gt, Gt, Gt, Sub, sub);
+impl_binop_variants!(Gt, Fr, Gt, Mul, mul);
+impl_assign_variants!(Gt, Gt, AddAssign, add_assign, +);
+impl_assign_variants!(Gt, Gt, SubAssign, sub_assign, -);
+impl_assign_variants!(Gt, Fr, MulAssign, mul_assign, *);
+
+impl Sum for Gt {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Gt::identity(), |acc, x| acc + x)
+    }
+}
+
+impl<'a> Sum<&'a Gt> for Gt {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Gt::identity(), |acc, x| acc + x)
+    }
+}
+
+impl Group for Gt {
+    type Scalar = Fr;
+
+    fn random(mut rng: impl RngCore) -> Self {
+        Gt::generator() * Fr::random(&mut rng)
+    }
+
+    fn identity() -> Self {
+        Gt(Fq12::one())
+    }
+
+    fn generator() -> Self {
+        Bn254::pairing(&G1Affine::generator(), &G2Affine::generator())
+    }
+
+    fn is_identity(&self) -> Choice {
+        self.0.ct_eq(&Fq12::one())
+    }
+
+    fn double(&self) -> Self {
+        Gt(self.0.square())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Bn254;
+
+impl Engine for Bn254 {
+    type Fr = Fr;
+    type G1 = G1Projective;
+    type G1Affine = G1Affine;
+    type G2 = G2Projective;
+    type G2Affine = G2Affine;
+    type Gt = Gt;
+
+    fn pairing(p: &G1Affine, q: &G2Affine) -> Gt {
+        final_exponentiation(&miller_loop(p, q))
+    }
+}
+
+impl PairingCurveAffine for G1Affine {
+    type Pair = G2Affine;
+    type PairingResult = Gt;
+
+    fn pairing_with(&self, other: &G2Affine) -> Gt {
+        Bn254::pairing(self, other)
+    }
+}
+
+impl PairingCurveAffine for G2Affine {
+    type Pair = G1Affine;
+    type PairingResult = Gt;
+
+    fn pairing_with(&self, other: &G1Affine) -> Gt {
+        Bn254::pairing(other, self)
+    }
+}
+
+// Nothing is precomputed; the loop works on the affine point itself
+#[derive(Clone, Debug)]
+pub struct G2Prepared(G2Affine);
+
+impl From<G2Affine> for G2Prepared {
+    fn from(q: G2Affine) -> Self {
+        G2Prepared(q)
+    }
+}
+
+// A product of Miller loops, before the final exponentiation
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MillerLoopOutput(Fq12);
+
+impl Add<&MillerLoopOutput> for MillerLoopOutput {
+    type Output = MillerLoopOutput;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: &MillerLoopOutput) -> MillerLoopOutput {
+        MillerLoopOutput(self.0 * rhs.0)
+    }
+}
+
+impl Add for MillerLoopOutput {
+    type Output = MillerLoopOutput;
+
+    fn add(self, rhs: MillerLoopOutput) -> MillerLoopOutput {
+        self.add(&rhs)
+    }
+}
+
+impl AddAssign<&MillerLoopOutput> for MillerLoopOutput {
+    fn add_assign(&mut self, rhs: &MillerLoopOutput) {
+        *self = *self + rhs;
+    }
+}
+
+impl AddAssign for MillerLoopOutput {
+    fn add_assign(&mut self, rhs: MillerLoopOutput) {
+        *self = *self + rhs;
+    }
+}
+
+impl MillerLoopResult for MillerLoopOutput {
+    type Gt = Gt;
+
+    fn final_exponentiation(&self) -> Gt {
+        final_exponentiation(&self.0)
+    }
+}
+
+impl MultiMillerLoop for Bn254 {
+    type G2Prepared = G2Prepared;
+    type Result = MillerLoopOutput;
+
+    fn multi_miller_loop(terms: &[(&G1Affine, &G2Prepared)]) -> MillerLoopOutput {
+        terms
+            .iter()
+            .fold(MillerLoopOutput::default(), |acc, (p, q)| acc + MillerLoopOutput(miller_loop(p, &q.0)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeField;
+    use pairing::group::{ Curve, GroupEncoding, UncompressedEncoding };
+    use rand::thread_rng;
+
+    use crate::tests::{ cube_fixture_on, unhex };
+    use crate::verifier::verify_proof;
+    use crate::{ gnark, snarkjs };
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let mut rng = thread_rng();
+        let x = Fq2::random(&mut rng);
+        assert_eq!(x * x.invert().unwrap(), Fq2::one());
+        let y = x.square().sqrt().unwrap();
+        assert!(y == x || y == -x);
+        let s = Fr::random(&mut rng);
+        assert_eq!(Fr::from_repr(s.to_repr()).unwrap(), s);
+        assert_eq!(Field::pow_vartime(&Fr::root_of_unity(), [1 << 28]), Fr::one());
+        assert_ne!(Field::pow_vartime(&Fr::root_of_unity(), [1 << 27]), Fr::one());
+        let f = Fq12 { c0: Fq6 { c0: x, c1: y, c2: x }, c1: Fq6 { c0: y, c1: x, c2: Fq2::one() } };
+        assert_eq!(f * f.invert().unwrap(), Fq12::one());
+    }
+
+    #[test]
+    fn groups() {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        assert!(bool::from(g1.is_on_curve() & g2.is_on_curve() & g2.is_torsion_free()));
+        assert!(bool::from((G2Projective::generator() * -Fr::one() + g2).is_identity()));
+        let p = G2Projective::random(thread_rng());
+        assert_eq!(p.double() + p, p * Fr::from(3));
+        assert_eq!(G2Affine::from_compressed(&p.to_affine().to_compressed()).unwrap(), p.to_affine());
+        assert_eq!(G2Affine::from_uncompressed(&p.to_affine().to_uncompressed()).unwrap(), p.to_affine());
+
+        // The generator compresses as gnark writes it
+        let mut golden = [0; 32];
+        golden[0] = 0x80;
+        golden[31] = 1;
+        assert_eq!(g1.to_bytes().as_ref(), &golden[..]);
+        assert_eq!((-g1).to_bytes().as_ref()[0], 0xc0);
+        assert_eq!(G1Affine::from_bytes(&(-g1).to_bytes()).unwrap(), -g1);
+        assert_eq!(G1Affine::identity().to_bytes().as_ref()[0], 0x40);
+        assert!(bool::from(G1Affine::from_bytes(&G1Affine::identity().to_bytes()).unwrap().is_identity()));
+        assert_eq!(G1Affine::identity().to_uncompressed().as_ref(), &[0; 64][..]);
+    }
+
+    #[test]
+    fn sqrt_parts() {
+        let mut rng = thread_rng();
+        let root = |a: &Fq2| a.sqrt_parts().map(|(c, n, swap)| {
+            let m = if bool::from(c.is_zero()) { Fq::zero() } else { n * c.invert().unwrap() };
+            if swap { Fq2::new(m, c) } else { Fq2::new(c, m) }
+        });
+
+        for a in [Fq2::random(&mut rng), Fq2::new(Fq::random(&mut rng), Fq::zero()), Fq2::new(-Fq::one(), Fq::zero()), Fq2::zero()] {
+            let a = a.square();
+            assert_eq!(root(&a).unwrap().square(), a);
+            // Every element of Fq is a square in Fq2, non-squares of Fq too
+            let b = Fq2::new(a.c0, Fq::zero());
+            assert_eq!(root(&b).unwrap().square(), b);
+        }
+        // xi = 9 + u, the non-residue of the tower
+        assert!(root(&Fq2::new(Fq::from(9), Fq::one())).is_none());
+    }
+
+    #[test]
+    fn batched_decompression() {
+        use crate::source::{ decode_each, DecodePoints };
+
+        let mut points: Vec<G2Affine> = (0..64).map(|_| G2Projective::random(thread_rng()).to_affine()).collect();
+        points[5] = G2Affine::identity();
+        points[6] = -points[7];
+        let bytes: Vec<u8> = points.iter().flat_map(|p| p.to_compressed().as_ref().to_vec()).collect();
+
+        for checked in [false, true] {
+            let mut out = vec![G2Affine::identity(); points.len()];
+            G2Affine::decode_points(&bytes, &mut out, true, checked).unwrap();
+            assert_eq!(out, points);
+            decode_each(&bytes, &mut out, true, checked).unwrap();
+            assert_eq!(out, points);
+        }
+
+        let mut out = vec![G2Affine::identity(); 3];
+        let mut bad = bytes.clone();
+        bad[64] &= 0x3f;
+        assert!(G2Affine::decode_points(&bad, &mut out, true, false).is_err());
+        // A coefficient of x above the modulus
+        let mut bad = bytes.clone();
+        bad[64] |= 0x3f;
+        bad[65..96].fill(0xff);
+        assert!(G2Affine::decode_points(&bad, &mut out, true, false).is_err());
+    }
+
+    #[test]
+    fn bilinearity() {
+        let mut rng = thread_rng();
+        let (a, b) = (Fr::random(&mut rng), Fr::random(&mut rng));
+        let g = Gt::generator();
+        assert!(!bool::from(g.is_identity()));
+        assert!(bool::from((g * -Fr::one() + g).is_identity()));
+        let p = (G1Affine::generator() * a).to_affine();
+        let q = (G2Affine::generator() * b).to_affine();
+        assert_eq!(Bn254::pairing(&p, &q), g * (a * b));
+        assert_eq!(Bn254::pairing(&-p, &q), -Bn254::pairing(&p, &q));
+        assert_eq!(Bn254::pairing(&G1Affine::identity(), &q), Gt::identity());
+
+        let terms = [(&p, &G2Prepared::from(q)), (&-p, &G2Prepared::from(q))];
+        assert!(bool::from(Bn254::multi_miller_loop(&terms).final_exponentiation().is_identity()));
+    }
+
+    #[test]
+    fn pairing_off_the_curve() {
+        // (1, 0), which the unchecked decoding takes as it is. The tangent
+        // is vertical, so the affine loop divided by zero.
+        let mut bytes = G2Uncompressed::default();
+        bytes.as_mut()[63] = 1;
+        let q = G2Affine::from_uncompressed_unchecked(&bytes).unwrap();
+        assert!(!bool::from(q.is_on_curve()));
+        assert!(bool::from(G2Affine::from_uncompressed(&bytes).is_none()));
+
+        let p = G1Affine::generator();
+        assert_eq!(Bn254::pairing(&p, &q), Gt::identity());
+        let terms = [(&p, &G2Prepared::from(q)), (&p, &G2Prepared::from(G2Affine::generator()))];
+        assert_eq!(Bn254::multi_miller_loop(&terms).final_exponentiation(), Gt::generator());
+    }
+
+    // Points as the precompiles take them, which is the uncompressed encoding
+    fn decode<G: UncompressedEncoding>(bytes: &[u8]) -> Option<G> {
+        let mut repr = G::Uncompressed::default();
+        repr.as_mut().copy_from_slice(bytes);
+        G::from_uncompressed(&repr).into()
+    }
+
+    // Known answers of the ECADD, ECMUL and ECPAIRING precompiles of
+    // EIP-196 and EIP-197, as in the tests of revm-precompile. A real
+    // snarkjs proof is verified in the tests of `snarkjs` and `gnark`.
+    #[test]
+    fn eip_precompile_vectors() {
+        let add = unhex(concat!(
+            "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9",
+            "063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266",
+            "07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed",
+            "06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
+        ));
+        let sum = unhex(concat!(
+            "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703",
+            "301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
+        ));
+        let (p, q) = (decode::<G1Affine>(&add[..64]).unwrap(), decode::<G1Affine>(&add[64..]).unwrap());
+        assert_eq!((G1Projective::from(p) + q).to_affine().to_uncompressed().as_ref(), &sum[..]);
+
+        let mul = unhex(concat!(
+            "2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7",
+            "21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204",
+        ));
+        let product = unhex(concat!(
+            "070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c",
+            "031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
+        ));
+        let p = decode::<G1Affine>(&mul).unwrap();
+        assert_eq!((p * Fr::from(0x11138ce750fa15c2)).to_affine().to_uncompressed().as_ref(), &product[..]);
+
+        // e(p1, q1) * e(p2, q2) = 1, G2 coordinates as c1 | c0
+        let pairs = unhex(concat!(
+            "1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59",
+            "3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41",
+            "209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7",
+            "04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678",
+            "2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d",
+            "120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550",
+            "111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c",
+            "2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411",
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        ));
+        let (p1, q1) = (decode::<G1Affine>(&pairs[..64]).unwrap(), decode::<G2Affine>(&pairs[64..192]).unwrap());
+        let (p2, q2) = (decode::<G1Affine>(&pairs[192..256]).unwrap(), decode::<G2Affine>(&pairs[256..]).unwrap());
+        // The second G2 point is the generator
+        assert_eq!(q2, G2Affine::generator());
+        let terms = [(&p1, &G2Prepared::from(q1)), (&p2, &G2Prepared::from(q2))];
+        assert!(bool::from(Bn254::multi_miller_loop(&terms).final_exponentiation().is_identity()));
+        assert_eq!(Bn254::pairing(&p1, &q1), -Bn254::pairing(&p2, &q2));
+        assert!(!bool::from(Bn254::pairing(&p1, &q1).is_identity()));
+
+        // Not on the curve, and the identity as zeros
+        assert!(decode::<G1Affine>(&[0x11; 64]).is_none());
+        assert!(bool::from(decode::<G1Affine>(&[0; 64]).unwrap().is_identity()));
+    }
+
+    #[test]
+    fn bn254_roundtrip_verifies() {
+        let fixture = cube_fixture_on::<Bn254>(3);
+        let proof = fixture.prove();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+        assert!(verify_proof(&proof, &[Fr::from(36)], fixture.params.vk.clone()).is_err());
+
+        let json = snarkjs::encode_proof_json(&proof).unwrap();
+        assert!(json.contains("\"curve\": \"bn128\""));
+        let decoded = snarkjs::decode_proof_json::<Bn254>(&json).unwrap();
+        let vk = snarkjs::decode_vk_json::<Bn254>(&snarkjs::encode_vk_json(&fixture.params.vk).unwrap()).unwrap();
+        assert!(verify_proof(&decoded, fixture.public_inputs(), vk).is_ok());
+
+        let decoded = gnark::decode_proof::<Bn254>(&gnark::encode_proof(&proof, true), true).unwrap();
+        let vk = gnark::decode_vk::<Bn254>(&gnark::encode_vk(&fixture.params.vk, false), false).unwrap();
+        assert!(verify_proof(&decoded, fixture.public_inputs(), vk).is_ok());
+    }
+}
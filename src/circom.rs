@@ -0,0 +1,327 @@
+// Circuits and witnesses produced by circom, in the iden3 binary formats.
+//
+// Both formats are a magic, a version u32 and a number of sections, each a
+// type u32, a size u64 and the section data. Integers are little-endian and
+// field elements take n8 bytes, little-endian and in standard form.
+//
+// .r1cs, version 1:
+//   section 1, header: n8 u32 | prime | wires u32 | public outputs u32
+//     | public inputs u32 | private inputs u32 | labels u64 | constraints u32
+//   section 2, the constraints: A, B and C of each, as
+//     number of terms u32 | (wire u32 | coefficient) per term
+//   section 3, the label u64 of each wire. Only its length is used, to
+//     bound the number of wires by the size of the file.
+//
+// .wtns, version 2:
+//   section 1, header: n8 u32 | prime | number of values u32
+//   section 2, the value of each wire
+//
+// Wires are numbered the way the variables of a `QAP` are: wire 0 is the
+// constant one, followed by the public outputs and the public inputs, which
+// together make up `inputs`, then the private inputs and the internal wires,
+// which make up `aux`. Like `assignments::extract_circuit`, the QAP gets one
+// constraint input * 0 = 0 per public wire after the circuit's own, which
+// is also how snarkjs lays out its keys.
+//
+// The prime of both files has to be the modulus of the scalar field.
+
+use ff::PrimeField;
+
+use crate::{ DecodingError, QAP };
+use crate::encoding::Reader;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+// Sections of a file in the iden3 container, in file order
+pub(crate) fn sections<'a>(bytes: &'a [u8], magic: &[u8; 4], version: u32) -> Result<Vec<(u32, &'a [u8])>, DecodingError> {
+    let mut rd = Reader::new(bytes);
+    if rd.bytes(4)? != magic || rd.u32_le()? != version {
+        return Err(DecodingError::InvalidHeader);
+    }
+    let n = rd.u32_le()?;
+    let mut sections = Vec::new();
+    for _ in 0..n {
+        let id = rd.u32_le()?;
+        let len = usize::try_from(rd.u64_le()?).map_err(|_| DecodingError::UnexpectedEof)?;
+        sections.push((id, rd.bytes(len)?));
+    }
+    Ok(sections)
+}
+
+pub(crate) fn section<'a>(sections: &[(u32, &'a [u8])], id: u32) -> Result<&'a [u8], DecodingError> {
+    sections.iter()
+        .find(|(i, _)| *i == id)
+        .map(|(_, data)| *data)
+        .ok_or(DecodingError::InvalidHeader)
+}
+
+// Little-endian integer in S, reduced
+fn from_le<S: PrimeField>(b: &[u8]) -> S {
+    let base = S::from(256);
+    b.iter().rev().fold(S::zero(), |acc, x| acc * base + S::from(u64::from(*x)))
+}
+
+// Whether a < b, both little-endian of the same length
+fn less_le(a: &[u8], b: &[u8]) -> bool {
+    a.iter().rev().zip(b.iter().rev())
+        .find(|(x, y)| x != y)
+        .is_some_and(|(x, y)| x < y)
+}
+
+// A prime of the same bit length as the modulus of S that is zero in S is
+// the modulus itself
+pub(crate) fn check_prime<S: PrimeField>(prime: &[u8]) -> Result<(), DecodingError> {
+    let bits = prime.iter().rposition(|x| *x != 0)
+        .map_or(0, |i| 8 * i as u32 + 8 - prime[i].leading_zeros());
+    if bits != S::NUM_BITS || !bool::from(from_le::<S>(prime).is_zero()) {
+        return Err(DecodingError::Mismatch);
+    }
+    Ok(())
+}
+
+// Field element in standard form, rejected unless below the prime
+pub(crate) fn scalar_le<S: PrimeField>(b: &[u8], prime: &[u8]) -> Result<S, DecodingError> {
+    if !less_le(b, prime) {
+        return Err(DecodingError::InvalidFieldElement);
+    }
+    Ok(from_le(b))
+}
+
+// Field header shared by both formats: n8 and the prime
+fn read_field<'a, S: PrimeField>(rd: &mut Reader<'a>) -> Result<(usize, &'a [u8]), DecodingError> {
+    let n8 = rd.u32_le()? as usize;
+    let prime = rd.bytes(n8)?;
+    check_prime::<S>(prime)?;
+    Ok((n8, prime))
+}
+
+type Terms<S> = Vec<(usize, Vec<(S, usize)>)>;
+
+// Terms of the variables that have any, and their sorted indices
+fn collect<S: PrimeField>(terms: Vec<Vec<(S, usize)>>) -> (Terms<S>, Vec<usize>) {
+    let terms: Terms<S> = terms.into_iter().enumerate().filter(|(_, t)| !t.is_empty()).collect();
+    let variables = terms.iter().map(|(i, _)| *i).collect();
+    (terms, variables)
+}
+
+pub struct R1cs<S: PrimeField> {
+    pub qap: QAP<S>,
+    // Public wires, wire 0 included
+    pub num_inputs: usize,
+    pub num_aux: usize,
+}
+
+pub fn decode_r1cs<S: PrimeField>(bytes: &[u8]) -> Result<R1cs<S>, DecodingError> {
+    let sections = sections(bytes, b"r1cs", 1)?;
+
+    let mut rd = Reader::new(section(&sections, 1)?);
+    let (n8, prime) = read_field::<S>(&mut rd)?;
+    let num_wires = rd.u32_le()? as usize;
+    let num_inputs = 1 + rd.u32_le()? as usize + rd.u32_le()? as usize;
+    let _private_inputs = rd.u32_le()?;
+    let _labels = rd.u64_le()?;
+    let num_constraints = rd.u32_le()? as usize;
+    if num_inputs > num_wires {
+        return Err(DecodingError::Mismatch);
+    }
+    if section(&sections, 3)?.len() as u64 != 8 * num_wires as u64 {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    let mut polys = [vec![Vec::new(); num_wires], vec![Vec::new(); num_wires], vec![Vec::new(); num_wires]];
+    let mut rd = Reader::new(section(&sections, 2)?);
+    for j in 0..num_constraints {
+        for terms in polys.iter_mut() {
+            for _ in 0..rd.u32_le()? {
+                let wire = rd.u32_le()? as usize;
+                let coeff = scalar_le(rd.bytes(n8)?, prime)?;
+                terms.get_mut(wire).ok_or(DecodingError::Mismatch)?.push((coeff, j));
+            }
+        }
+    }
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    for (i, terms) in polys[0].iter_mut().take(num_inputs).enumerate() {
+        terms.push((S::one(), num_constraints + i));
+    }
+
+    let [a, b, c] = polys;
+    let (a, a_constraints) = collect(a);
+    let (b, b_constraints) = collect(b);
+    let (c, _) = collect(c);
+
+    Ok(R1cs {
+        qap: QAP { a, b, c, a_constraints, b_constraints },
+        num_inputs,
+        num_aux: num_wires - num_inputs,
+    })
+}
+
+// Splits a witness of `circuit` into the `inputs` and `aux` of
+// `prover::create_proof`
+pub fn decode_wtns<S: PrimeField>(bytes: &[u8], circuit: &R1cs<S>) -> Result<(Vec<S>, Vec<S>), DecodingError> {
+    let sections = sections(bytes, b"wtns", 2)?;
+
+    let mut rd = Reader::new(section(&sections, 1)?);
+    let (n8, prime) = read_field::<S>(&mut rd)?;
+    let n = rd.u32_le()? as usize;
+    if n != circuit.num_inputs + circuit.num_aux {
+        return Err(DecodingError::Mismatch);
+    }
+
+    let mut rd = Reader::new(section(&sections, 2)?);
+    let mut inputs = (0..n)
+        .map(|_| scalar_le(rd.bytes(n8)?, prime))
+        .collect::<Result<Vec<S>, _>>()?;
+    if inputs[0] != S::one() {
+        return Err(DecodingError::Mismatch);
+    }
+    let aux = inputs.split_off(circuit.num_inputs);
+    Ok((inputs, aux))
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::bn254::Fr;
+    use crate::prover::create_proof;
+    use crate::tests::{ container, cube_fixture, scalar_modulus };
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    fn bn254_prime() -> Vec<u8> {
+        let mut p = [
+            0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+            0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+        ];
+        p.reverse();
+        p.to_vec()
+    }
+
+    // `tests::CubeDemo` as circom would emit it: wires one, out, x, tmp_1
+    // and x_cubed
+    fn cube_r1cs(prime: &[u8]) -> Vec<u8> {
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend_from_slice(prime);
+        for x in [5u32, 1, 0, 1] {
+            header.extend_from_slice(&x.to_le_bytes());
+        }
+        header.extend_from_slice(&5u64.to_le_bytes());
+        header.extend_from_slice(&3u32.to_le_bytes());
+
+        let constraints: [[&[(u32, u64)]; 3]; 3] = [
+            [&[(2, 1)], &[(2, 1)], &[(3, 1)]],
+            [&[(3, 1)], &[(2, 1)], &[(4, 1)]],
+            [&[(4, 1), (2, 1), (0, 5)], &[(0, 1)], &[(1, 1)]],
+        ];
+        let mut body = Vec::new();
+        for lc in constraints.iter().flatten() {
+            body.extend_from_slice(&(lc.len() as u32).to_le_bytes());
+            for (wire, coeff) in lc.iter() {
+                body.extend_from_slice(&wire.to_le_bytes());
+                body.extend_from_slice(&Scalar::from(*coeff).to_repr());
+            }
+        }
+
+        let labels = (0..5u64).flat_map(u64::to_le_bytes).collect();
+        container(b"r1cs", 1, &[(1, header), (2, body), (3, labels)])
+    }
+
+    fn cube_wtns(prime: &[u8], values: &[Scalar]) -> Vec<u8> {
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend_from_slice(prime);
+        header.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        let body = values.iter().flat_map(|x| x.to_repr()).collect();
+        container(b"wtns", 2, &[(1, header), (2, body)])
+    }
+
+    #[test]
+    fn circom_cube_matches_bellman() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let prime = scalar_modulus();
+
+        let circuit = decode_r1cs::<Scalar>(&cube_r1cs(&prime)).unwrap();
+        assert_eq!(circuit.num_inputs, fixture.inputs.len());
+        assert_eq!(circuit.num_aux, fixture.aux.len());
+        assert_eq!(circuit.qap.num_constraints(), fixture.num_constraints);
+        assert_eq!(circuit.qap.a_constraints, fixture.qap.a_constraints);
+        assert_eq!(circuit.qap.b_constraints, fixture.qap.b_constraints);
+
+        let values: Vec<_> = [1, 35, 3, 9, 27].into_iter().map(Scalar::from).collect();
+        let (inputs, aux) = decode_wtns(&cube_wtns(&prime, &values), &circuit).unwrap();
+        assert_eq!(inputs, fixture.inputs);
+        assert_eq!(aux, fixture.aux);
+
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let proof = create_proof::<Bls12, _>(&fixture.params, &inputs, &aux, r, s, &circuit.qap, circuit.qap.num_constraints()).unwrap();
+        let expected = create_proof::<Bls12, _>(&fixture.params, &fixture.inputs, &fixture.aux, r, s, &fixture.qap, fixture.num_constraints).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+        assert!(verify_proof(&proof, &inputs[1..], fixture.params.vk.clone()).is_ok());
+    }
+
+    // Written by `snarkjs wtns calculate` for the benchmark circuit of
+    // ark-circom 0.1.0, from its test vectors. The circuit has one public
+    // output, which is the public.json of the proof snarkjs made with this
+    // witness. Its .r1cs is not part of the vectors, so only the number of
+    // wires of a circuit is given here.
+    #[test]
+    fn snarkjs_wtns_decodes() {
+        const WTNS: &[u8] = include_bytes!("testdata/snarkjs/complex_circuit.wtns");
+        let output = "20227169454906525228014700210166866282343639252280745415680311389428188660505";
+
+        let circuit = R1cs::<Fr> { qap: QAP::default(), num_inputs: 2, num_aux: 19_999 };
+        let (inputs, aux) = decode_wtns(WTNS, &circuit).unwrap();
+        assert_eq!(inputs, [Fr::one(), Fr::from_str_vartime(output).unwrap()]);
+        assert_eq!(aux.len(), 19_999);
+
+        let circuit = R1cs::<Fr> { qap: QAP::default(), num_inputs: 2, num_aux: 19_998 };
+        assert_eq!(decode_wtns(WTNS, &circuit).err(), Some(DecodingError::Mismatch));
+        let circuit = R1cs::<Scalar> { qap: QAP::default(), num_inputs: 2, num_aux: 19_999 };
+        assert_eq!(decode_wtns(WTNS, &circuit).err(), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn circom_rejects_bad_files() {
+        let prime = scalar_modulus();
+        let values: Vec<_> = [1, 35, 3, 9, 27].into_iter().map(Scalar::from).collect();
+        let circuit = decode_r1cs::<Scalar>(&cube_r1cs(&prime)).unwrap();
+
+        // Other field
+        assert_eq!(decode_r1cs::<Scalar>(&cube_r1cs(&bn254_prime())).err(), Some(DecodingError::Mismatch));
+        assert_eq!(decode_wtns(&cube_wtns(&bn254_prime(), &values), &circuit).err(), Some(DecodingError::Mismatch));
+
+        // Wire 0 is not one
+        let mut bad = values.clone();
+        bad[0] = Scalar::from(2);
+        assert_eq!(decode_wtns(&cube_wtns(&prime, &bad), &circuit).err(), Some(DecodingError::Mismatch));
+
+        // Wrong number of values
+        assert_eq!(decode_wtns(&cube_wtns(&prime, &values[..4]), &circuit).err(), Some(DecodingError::Mismatch));
+
+        // A value equal to the prime
+        let mut wtns = cube_wtns(&prime, &values);
+        let end = wtns.len();
+        wtns[end - 32..].copy_from_slice(&prime);
+        assert_eq!(decode_wtns(&wtns, &circuit).err(), Some(DecodingError::InvalidFieldElement));
+
+        let r1cs = cube_r1cs(&prime);
+        assert_eq!(decode_r1cs::<Scalar>(&r1cs[..r1cs.len() - 1]).err(), Some(DecodingError::UnexpectedEof));
+
+        // Billions of wires in a file of a few hundred bytes
+        let mut bad = r1cs.clone();
+        let wires = 12 + 12 + 4 + 32;
+        bad[wires..wires + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode_r1cs::<Scalar>(&bad).err(), Some(DecodingError::InvalidHeader));
+        assert_eq!(decode_wtns(&r1cs, &circuit).err(), Some(DecodingError::InvalidHeader));
+    }
+}
@@ -0,0 +1,194 @@
+// Affine coordinates of the points of an Engine, for the formats of other
+// tools, which store points as coordinate pairs.
+//
+// Base field elements are big-endian integers of `FQ_LEN` bytes, below
+// `FQ_MODULUS`. An element c0 + c1 * u of the quadratic extension that G2
+// lives in is the pair [c0, c1]. The identity has no affine coordinates:
+// the getters return `None` for it and the constructors never produce it.
+// Points are checked to be on the curve and in the prime-order subgroup.
+
+use pairing::Engine;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::vec::Vec;
+
+pub trait Coordinates: Engine {
+    const FQ_LEN: usize;
+
+    // Modulus of the base field, big-endian
+    const FQ_MODULUS: &'static [u8];
+
+    fn g1_from_coordinates(x: &[u8], y: &[u8]) -> Option<Self::G1Affine>;
+
+    fn g1_coordinates(p: &Self::G1Affine) -> Option<[Vec<u8>; 2]>;
+
+    fn g2_from_coordinates(x: [&[u8]; 2], y: [&[u8]; 2]) -> Option<Self::G2Affine>;
+
+    fn g2_coordinates(p: &Self::G2Affine) -> Option<[[Vec<u8>; 2]; 2]>;
+}
+
+// Base field moduli of the curves other tools know by name
+pub(crate) const BLS12_381_FQ: &[u8] = &[
+    0x1a, 0x01, 0x11, 0xea, 0x39, 0x7f, 0xe6, 0x9a, 0x4b, 0x1b, 0xa7, 0xb6, 0x43, 0x4b, 0xac, 0xd7,
+    0x64, 0x77, 0x4b, 0x84, 0xf3, 0x85, 0x12, 0xbf, 0x67, 0x30, 0xd2, 0xa0, 0xf6, 0xb0, 0xf6, 0x24,
+    0x1e, 0xab, 0xff, 0xfe, 0xb1, 0x53, 0xff, 0xff, 0xb9, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xab,
+];
+
+pub(crate) const BN254_FQ: &[u8] = &[
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+// The uncompressed encoding of bls12_381 is x | y, with the coefficients
+// of G2 coordinates in the order c1 | c0, and flags in the top three bits,
+// which are clear for any point other than the identity
+#[cfg(any(test, feature = "bls12_381"))]
+mod bls12 {
+    use bls12_381::{ Bls12, G1Affine, G2Affine };
+
+    use super::*;
+
+    const LEN: usize = 48;
+
+    fn concat<const N: usize>(parts: &[&[u8]]) -> Option<[u8; N]> {
+        if parts.iter().any(|p| p.len() != LEN || p[0] & 0xe0 != 0) {
+            return None;
+        }
+        let mut out = [0; N];
+        for (chunk, p) in out.chunks_mut(LEN).zip(parts) {
+            chunk.copy_from_slice(p);
+        }
+        Some(out)
+    }
+
+    impl Coordinates for Bls12 {
+        const FQ_LEN: usize = LEN;
+
+        const FQ_MODULUS: &'static [u8] = BLS12_381_FQ;
+
+        fn g1_from_coordinates(x: &[u8], y: &[u8]) -> Option<G1Affine> {
+            Option::from(G1Affine::from_uncompressed(&concat(&[x, y])?))
+        }
+
+        fn g1_coordinates(p: &G1Affine) -> Option<[Vec<u8>; 2]> {
+            if bool::from(p.is_identity()) {
+                return None;
+            }
+            let b = p.to_uncompressed();
+            Some([b[..LEN].to_vec(), b[LEN..].to_vec()])
+        }
+
+        fn g2_from_coordinates(x: [&[u8]; 2], y: [&[u8]; 2]) -> Option<G2Affine> {
+            Option::from(G2Affine::from_uncompressed(&concat(&[x[1], x[0], y[1], y[0]])?))
+        }
+
+        fn g2_coordinates(p: &G2Affine) -> Option<[[Vec<u8>; 2]; 2]> {
+            if bool::from(p.is_identity()) {
+                return None;
+            }
+            let b = p.to_uncompressed();
+            let c = |i: usize| b[i * LEN..(i + 1) * LEN].to_vec();
+            Some([[c(1), c(0)], [c(3), c(2)]])
+        }
+    }
+}
+
+// The uncompressed encoding of the bn254 module is gnark's: x | y with G2
+// coefficients as c1 | c0, flags in the top two bits, and all zeros for
+// the identity, which these constructors must not accept
+#[cfg(any(test, feature = "bn254"))]
+mod bn254 {
+    use crate::bn254::{ Bn254, G1Affine, G1Uncompressed, G2Affine, G2Uncompressed };
+
+    use super::*;
+
+    const LEN: usize = 32;
+
+    fn concat<T: Default + AsMut<[u8]>>(parts: &[&[u8]]) -> Option<T> {
+        if parts.iter().any(|p| p.len() != LEN) || parts.iter().all(|p| p.iter().all(|b| *b == 0)) {
+            return None;
+        }
+        let mut out = T::default();
+        for (chunk, p) in out.as_mut().chunks_mut(LEN).zip(parts) {
+            chunk.copy_from_slice(p);
+        }
+        Some(out)
+    }
+
+    impl Coordinates for Bn254 {
+        const FQ_LEN: usize = LEN;
+
+        const FQ_MODULUS: &'static [u8] = BN254_FQ;
+
+        fn g1_from_coordinates(x: &[u8], y: &[u8]) -> Option<G1Affine> {
+            Option::from(G1Affine::from_uncompressed(&concat::<G1Uncompressed>(&[x, y])?))
+        }
+
+        fn g1_coordinates(p: &G1Affine) -> Option<[Vec<u8>; 2]> {
+            if bool::from(p.is_identity()) {
+                return None;
+            }
+            let b = p.to_uncompressed();
+            Some([b.as_ref()[..LEN].to_vec(), b.as_ref()[LEN..].to_vec()])
+        }
+
+        fn g2_from_coordinates(x: [&[u8]; 2], y: [&[u8]; 2]) -> Option<G2Affine> {
+            Option::from(G2Affine::from_uncompressed(&concat::<G2Uncompressed>(&[x[1], x[0], y[1], y[0]])?))
+        }
+
+        fn g2_coordinates(p: &G2Affine) -> Option<[[Vec<u8>; 2]; 2]> {
+            if bool::from(p.is_identity()) {
+                return None;
+            }
+            let b = p.to_uncompressed();
+            let c = |i: usize| b.as_ref()[i * LEN..(i + 1) * LEN].to_vec();
+            Some([[c(1), c(0)], [c(3), c(2)]])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, G1Affine, G2Affine };
+
+    use super::*;
+
+    #[test]
+    fn bls12_coordinates_roundtrip() {
+        let g1 = G1Affine::generator();
+        let [x, y] = Bls12::g1_coordinates(&g1).unwrap();
+        assert_eq!(x.len(), Bls12::FQ_LEN);
+        // x of the generator, as in the bls12_381 specification
+        assert_eq!(&x[..4], &[0x17, 0xf1, 0xd3, 0xa7]);
+        assert_eq!(Bls12::g1_from_coordinates(&x, &y), Some(g1));
+        assert_eq!(Bls12::g1_from_coordinates(&y, &x), None);
+        assert_eq!(Bls12::g1_coordinates(&G1Affine::identity()), None);
+
+        let g2 = G2Affine::generator();
+        let [x, y] = Bls12::g2_coordinates(&g2).unwrap();
+        assert_eq!(&x[0][..4], &[0x02, 0x4a, 0xa2, 0xb2]);
+        assert_eq!(Bls12::g2_from_coordinates([&x[0], &x[1]], [&y[0], &y[1]]), Some(g2));
+        assert_eq!(Bls12::g2_from_coordinates([&x[1], &x[0]], [&y[0], &y[1]]), None);
+    }
+
+    #[test]
+    fn bn254_coordinates_roundtrip() {
+        use crate::bn254::{ Bn254, G1Affine, G2Affine };
+
+        let g1 = G1Affine::generator();
+        let [x, y] = Bn254::g1_coordinates(&g1).unwrap();
+        assert_eq!(x[31], 1);
+        assert_eq!(y[31], 2);
+        assert_eq!(Bn254::g1_from_coordinates(&x, &y), Some(g1));
+        assert_eq!(Bn254::g1_from_coordinates(&y, &x), None);
+        assert_eq!(Bn254::g1_from_coordinates(&[0; 32], &[0; 32]), None);
+        assert_eq!(Bn254::g1_from_coordinates(BN254_FQ, &y), None);
+
+        let g2 = G2Affine::generator();
+        let [x, y] = Bn254::g2_coordinates(&g2).unwrap();
+        // x.c0 of the generator, as in EIP-197
+        assert_eq!(&x[0][..4], &[0x18, 0x00, 0xde, 0xef]);
+        assert_eq!(Bn254::g2_from_coordinates([&x[0], &x[1]], [&y[0], &y[1]]), Some(g2));
+        assert_eq!(Bn254::g2_from_coordinates([&x[1], &x[0]], [&y[0], &y[1]]), None);
+    }
+}
@@ -0,0 +1,183 @@
+// Constant-time proving path, for devices whose timing or power draw can be
+// observed by whoever holds them.
+//
+// `create_proof` in this module runs the same computation as
+// `prover::create_proof`, but every multiplication of a group element by
+// witness data, by a coefficient of h(X) or by r and s goes through a
+// fixed-window scalar multiplication. That multiplication always processes
+// every window of the scalar, always performs the same sequence of
+// doublings and additions, and reads its table through a linear scan with
+// `subtle::ConditionallySelectable`, so neither the control flow nor the
+// memory access pattern depends on the scalar. The MSMs are plain sums of
+// such products, without any bucket or sparsity tricks.
+//
+// The rest of the prover already only branches and indexes on public data:
+// the QAP evaluation and the FFTs walk the constraint structure and the
+// domain size, and witness values only ever enter field additions and
+// multiplications.
+//
+// Operations that remain variable-time:
+// - `fft_params`, the FFT twiddle factors and the vanishing polynomial
+//   inverse, which use `pow_vartime` and `invert` on public domain values
+// - the traversal of the QAP, the query vectors and the domain, whose shape
+//   (lengths, sparsity pattern, constraint indices) is public
+// - allocation of the evaluation buffers, whose sizes are public
+// - the length assertions on the parameters
+// - the final conversions of A, B and C to affine form, which happen on
+//   values that are about to be published anyway
+//
+// The guarantees also rest on the Engine: field arithmetic on `E::Fr`,
+// `PrimeFieldBits::to_le_bits` and the group law on `E::G1`/`E::G2` must
+// be constant-time themselves, including additions involving the identity.
+// This holds for `bls12_381`, which uses complete addition formulas.
+
+use core::convert::Infallible;
+
+use pairing::Engine;
+use pairing::group::Group;
+use ff::PrimeFieldBits;
+use subtle::{ ConditionallySelectable, ConstantTimeEq };
+
+use crate::{ Proof, Parameters, QAP };
+use crate::backend::Backend;
+use crate::prover::{ create_proof_with_backend, ProvingError };
+
+const WINDOW: usize = 4;
+
+// Fixed-window scalar multiplication with a constant operation sequence
+pub fn mul<G, S>(base: G, scalar: &S) -> G
+where
+    G: Group + ConditionallySelectable,
+    S: PrimeFieldBits,
+{
+    let mut table = [G::identity(); 1 << WINDOW];
+    for i in 1..table.len() {
+        table[i] = table[i - 1] + base;
+    }
+
+    let bits = scalar.to_le_bits();
+    let num_bits = S::NUM_BITS as usize;
+    let windows = num_bits.div_ceil(WINDOW);
+
+    let mut acc = G::identity();
+    for w in (0..windows).rev() {
+        for _ in 0..WINDOW {
+            acc = acc.double();
+        }
+
+        let mut idx = 0u8;
+        for j in 0..WINDOW {
+            let k = w * WINDOW + j;
+            if k < num_bits {
+                idx |= (bits[k] as u8) << j;
+            }
+        }
+
+        let mut t = G::identity();
+        for (i, p) in table.iter().enumerate() {
+            t.conditional_assign(p, (i as u8).ct_eq(&idx));
+        }
+        acc += t;
+    }
+
+    acc
+}
+
+// Backend whose MSMs are sums of `mul` products, as the default MSMs of
+// `Backend` are
+struct ConstTime;
+
+impl<E> Backend<E> for ConstTime
+where
+    E: Engine,
+    E::G1: ConditionallySelectable,
+    E::G2: ConditionallySelectable,
+    E::Fr: PrimeFieldBits,
+{
+    fn mul_g1(&self, p: E::G1, s: &E::Fr) -> E::G1 {
+        mul(p, s)
+    }
+
+    fn mul_g2(&self, p: E::G2, s: &E::Fr) -> E::G2 {
+        mul(p, s)
+    }
+}
+
+pub fn create_proof<E>(
+    params: Parameters<E>,
+    inputs: &[E::Fr],
+    aux: &[E::Fr],
+    r: E::Fr,
+    s: E::Fr,
+    qap: QAP<E::Fr>,
+    num_constraints: usize
+) -> Result<Proof<E>, ProvingError<Infallible>>
+where
+    E: Engine,
+    E::G1: ConditionallySelectable,
+    E::G2: ConditionallySelectable,
+    E::Fr: PrimeFieldBits,
+{
+    create_proof_with_backend(&ConstTime, params, inputs, aux, r, s, qap, num_constraints)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::{ Mul, Neg };
+
+    use bls12_381::{ Bls12, G1Projective, G2Projective, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::prover;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn mul_matches_engine() {
+        let mut rng = thread_rng();
+        let g1 = G1Projective::random(&mut rng);
+        let g2 = G2Projective::random(&mut rng);
+
+        let mut scalars = vec![Scalar::zero(), Scalar::one(), Scalar::one().neg(), Scalar::from(16)];
+        scalars.extend((0..8).map(|_| Scalar::random(&mut rng)));
+
+        for s in scalars.iter() {
+            assert_eq!(mul(g1, s), g1.mul(s));
+            assert_eq!(mul(g2, s), g2.mul(s));
+        }
+    }
+
+    #[test]
+    fn ct_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let proof = create_proof::<Bls12>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        let expected = prover::create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+    }
+}
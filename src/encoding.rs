@@ -0,0 +1,115 @@
+// Minimal byte codec shared by the serializable types of this crate.
+// Integers are big-endian, field elements use their `PrimeField::Repr`
+// and curve points their compressed `GroupEncoding`. The little-endian
+// readers are for the formats of other tools.
+
+use ff::PrimeField;
+use pairing::group::GroupEncoding;
+
+use crate::DecodingError;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::vec::Vec;
+
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer { buf: Vec::new() }
+    }
+
+    pub fn bytes(&mut self, b: &[u8]) {
+        self.buf.extend_from_slice(b);
+    }
+
+    pub fn u8(&mut self, x: u8) {
+        self.buf.push(x);
+    }
+
+    pub fn u32(&mut self, x: u32) {
+        self.bytes(&x.to_be_bytes());
+    }
+
+    pub fn u64(&mut self, x: u64) {
+        self.bytes(&x.to_be_bytes());
+    }
+
+    pub fn scalar<S: PrimeField>(&mut self, x: &S) {
+        self.bytes(x.to_repr().as_ref());
+    }
+
+    pub fn point<G: GroupEncoding>(&mut self, p: &G) {
+        self.bytes(p.to_bytes().as_ref());
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], DecodingError> {
+        if self.buf.len() < n {
+            return Err(DecodingError::UnexpectedEof);
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodingError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DecodingError> {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_be_bytes(b))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DecodingError> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(b))
+    }
+
+    pub fn u32_le(&mut self) -> Result<u32, DecodingError> {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(b))
+    }
+
+    pub fn u64_le(&mut self) -> Result<u64, DecodingError> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(b))
+    }
+
+    pub fn scalar<S: PrimeField>(&mut self) -> Result<S, DecodingError> {
+        let mut repr = S::Repr::default();
+        let n = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.bytes(n)?);
+        Option::from(S::from_repr(repr)).ok_or(DecodingError::InvalidFieldElement)
+    }
+
+    pub fn point<G: GroupEncoding>(&mut self) -> Result<G, DecodingError> {
+        let mut repr = G::Repr::default();
+        let n = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.bytes(n)?);
+        Option::from(G::from_bytes(&repr)).ok_or(DecodingError::InvalidPoint)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
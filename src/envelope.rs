@@ -0,0 +1,261 @@
+// Self-describing proofs, which say which curve and verification key they
+// are for, so that a proof checked against the wrong key is rejected as
+// such instead of failing the pairing check.
+//
+// Byte layout of an envelope, integers big-endian:
+//
+//   magic "EGPE" | version u8 | curve u8 | 2 reserved bytes
+//   fingerprint of the verification key, 32 bytes
+//   number of public inputs u32 | public inputs
+//   a | b | c
+//
+// Public inputs are `PrimeField` representations and points are
+// compressed. Curves are numbered by `curve_id`, after their base field
+// like the curve names of `snarkjs`: 1 for BLS12-381 and 2 for BN254.
+//
+// The fingerprint is the Keccak-256 hash of the elements of the
+// verification key that verification reads, with compressed points:
+//
+//   alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | number of ic points u32 | ic
+//
+// beta_g1 and delta_g1 are left out, as verification does not read them:
+// keys that accept the same proofs have the same fingerprint.
+//
+// `verify_envelope` reads the header and compares the fingerprint before
+// it decodes any point, so a wrong curve or key costs neither square
+// roots nor pairings.
+
+use pairing::Engine;
+
+use crate::{ DecodingError, Proof, VerificationError, VerificationKey };
+use crate::coordinates::{ BLS12_381_FQ, BN254_FQ, Coordinates };
+use crate::encoding::{ Reader, Writer };
+use crate::keccak::keccak256;
+use crate::verifier::verify_proof;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::vec::Vec;
+
+const MAGIC: &[u8; 4] = b"EGPE";
+const VERSION: u8 = 1;
+
+pub const BLS12_381: u8 = 1;
+pub const BN254: u8 = 2;
+
+const CURVES: [(u8, &[u8]); 2] = [(BLS12_381, BLS12_381_FQ), (BN254, BN254_FQ)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeError {
+    Decoding(DecodingError),
+    // The envelope is for another curve, whose number it holds
+    WrongCurve(u8),
+    // The envelope is for another verification key
+    WrongKey,
+    Verification(VerificationError),
+}
+
+impl From<DecodingError> for EnvelopeError {
+    fn from(e: DecodingError) -> Self {
+        EnvelopeError::Decoding(e)
+    }
+}
+
+impl From<VerificationError> for EnvelopeError {
+    fn from(e: VerificationError) -> Self {
+        EnvelopeError::Verification(e)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Envelope<E: Engine> {
+    pub fingerprint: [u8; 32],
+    pub public_inputs: Vec<E::Fr>,
+    pub proof: Proof<E>,
+}
+
+impl<E: Engine> Envelope<E> {
+    pub fn new(proof: Proof<E>, public_inputs: &[E::Fr], vk: &VerificationKey<E>) -> Self {
+        Envelope { fingerprint: fingerprint(vk), public_inputs: public_inputs.to_vec(), proof }
+    }
+
+    // Checks the fingerprint, then the proof
+    pub fn verify(&self, vk: &VerificationKey<E>) -> Result<(), EnvelopeError> {
+        if self.fingerprint != fingerprint(vk) {
+            return Err(EnvelopeError::WrongKey);
+        }
+        verify_proof(&self.proof, &self.public_inputs, vk.clone())?;
+        Ok(())
+    }
+}
+
+// Number of the curve of E in envelopes, None for curves without one
+pub fn curve_id<E: Coordinates>() -> Option<u8> {
+    CURVES.iter()
+        .find(|(_, q)| *q == E::FQ_MODULUS)
+        .map(|(id, _)| *id)
+}
+
+pub fn fingerprint<E: Engine>(vk: &VerificationKey<E>) -> [u8; 32] {
+    let mut w = Writer::new();
+    w.point(&vk.alpha_g1);
+    w.point(&vk.beta_g2);
+    w.point(&vk.gamma_g2);
+    w.point(&vk.delta_g2);
+    w.u32(vk.ic.len() as u32);
+    for p in vk.ic.iter() {
+        w.point(p);
+    }
+    keccak256(&w.finish())
+}
+
+// `Mismatch` for curves without a number
+pub fn encode_envelope<E: Coordinates>(envelope: &Envelope<E>) -> Result<Vec<u8>, DecodingError> {
+    let mut w = Writer::new();
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    w.u8(curve_id::<E>().ok_or(DecodingError::Mismatch)?);
+    w.bytes(&[0, 0]);
+    w.bytes(&envelope.fingerprint);
+    w.u32(envelope.public_inputs.len() as u32);
+    for x in envelope.public_inputs.iter() {
+        w.scalar(x);
+    }
+    w.point(&envelope.proof.a);
+    w.point(&envelope.proof.b);
+    w.point(&envelope.proof.c);
+    Ok(w.finish())
+}
+
+// The curve number and fingerprint, leaving the reader at the inputs
+pub(crate) fn decode_header(rd: &mut Reader) -> Result<(u8, [u8; 32]), DecodingError> {
+    if rd.bytes(4)? != MAGIC || rd.u8()? != VERSION {
+        return Err(DecodingError::InvalidHeader);
+    }
+    let curve = rd.u8()?;
+    if rd.bytes(2)? != [0, 0] {
+        return Err(DecodingError::InvalidHeader);
+    }
+    let mut fingerprint = [0; 32];
+    fingerprint.copy_from_slice(rd.bytes(32)?);
+    Ok((curve, fingerprint))
+}
+
+fn decode_body<E: Engine>(rd: &mut Reader, fingerprint: [u8; 32]) -> Result<Envelope<E>, DecodingError> {
+    let n = rd.u32()? as usize;
+    let mut public_inputs = Vec::new();
+    for _ in 0..n {
+        public_inputs.push(rd.scalar()?);
+    }
+    let proof = Proof { a: rd.point()?, b: rd.point()?, c: rd.point()? };
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(Envelope { fingerprint, public_inputs, proof })
+}
+
+// Decodes an envelope, with `Mismatch` if it is for another curve or E
+// has no number
+pub fn decode_envelope<E: Coordinates>(bytes: &[u8]) -> Result<Envelope<E>, DecodingError> {
+    let mut rd = Reader::new(bytes);
+    let (curve, fingerprint) = decode_header(&mut rd)?;
+    if Some(curve) != curve_id::<E>() {
+        return Err(DecodingError::Mismatch);
+    }
+    decode_body(&mut rd, fingerprint)
+}
+
+// Checks an envelope against `vk` and returns what it holds
+pub fn verify_envelope<E: Coordinates>(bytes: &[u8], vk: &VerificationKey<E>) -> Result<Envelope<E>, EnvelopeError> {
+    let mut rd = Reader::new(bytes);
+    let (curve, key) = decode_header(&mut rd)?;
+    if Some(curve) != curve_id::<E>() {
+        return Err(EnvelopeError::WrongCurve(curve));
+    }
+    if key != fingerprint(vk) {
+        return Err(EnvelopeError::WrongKey);
+    }
+    let envelope = decode_body(&mut rd, key)?;
+    verify_proof(&envelope.proof, &envelope.public_inputs, vk.clone())?;
+    Ok(envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+
+    use crate::bn254::Bn254;
+    use crate::tests::{ cube_fixture, cube_fixture_on };
+    use super::*;
+
+    #[test]
+    fn envelope_roundtrip_verifies() {
+        let fixture = cube_fixture(3);
+        let vk = &fixture.params.vk;
+        let envelope = Envelope::new(fixture.prove(), fixture.public_inputs(), vk);
+        let bytes = encode_envelope(&envelope).unwrap();
+        assert_eq!(&bytes[..8], b"EGPE\x01\x01\x00\x00");
+        assert_eq!(&bytes[8..40], &fingerprint(vk));
+        assert_eq!(bytes.len(), 40 + 4 + 32 + 2 * 48 + 96);
+
+        let decoded = decode_envelope::<Bls12>(&bytes).unwrap();
+        assert_eq!(decoded.public_inputs, fixture.public_inputs());
+        assert_eq!(decoded.proof.b, envelope.proof.b);
+        let verified = verify_envelope(&bytes, vk).unwrap();
+        assert_eq!(verified.public_inputs, fixture.public_inputs());
+
+        // Right key, wrong statement
+        let wrong = Envelope::new(envelope.proof.clone(), &[Scalar::from(36)], vk);
+        let result = verify_envelope(&encode_envelope(&wrong).unwrap(), vk);
+        assert_eq!(result.err(), Some(EnvelopeError::Verification(VerificationError::InvalidProof)));
+    }
+
+    #[test]
+    fn envelope_rejects_other_keys_and_curves() {
+        let fixture = cube_fixture(3);
+        let bytes = encode_envelope(&Envelope::new(fixture.prove(), fixture.public_inputs(), &fixture.params.vk)).unwrap();
+
+        let other = cube_fixture(3);
+        assert_ne!(fingerprint(&other.params.vk), fingerprint(&fixture.params.vk));
+        assert_eq!(verify_envelope(&bytes, &other.params.vk).err(), Some(EnvelopeError::WrongKey));
+
+        let bn = cube_fixture_on::<Bn254>(3);
+        assert_eq!(verify_envelope(&bytes, &bn.params.vk).err(), Some(EnvelopeError::WrongCurve(1)));
+        assert_eq!(decode_envelope::<Bn254>(&bytes).err(), Some(DecodingError::Mismatch));
+        let bn_bytes = encode_envelope(&Envelope::new(bn.prove(), bn.public_inputs(), &bn.params.vk)).unwrap();
+        assert_eq!(bn_bytes[5], 2);
+        assert_eq!((curve_id::<Bls12>(), curve_id::<Bn254>()), (Some(BLS12_381), Some(BN254)));
+        assert!(verify_envelope(&bn_bytes, &bn.params.vk).is_ok());
+
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert_eq!(decode_envelope::<Bls12>(&bad).err(), Some(DecodingError::InvalidHeader));
+        assert_eq!(decode_envelope::<Bls12>(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::UnexpectedEof));
+        assert_eq!(decode_envelope::<Bls12>(&[&bytes[..], &[0]].concat()).err(), Some(DecodingError::InvalidHeader));
+    }
+
+    #[test]
+    fn fingerprint_covers_the_verifying_elements() {
+        let fixture = cube_fixture(3);
+        let vk = &fixture.params.vk;
+        let other = &cube_fixture(3).params.vk;
+        let envelope = Envelope::new(fixture.prove(), fixture.public_inputs(), vk);
+
+        // The prover's elements do not change what verifies
+        let same = VerificationKey { beta_g1: other.beta_g1, delta_g1: other.delta_g1, ..vk.clone() };
+        assert_eq!(fingerprint(&same), fingerprint(vk));
+        assert_eq!(envelope.verify(&same), Ok(()));
+
+        let keys = [
+            VerificationKey { alpha_g1: other.alpha_g1, ..vk.clone() },
+            VerificationKey { beta_g2: other.beta_g2, ..vk.clone() },
+            VerificationKey { gamma_g2: other.gamma_g2, ..vk.clone() },
+            VerificationKey { delta_g2: other.delta_g2, ..vk.clone() },
+            VerificationKey { ic: other.ic.clone(), ..vk.clone() },
+            VerificationKey { ic: vk.ic[..1].to_vec(), ..vk.clone() },
+        ];
+        for key in keys.iter() {
+            assert_ne!(fingerprint(key), fingerprint(vk));
+            assert_eq!(envelope.verify(key), Err(EnvelopeError::WrongKey));
+        }
+    }
+}
@@ -0,0 +1,412 @@
+// Fixed-base precomputation for the prover.
+//
+// The query points never change between proofs, so the products with
+// witness data and with r and s can be looked up instead of computed. For a
+// base P and a window of w bits, the table of P holds k * 2^(w*j) * P for
+// every window j of a scalar and every digit 1 <= k < 2^w. A product is
+// then the sum of one table entry per nonzero digit, with no doublings,
+// at the cost of (2^w - 1) * ceil(NUM_BITS / w) points per base.
+//
+// `FixedBaseTables` covers the h, l and a queries and delta_g1, delta_g2
+// and beta_g1. `PrecomputedParameters` bundles them with the `Parameters`
+// they were built from; as a `ParameterSource` it hands the tables to the
+// prover, which then uses them in place of variable-base multiplication.
+//
+// Table lookups are indexed by the digits of the scalar and therefore not
+// constant-time. `ct::create_proof` only takes plain `Parameters` and never
+// uses them.
+//
+// Tables take long to build and far more space than the key, so they can be
+// stored and loaded. Byte layout of encoded tables, integers big-endian:
+//
+//   magic "EGFB" | version u8 | window u8 | 2 reserved bytes
+//   number of points of h, l and a, u32 each
+//   the tables of h, l, a, delta_g1, delta_g2 and beta_g1, uncompressed
+//
+// Decoding checks that every entry is a point of the prime order subgroup,
+// but not that it is the right multiple of its base, which would take as
+// long as building the tables. `PrecomputedParameters::from_bytes` does
+// check that the tables start with the bases of its key.
+
+use core::convert::Infallible;
+use core::ops::AddAssign;
+
+use ff::{ PrimeField, PrimeFieldBits };
+use pairing::Engine;
+use pairing::group::prime::{ PrimeCurve, PrimeCurveAffine };
+
+use crate::{ DecodingError, Parameters, VerificationKey };
+use crate::encoding::{ Reader, Writer };
+use crate::source::{ g1_len, g2_len, query_g1, query_len, read_point, write_point, ParameterSource, Query };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+const MAGIC: &[u8; 4] = b"EGFB";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 20;
+
+// Products with fixed bases, as used by the prover
+pub trait FixedBase<E: Engine> {
+    // Whether the tables cover a query
+    fn covers(&self, query: Query) -> bool;
+
+    // s times point i of a covered query
+    fn mul_query(&self, query: Query, i: usize, s: &E::Fr) -> E::G1;
+
+    fn mul_delta_g1(&self, s: &E::Fr) -> E::G1;
+
+    fn mul_delta_g2(&self, s: &E::Fr) -> E::G2;
+
+    fn mul_beta_g1(&self, s: &E::Fr) -> E::G1;
+}
+
+pub struct FixedBaseTables<E: Engine> {
+    window: usize,
+    h: Vec<E::G1Affine>,
+    l: Vec<E::G1Affine>,
+    a: Vec<E::G1Affine>,
+    delta_g1: Vec<E::G1Affine>,
+    delta_g2: Vec<E::G2Affine>,
+    beta_g1: Vec<E::G1Affine>,
+}
+
+// Number of table entries per base
+fn stride<S: PrimeFieldBits>(window: usize) -> usize {
+    ((1 << window) - 1) * (S::NUM_BITS as usize).div_ceil(window)
+}
+
+fn build<G: PrimeCurve>(bases: &[G::Affine], window: usize) -> Vec<G::Affine>
+where
+    G::Scalar: PrimeFieldBits,
+{
+    let digits = (1 << window) - 1;
+    let stride = stride::<G::Scalar>(window);
+    let mut table = vec![G::identity(); bases.len() * stride];
+
+    for (base, entries) in bases.iter().zip(table.chunks_mut(stride)) {
+        let mut p = base.to_curve();
+        for entries in entries.chunks_mut(digits) {
+            let mut acc = p;
+            for e in entries.iter_mut() {
+                *e = acc;
+                acc += p;
+            }
+            // acc is now 2^w * p, the base of the next window
+            p = acc;
+        }
+    }
+
+    let mut affine = vec![G::identity().to_affine(); table.len()];
+    G::batch_normalize(&table, &mut affine);
+    affine
+}
+
+fn lookup<G>(table: &[G::Affine], window: usize, s: &G::Scalar) -> G
+where
+    G: PrimeCurve + AddAssign<G::Affine>,
+    G::Scalar: PrimeFieldBits,
+{
+    let bits = s.to_le_bits();
+    let num_bits = G::Scalar::NUM_BITS as usize;
+    let digits = (1 << window) - 1;
+
+    let mut acc = G::identity();
+    for (j, start) in (0..num_bits).step_by(window).enumerate() {
+        let mut d = 0;
+        for (k, bit) in bits[start..num_bits.min(start + window)].iter().enumerate() {
+            d |= (*bit as usize) << k;
+        }
+        if d != 0 {
+            acc += table[j * digits + d - 1];
+        }
+    }
+    acc
+}
+
+impl<E: Engine> FixedBaseTables<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    // Builds the tables for a window of 1 to 8 bits. A window of 4 takes
+    // 15 * 64 points per base for a 255-bit scalar field and replaces each
+    // multiplication by at most 64 additions.
+    pub fn new(params: &Parameters<E>, window: usize) -> Self {
+        assert!((1..=8).contains(&window));
+        let vk = &params.vk;
+        FixedBaseTables {
+            window,
+            h: build::<E::G1>(&params.h, window),
+            l: build::<E::G1>(&params.l, window),
+            a: build::<E::G1>(&params.a_g1, window),
+            delta_g1: build::<E::G1>(&[vk.delta_g1], window),
+            delta_g2: build::<E::G2>(&[vk.delta_g2], window),
+            beta_g1: build::<E::G1>(&[vk.beta_g1], window),
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    // Encodes the tables in the layout described above
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let stride = stride::<E::Fr>(self.window);
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u8(VERSION);
+        w.u8(self.window as u8);
+        w.bytes(&[0, 0]);
+        for t in [&self.h, &self.l, &self.a] {
+            w.u32((t.len() / stride) as u32);
+        }
+        for p in self.h.iter().chain(&self.l).chain(&self.a).chain(&self.delta_g1) {
+            write_point(&mut w, p, false);
+        }
+        for p in self.delta_g2.iter() {
+            write_point(&mut w, p, false);
+        }
+        for p in self.beta_g1.iter() {
+            write_point(&mut w, p, false);
+        }
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        if rd.bytes(4)? != MAGIC || rd.u8()? != VERSION {
+            return Err(DecodingError::InvalidHeader);
+        }
+        let window = rd.u8()? as usize;
+        rd.bytes(2)?;
+        if !(1..=8).contains(&window) {
+            return Err(DecodingError::InvalidHeader);
+        }
+        let counts = [rd.u32()? as u64, rd.u32()? as u64, rd.u32()? as u64];
+
+        // The counts are checked against the length before anything is
+        // allocated for them
+        let stride = stride::<E::Fr>(window);
+        let (g1, g2) = (g1_len::<E>(false), g2_len::<E>(false));
+        let points = counts.iter().sum::<u64>() + 2;
+        if bytes.len() as u64 != HEADER_LEN as u64 + stride as u64 * (points * g1 as u64 + g2 as u64) {
+            return Err(DecodingError::InvalidHeader);
+        }
+
+        let mut g1_table = |n: u64| -> Result<Vec<E::G1Affine>, DecodingError> {
+            (0..n as usize * stride).map(|_| read_point(rd.bytes(g1)?, false, true)).collect()
+        };
+        let h = g1_table(counts[0])?;
+        let l = g1_table(counts[1])?;
+        let a = g1_table(counts[2])?;
+        let delta_g1 = g1_table(1)?;
+        let delta_g2 = (0..stride).map(|_| read_point(rd.bytes(g2)?, false, true)).collect::<Result<_, _>>()?;
+        let beta_g1 = (0..stride).map(|_| read_point(rd.bytes(g1)?, false, true)).collect::<Result<_, _>>()?;
+
+        Ok(FixedBaseTables { window, h, l, a, delta_g1, delta_g2, beta_g1 })
+    }
+}
+
+impl<E: Engine> FixedBase<E> for FixedBaseTables<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    fn covers(&self, query: Query) -> bool {
+        matches!(query, Query::H | Query::L | Query::A)
+    }
+
+    fn mul_query(&self, query: Query, i: usize, s: &E::Fr) -> E::G1 {
+        let table = match query {
+            Query::H => &self.h,
+            Query::L => &self.l,
+            Query::A => &self.a,
+            _ => panic!("no table for {:?}", query),
+        };
+        let stride = stride::<E::Fr>(self.window);
+        lookup::<E::G1>(&table[i * stride..(i + 1) * stride], self.window, s)
+    }
+
+    fn mul_delta_g1(&self, s: &E::Fr) -> E::G1 {
+        lookup::<E::G1>(&self.delta_g1, self.window, s)
+    }
+
+    fn mul_delta_g2(&self, s: &E::Fr) -> E::G2 {
+        lookup::<E::G2>(&self.delta_g2, self.window, s)
+    }
+
+    fn mul_beta_g1(&self, s: &E::Fr) -> E::G1 {
+        lookup::<E::G1>(&self.beta_g1, self.window, s)
+    }
+}
+
+// Proving key together with its fixed-base tables
+pub struct PrecomputedParameters<E: Engine> {
+    pub params: Parameters<E>,
+    pub tables: FixedBaseTables<E>,
+}
+
+impl<E: Engine> PrecomputedParameters<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    pub fn new(params: Parameters<E>, window: usize) -> Self {
+        let tables = FixedBaseTables::new(&params, window);
+        PrecomputedParameters { params, tables }
+    }
+
+    // Loads tables encoded by `FixedBaseTables::to_bytes` for `params`.
+    // Each table must cover the query of the same length and start with
+    // its base, which catches tables built for another key.
+    pub fn from_bytes(params: Parameters<E>, bytes: &[u8]) -> Result<Self, DecodingError> {
+        let tables = FixedBaseTables::<E>::from_bytes(bytes)?;
+        let stride = stride::<E::Fr>(tables.window);
+        let vk = &params.vk;
+        let starts = |table: &[E::G1Affine], bases: &[E::G1Affine]| {
+            table.len() == bases.len() * stride && table.chunks(stride).zip(bases).all(|(t, b)| t[0] == *b)
+        };
+        if !starts(&tables.h, &params.h)
+            || !starts(&tables.l, &params.l)
+            || !starts(&tables.a, &params.a_g1)
+            || !starts(&tables.delta_g1, &[vk.delta_g1])
+            || !starts(&tables.beta_g1, &[vk.beta_g1])
+            || tables.delta_g2[0] != vk.delta_g2
+        {
+            return Err(DecodingError::Mismatch);
+        }
+        Ok(PrecomputedParameters { params, tables })
+    }
+}
+
+// A shared reference is the source, so that one set of tables serves any
+// number of proofs; pass `&mut &precomputed` where a `&mut` source is taken
+impl<E: Engine> ParameterSource<E> for &PrecomputedParameters<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    type Error = Infallible;
+
+    fn vk(&self) -> &VerificationKey<E> {
+        &self.params.vk
+    }
+
+    fn len(&self, query: Query) -> usize {
+        query_len(&self.params, query)
+    }
+
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], Infallible> {
+        Ok(query_g1(&self.params, query, offset))
+    }
+
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], Infallible> {
+        Ok(&self.params.b_g2[offset..])
+    }
+
+    fn fixed_base(&self) -> Option<&dyn FixedBase<E>> {
+        Some(&self.tables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, G1Projective, G2Projective, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::prover::{ create_proof, create_proof_from_source, required_scratch };
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn table_products_match() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let params = &fixture.params;
+
+        for window in [1, 3, 4, 8] {
+            let tables = FixedBaseTables::new(params, window);
+            let mut scalars = vec![Scalar::zero(), Scalar::one(), -Scalar::one()];
+            scalars.push(Scalar::random(&mut rng));
+
+            for s in scalars.iter() {
+                assert_eq!(tables.mul_delta_g1(s), G1Projective::from(params.vk.delta_g1) * s);
+                assert_eq!(tables.mul_delta_g2(s), G2Projective::from(params.vk.delta_g2) * s);
+                assert_eq!(tables.mul_beta_g1(s), G1Projective::from(params.vk.beta_g1) * s);
+                for (i, p) in params.l.iter().enumerate() {
+                    assert_eq!(tables.mul_query(Query::L, i, s), G1Projective::from(*p) * s);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn precomputed_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+
+        let precomputed = PrecomputedParameters::new(fixture.params.clone(), 4);
+        let proof = create_proof::<Bls12, _>(
+            &precomputed,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        let mut scratch = vec![Scalar::zero(); required_scratch(&fixture.qap).unwrap()];
+        let proof = create_proof_from_source(
+            &mut &precomputed,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        ).unwrap();
+        assert_eq!(proof.c, expected.c);
+    }
+
+    #[test]
+    fn tables_roundtrip() {
+        let fixture = cube_fixture(3);
+        let precomputed = PrecomputedParameters::new(fixture.params.clone(), 1);
+        let bytes = precomputed.tables.to_bytes();
+
+        let loaded = PrecomputedParameters::from_bytes(fixture.params.clone(), &bytes).unwrap();
+        assert_eq!(loaded.tables.window(), 1);
+        assert_eq!(loaded.tables.to_bytes(), bytes);
+        let s = Scalar::random(thread_rng());
+        assert_eq!(loaded.tables.mul_query(Query::A, 1, &s), G1Projective::from(fixture.params.a_g1[1]) * s);
+
+        assert_eq!(FixedBaseTables::<Bls12>::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::InvalidHeader));
+        let mut bad = bytes.clone();
+        bad[5] = 9;
+        assert_eq!(FixedBaseTables::<Bls12>::from_bytes(&bad).err(), Some(DecodingError::InvalidHeader));
+        let mut bad = bytes.clone();
+        bad[HEADER_LEN + 40] ^= 1;
+        assert_eq!(FixedBaseTables::<Bls12>::from_bytes(&bad).err(), Some(DecodingError::InvalidPoint));
+
+        // Tables of another key
+        let other = cube_fixture(3);
+        assert_eq!(PrecomputedParameters::from_bytes(other.params, &bytes).err(), Some(DecodingError::Mismatch));
+    }
+}
@@ -0,0 +1,132 @@
+// Keccak-256, the hash of Ethereum, for function selectors and key
+// fingerprints. `Keccak256` hashes a message given in pieces, for
+// fingerprints of data that is never encoded as a whole.
+
+// Keccak-f[1600] round constants, rotations and lane order
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+
+const LANES: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+fn keccak_f(st: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS {
+        let mut column = [0u64; 5];
+        for (i, c) in column.iter_mut().enumerate() {
+            *c = st[i] ^ st[i + 5] ^ st[i + 10] ^ st[i + 15] ^ st[i + 20];
+        }
+        for i in 0..5 {
+            let t = column[(i + 4) % 5] ^ column[(i + 1) % 5].rotate_left(1);
+            for row in st.chunks_mut(5) {
+                row[i] ^= t;
+            }
+        }
+
+        let mut t = st[1];
+        for (lane, rotation) in LANES.iter().zip(ROTATIONS) {
+            let next = st[*lane];
+            st[*lane] = t.rotate_left(rotation);
+            t = next;
+        }
+
+        for row in st.chunks_mut(5) {
+            let r = [row[0], row[1], row[2], row[3], row[4]];
+            for (i, x) in row.iter_mut().enumerate() {
+                *x = r[i] ^ (!r[(i + 1) % 5] & r[(i + 2) % 5]);
+            }
+        }
+
+        st[0] ^= rc;
+    }
+}
+
+const RATE: usize = 136;
+
+// Keccak-256 as Ethereum uses it, with the original padding rather than
+// that of SHA-3
+pub struct Keccak256 {
+    st: [u64; 25],
+    // Bytes of the current block absorbed so far
+    block: [u8; RATE],
+    len: usize,
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Keccak256 { st: [0; 25], block: [0; RATE], len: 0 }
+    }
+}
+
+impl Keccak256 {
+    fn absorb(&mut self) {
+        for (lane, chunk) in self.st.iter_mut().zip(self.block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        keccak_f(&mut self.st);
+        self.len = 0;
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = data.len().min(RATE - self.len);
+            self.block[self.len..self.len + n].copy_from_slice(&data[..n]);
+            self.len += n;
+            data = &data[n..];
+            if self.len == RATE {
+                self.absorb();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> [u8; 32] {
+        self.block[self.len..].fill(0);
+        self.block[self.len] ^= 0x01;
+        self.block[RATE - 1] ^= 0x80;
+        self.absorb();
+
+        let mut out = [0; 32];
+        for (chunk, lane) in out.chunks_mut(8).zip(self.st.iter()) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        out
+    }
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut k = Keccak256::default();
+    k.update(data);
+    k.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keccak_vectors() {
+        assert_eq!(&keccak256(b"")[..4], &[0xc5, 0xd2, 0x46, 0x01]);
+        let hash = keccak256(b"transfer(address,uint256)");
+        assert_eq!(&hash[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        // More than one block of 136 bytes
+        let hash = keccak256(&[0x61; 200]);
+        assert_eq!(&hash[..4], &[0x96, 0xea, 0x54, 0x06]);
+    }
+
+    #[test]
+    fn incremental_matches_whole() {
+        let data: Vec<u8> = (0..500).map(|i| i as u8).collect();
+        for split in [0, 1, 135, 136, 137, 272, 499, 500] {
+            let mut k = Keccak256::default();
+            k.update(&data[..split]);
+            k.update(&data[split..]);
+            assert_eq!(k.finish(), keccak256(&data));
+        }
+    }
+}
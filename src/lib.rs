@@ -8,61 +8,131 @@ use alloc::vec::Vec;
 use ff::PrimeField;
 use pairing::Engine;
 
+pub mod backend;
 pub mod prover;
 pub mod verifier;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
+pub mod ct;
+pub mod task;
+pub mod source;
+pub mod view;
+pub mod fixed;
+pub mod split;
+pub mod batch;
+pub mod witness;
+pub mod circom;
+pub mod coordinates;
+pub mod snarkjs;
+pub mod gnark;
+pub mod envelope;
+#[cfg(any(test, feature = "bls12_381", feature = "bn254"))]
+pub mod any;
+#[cfg(any(test, feature = "bn254"))]
+pub mod bn254;
+#[cfg(any(test, feature = "bn254"))]
+pub mod solidity;
+#[cfg(any(test, feature = "bls12_381"))]
+pub mod arkworks;
+#[cfg(any(test, feature = "deterministic"))]
+pub mod nonce;
 mod poly;
-
+mod encoding;
+mod uint;
+mod json;
+mod keccak;
+#[cfg(any(test, feature = "serde"))]
+mod serialize;
+#[cfg(any(test, feature = "zeroize"))]
+mod wipe;
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VerificationError {
     InvalidProof,
     InvalidVerifyingKey,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodingError {
+    UnexpectedEof,
+    InvalidHeader,
+    InvalidFieldElement,
+    InvalidPoint,
+    // The data is well formed but does not fit the other arguments
+    Mismatch,
+}
+
 
 #[derive(Clone, Debug)]
+#[cfg_attr(any(test, feature = "serde"), derive(serde::Serialize, serde::Deserialize), serde(bound = ""))]
 pub struct Proof<E: Engine> {
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub a: E::G1Affine,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub b: E::G2Affine,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub c: E::G1Affine,
 }
 #[derive(Default, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(serde::Serialize, serde::Deserialize), serde(bound = ""))]
 pub struct VerificationKey<E: Engine> {
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub alpha_g1: E::G1Affine,
 
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub beta_g1: E::G1Affine,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub beta_g2: E::G2Affine,
 
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub gamma_g2: E::G2Affine,
 
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub delta_g1: E::G1Affine,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::point"))]
     pub delta_g2: E::G2Affine,
 
     // LP_i = [(beta * A_i(tau) + alpha * B_i(tau) + C_i(tau))/gamma]*G_1
     // for all public inputs.
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub ic: Vec<E::G1Affine>
 }
 
 #[derive(Default, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(serde::Serialize, serde::Deserialize), serde(bound = ""))]
 pub struct Parameters<E: Engine> {
     pub vk: VerificationKey<E>,
 
     // H query
     // h_i = (tau^i*Z_x(tau)/delta)*G1
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub h: Vec<E::G1Affine>,
 
     // L query
     // l_i = (L_i(tau)/delta)*G1
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub l: Vec<E::G1Affine>,
 
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub a_g1:  Vec<E::G1Affine>,
     
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub b_g1: Vec<E::G1Affine>,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::points"))]
     pub b_g2: Vec<E::G2Affine>,
 }
 
+// Deserializing goes through `QAP::new`, see `serialize`
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(serde::Serialize), serde(bound = ""))]
 pub struct QAP<S: PrimeField> {
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::query"))]
     pub a: Vec<(usize, Vec<(S, usize)>)>,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::query"))]
     pub b: Vec<(usize, Vec<(S, usize)>)>,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "crate::serialize::query"))]
     pub c: Vec<(usize, Vec<(S, usize)>)>,
 
     // Sorted array of variable indices for which 
@@ -71,13 +141,77 @@ pub struct QAP<S: PrimeField> {
     pub b_constraints: Vec<usize>
 }
 
+impl<E: Engine> Parameters<E> {
+    // Checks the query lengths against `qap`, which the prover otherwise
+    // asserts on: a_g1, b_g1 and b_g2 have a point per variable of A and B,
+    // h covers the domain and every variable is an input of ic or has a
+    // point in l. `Mismatch` if not.
+    pub fn check(&self, qap: &QAP<E::Fr>) -> Result<(), DecodingError> {
+        let (_, m, _) = poly::fft_params::<E::Fr>(qap.num_constraints()).ok_or(DecodingError::Mismatch)?;
+        let num_variables = self.vk.ic.len() + self.l.len();
+        let mut variables = qap.a.iter().chain(qap.b.iter()).chain(qap.c.iter()).map(|(i, _)| *i);
+
+        if self.vk.ic.is_empty()
+            || self.a_g1.len() != qap.a_constraints.len()
+            || self.b_g1.len() != qap.b_constraints.len()
+            || self.b_g2.len() != qap.b_constraints.len()
+            || self.h.len() < m - 1
+            || variables.any(|i| i >= num_variables)
+        {
+            return Err(DecodingError::Mismatch);
+        }
+        Ok(())
+    }
+}
+
+impl<S: PrimeField> QAP<S> {
+    // A QAP from its parts, with `Mismatch` unless the records of A, B and
+    // C are in strictly increasing order of variable, and a_constraints and
+    // b_constraints hold exactly the variables of A and B
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        a: Vec<(usize, Vec<(S, usize)>)>,
+        b: Vec<(usize, Vec<(S, usize)>)>,
+        c: Vec<(usize, Vec<(S, usize)>)>,
+        a_constraints: Vec<usize>,
+        b_constraints: Vec<usize>
+    ) -> Result<Self, DecodingError> {
+        let variables = |query: &[(usize, Vec<(S, usize)>)]| query.iter().map(|(i, _)| *i).collect::<Vec<usize>>();
+        let sorted = |v: &[usize]| v.windows(2).all(|w| w[0] < w[1]);
+
+        if variables(&a) != a_constraints || variables(&b) != b_constraints || !sorted(&a_constraints)
+            || !sorted(&b_constraints) || !sorted(&variables(&c))
+        {
+            return Err(DecodingError::Mismatch);
+        }
+        Ok(QAP { a, b, c, a_constraints, b_constraints })
+    }
+
+    // One more than the largest constraint index referenced by a query
+    pub fn num_constraints(&self) -> usize {
+        self.a.iter().chain(self.b.iter()).chain(self.c.iter())
+            .flat_map(|(_, v)| v.iter().map(|(_, u)| u + 1))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
 #[cfg(any(test, feature = "std"))]
 pub mod assignments {
     use std::collections::HashMap;
     use bellman::{ConstraintSystem, LinearCombination, SynthesisError, Variable, Index, Circuit};
-    use bellman::groth16::{Parameters as BellmanParams};
+    use bellman::groth16::Parameters as BellmanParams;
     use pairing::group::ff::{ Field, PrimeField };
     use std::sync::Arc;
+    #[cfg(any(test, feature = "simulator"))]
+    use {
+        bellman::groth16::generate_parameters,
+        pairing::group::{ Group, WnafGroup },
+        rand_core::RngCore,
+        crate::simulator::Trapdoor,
+    };
+    #[cfg(any(test, feature = "zeroize"))]
+    use crate::wipe::wipe_slice;
     use super::*;
     #[derive(Default, Debug)]
     pub struct AnalyzeCircuit<S: PrimeField> {
@@ -93,6 +227,20 @@ pub mod assignments {
     }
 
     impl<S: PrimeField> AnalyzeCircuit<S> {
+        fn new(extract_assignments: bool) -> Self {
+            AnalyzeCircuit {
+                input_assignment: Vec::new(),
+                num_inputs: 0,
+                aux_assignment: Vec::new(),
+                num_aux: 0,
+                num_constraints: 0,
+                extract_assignments,
+                at: Vec::new(),
+                bt: Vec::new(),
+                ct: Vec::new(),
+            }
+        }
+
         pub fn get_num_states(&self) -> (usize, usize) {
             (self.num_inputs, self.num_aux)
         }
@@ -106,7 +254,8 @@ pub mod assignments {
         }
 
         // Only call after synthesize
-        pub fn qap(self) -> QAP<S> {
+        pub fn qap(mut self) -> QAP<S> {
+            #[allow(clippy::type_complexity)]
             fn collect<S: PrimeField>(v: Vec<(Index, S, usize)>, p: usize) -> (Vec<(usize, Vec<(S, usize)>)>, Vec<usize>) {
                 let mut constraints: Vec<usize> = Vec::new();
                 let mut map: HashMap<usize, Vec<(S, usize)>> = HashMap::new();
@@ -130,12 +279,15 @@ pub mod assignments {
                 }
                 constraints.sort();
 
-                (map.into_iter().collect(), constraints)
+                // Records in increasing order of variable, as `QAP::new` requires
+                let mut records: Vec<_> = map.into_iter().collect();
+                records.sort_unstable_by_key(|(i, _)| *i);
+                (records, constraints)
             }
 
-            let (a, a_constraints) = collect(self.at, self.num_inputs);
-            let (b, b_constraints) = collect(self.bt, self.num_inputs);
-            let (c, _) = collect(self.ct, self.num_inputs);
+            let (a, a_constraints) = collect(core::mem::take(&mut self.at), self.num_inputs);
+            let (b, b_constraints) = collect(core::mem::take(&mut self.bt), self.num_inputs);
+            let (c, _) = collect(core::mem::take(&mut self.ct), self.num_inputs);
 
 
             QAP {
@@ -167,6 +319,29 @@ pub mod assignments {
         }
     }
 
+    // Wipes the extracted witness. Copies handed out by `get_assignments`
+    // and `to_bytes` belong to the caller, and buffers the vectors left
+    // behind while growing during synthesis are not covered.
+    #[cfg(any(test, feature = "zeroize"))]
+    impl<S: PrimeField> zeroize::Zeroize for AnalyzeCircuit<S> {
+        fn zeroize(&mut self) {
+            wipe_slice(&mut self.input_assignment, S::zero());
+            wipe_slice(&mut self.aux_assignment, S::zero());
+            self.input_assignment.clear();
+            self.aux_assignment.clear();
+        }
+    }
+
+    #[cfg(any(test, feature = "zeroize"))]
+    impl<S: PrimeField> Drop for AnalyzeCircuit<S> {
+        fn drop(&mut self) {
+            zeroize::Zeroize::zeroize(self);
+        }
+    }
+
+    #[cfg(any(test, feature = "zeroize"))]
+    impl<S: PrimeField> zeroize::ZeroizeOnDrop for AnalyzeCircuit<S> {}
+
     impl<S: PrimeField> ConstraintSystem<S> for AnalyzeCircuit<S> {
         type Root = AnalyzeCircuit<S>;
 
@@ -210,7 +385,7 @@ pub mod assignments {
                 current_constraint: usize,
             ) {
                 for (var, c) in lc.as_ref() {
-                    output.push((var.get_unchecked(), c.clone(), current_constraint))
+                    output.push((var.get_unchecked(), *c, current_constraint))
                 }
             }
             
@@ -242,10 +417,7 @@ pub mod assignments {
         E: Engine,
         C: Circuit<E::Fr>
     {
-        let mut cs = AnalyzeCircuit::<E::Fr>{
-            extract_assignments: true,
-            ..Default::default()
-        };
+        let mut cs = AnalyzeCircuit::<E::Fr>::new(true);
 
         cs.alloc_input(|| "one", || Ok(E::Fr::one()))?;
         circuit.synthesize(&mut cs)?;
@@ -260,10 +432,7 @@ pub mod assignments {
         S: PrimeField,
         C: Circuit<S>
     {
-        let mut cs = AnalyzeCircuit::<S>{
-            extract_assignments: false,
-            ..Default::default()
-        };
+        let mut cs = AnalyzeCircuit::<S>::new(false);
         
         cs.alloc_input(|| "one", || Ok(S::one()))?;
         circuit.synthesize(&mut cs)?;
@@ -315,7 +484,7 @@ pub mod assignments {
             }
         };
     
-        let grothparams = Parameters{
+        Parameters {
             vk: VerificationKey {
                 alpha_g1: params.vk.alpha_g1,
                 beta_g1: params.vk.beta_g1,
@@ -330,9 +499,31 @@ pub mod assignments {
             a_g1,
             b_g1,
             b_g2,
-        };
-
-        grothparams
+        }
+    }
+    // Runs the setup with freshly sampled toxic waste and hands it back
+    // alongside the parameters, for use with `simulator::simulate_proof`.
+    #[cfg(any(test, feature = "simulator"))]
+    pub fn generate_parameters_with_trapdoor<E, C, R>(circuit: C, rng: R) -> Result<(Parameters<E>, Trapdoor<E>), SynthesisError>
+    where
+        E: Engine,
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+        C: Circuit<E::Fr>,
+        R: RngCore
+    {
+        let trapdoor = Trapdoor::<E>::random(rng);
+        let params = generate_parameters::<E, C>(
+            circuit,
+            E::G1::generator(),
+            E::G2::generator(),
+            *trapdoor.alpha(),
+            *trapdoor.beta(),
+            *trapdoor.gamma(),
+            *trapdoor.delta(),
+            *trapdoor.tau(),
+        )?;
+
+        Ok((create_params(params), trapdoor))
     }
 }
-
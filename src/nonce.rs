@@ -0,0 +1,223 @@
+// Derivation of the blinding factors r and s without a trustworthy RNG,
+// in the style of RFC 6979. An HMAC-SHA256 DRBG is keyed with a device
+// secret and seeded with a digest of the full assignment and a per-proof
+// counter. The hedged variant also feeds whatever entropy is available
+// into the seed, so a weak RNG can only add to, never subtract from, the
+// deterministic guarantee.
+//
+// Reusing (secret, counter) for the same assignment yields the same proof,
+// which leaks nothing. The counter must never repeat for a given secret
+// if proofs should stay unlinkable.
+
+use ff::PrimeField;
+use hmac::{ Hmac, Mac };
+use sha2::{ Digest, Sha256 };
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DOMAIN: &[u8] = b"embedded-groth/groth16/blinding/v2";
+
+struct HmacDrbg {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl HmacDrbg {
+    // RFC 6979 3.2 steps b. to g.
+    fn new(seed: &[&[u8]]) -> Self {
+        let mut drbg = HmacDrbg {
+            k: [0x00; 32],
+            v: [0x01; 32],
+        };
+        drbg.update(seed);
+        drbg
+    }
+
+    fn hmac(&self, prefix: &[u8], data: &[&[u8]]) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.k).unwrap();
+        mac.update(prefix);
+        for d in data {
+            mac.update(d);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    // K = HMAC_K(V || 0x00 || seed), V = HMAC_K(V), and the same again
+    // with 0x01 if the seed is not empty
+    fn update(&mut self, seed: &[&[u8]]) {
+        for sep in [0x00, 0x01] {
+            if sep == 0x01 && seed.is_empty() {
+                break;
+            }
+            let mut prefix = [sep; 33];
+            prefix[..32].copy_from_slice(&self.v);
+            self.k = self.hmac(&prefix, seed);
+            self.v = self.hmac(&self.v, &[]);
+        }
+    }
+
+    fn fill(&mut self, out: &mut [u8]) {
+        for chunk in out.chunks_mut(32) {
+            self.v = self.hmac(&self.v, &[]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[]);
+    }
+
+    // Reduces 512 bits of output modulo the field order, which leaves a
+    // bias far below the security level for any field of at most 256 bits.
+    fn next_scalar<S: PrimeField>(&mut self) -> S {
+        let mut buf = [0u8; 64];
+        self.fill(&mut buf);
+
+        let base = S::from(256);
+        buf.iter().fold(S::zero(), |acc, b| acc * base + S::from(u64::from(*b)))
+    }
+}
+
+fn digest_assignment<S: PrimeField>(inputs: &[S], aux: &[S], counter: u64) -> [u8; 32] {
+    let mut h = Sha256::new();
+    h.update(DOMAIN);
+    h.update((inputs.len() as u64).to_be_bytes());
+    for x in inputs {
+        h.update(x.to_repr());
+    }
+    h.update((aux.len() as u64).to_be_bytes());
+    for x in aux {
+        h.update(x.to_repr());
+    }
+    h.update(counter.to_be_bytes());
+    h.finalize().into()
+}
+
+fn derive<S: PrimeField>(secret_key: &[u8], inputs: &[S], aux: &[S], counter: u64, entropy: &[u8]) -> (S, S) {
+    let h = digest_assignment(inputs, aux, counter);
+    // Both variable-length parts are length-prefixed, so that no two
+    // (secret, entropy) pairs give the same seed
+    let key_len = (secret_key.len() as u64).to_be_bytes();
+    let entropy_len = (entropy.len() as u64).to_be_bytes();
+    let mut drbg = HmacDrbg::new(&[DOMAIN, &key_len, secret_key, &h, &entropy_len, entropy]);
+
+    let r = drbg.next_scalar();
+    let s = drbg.next_scalar();
+    (r, s)
+}
+
+// Derives (r, s) purely from the device secret, the assignment and the counter.
+pub fn deterministic_blinding<S: PrimeField>(
+    secret_key: &[u8],
+    inputs: &[S],
+    aux: &[S],
+    counter: u64,
+) -> (S, S)
+{
+    derive(secret_key, inputs, aux, counter, &[])
+}
+
+// Same as `deterministic_blinding`, with additional entropy mixed into the seed.
+pub fn hedged_blinding<S: PrimeField>(
+    secret_key: &[u8],
+    inputs: &[S],
+    aux: &[S],
+    counter: u64,
+    entropy: &[u8],
+) -> (S, S)
+{
+    derive(secret_key, inputs, aux, counter, entropy)
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+
+    use crate::prover::create_proof;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    const KEY: &[u8] = b"device secret key";
+
+    #[test]
+    fn hmac_drbg_rfc6979_vector() {
+        // RFC 6979 A.2.5, P-256 with SHA-256, message "sample": the first
+        // candidate k is the 32 byte DRBG output for seed x || h1.
+        let x = [
+            0xc9, 0xaf, 0xa9, 0xd8, 0x45, 0xba, 0x75, 0x16, 0x6b, 0x5c, 0x21, 0x57, 0x67, 0xb1, 0xd6, 0x93,
+            0x4e, 0x50, 0xc3, 0xdb, 0x36, 0xe8, 0x9b, 0x12, 0x7b, 0x8a, 0x62, 0x2b, 0x12, 0x0f, 0x67, 0x21,
+        ];
+        let h1: [u8; 32] = Sha256::digest(b"sample").into();
+        let k = [
+            0xa6, 0xe3, 0xc5, 0x7d, 0xd0, 0x1a, 0xbe, 0x90, 0x08, 0x65, 0x38, 0x39, 0x83, 0x55, 0xdd, 0x4c,
+            0x3b, 0x17, 0xaa, 0x87, 0x33, 0x82, 0xb0, 0xf2, 0x4d, 0x61, 0x29, 0x49, 0x3d, 0x8a, 0xad, 0x60,
+        ];
+
+        let mut drbg = HmacDrbg::new(&[&x, &h1]);
+        let mut out = [0u8; 32];
+        drbg.fill(&mut out);
+        assert_eq!(out, k);
+    }
+
+    #[test]
+    fn deterministic_blinding_is_stable() {
+        let inputs = [Scalar::one(), Scalar::from(35)];
+        let aux = [Scalar::from(3), Scalar::from(9), Scalar::from(27)];
+
+        let (r, s) = deterministic_blinding(KEY, &inputs, &aux, 0);
+        assert_eq!((r, s), deterministic_blinding(KEY, &inputs, &aux, 0));
+        assert_ne!(r, s);
+
+        // Every part of the seed matters
+        assert_ne!((r, s), deterministic_blinding(KEY, &inputs, &aux, 1));
+        assert_ne!((r, s), deterministic_blinding(b"other key", &inputs, &aux, 0));
+        assert_ne!((r, s), deterministic_blinding(KEY, &inputs, &[Scalar::from(4), aux[1], aux[2]], 0));
+        assert_ne!((r, s), deterministic_blinding(KEY, &inputs[..1], &[inputs[1], aux[0], aux[1], aux[2]], 0));
+    }
+
+    #[test]
+    fn hedged_blinding_mixes_entropy() {
+        let inputs = [Scalar::one(), Scalar::from(35)];
+        let aux = [Scalar::from(3), Scalar::from(9), Scalar::from(27)];
+
+        let det = deterministic_blinding(KEY, &inputs, &aux, 0);
+        let a = hedged_blinding(KEY, &inputs, &aux, 0, &[1, 2, 3]);
+        assert_ne!(det, a);
+        assert_ne!(a, hedged_blinding(KEY, &inputs, &aux, 0, &[1, 2, 4]));
+        assert_eq!(det, hedged_blinding(KEY, &inputs, &aux, 0, &[]));
+    }
+
+    #[test]
+    fn seed_separates_secret_and_entropy() {
+        let inputs = [Scalar::one(), Scalar::from(35)];
+        let aux = [Scalar::from(3), Scalar::from(9), Scalar::from(27)];
+        let h = digest_assignment(&inputs, &aux, 0);
+
+        // Without a length before the secret, moving the start of the
+        // entropy into the secret gives the same seed bytes:
+        // KEY | h | len(entropy) | entropy with entropy = tail | h | 1 | [7]
+        let tail = b"tail";
+        let entropy = [&tail[..], &h, &1u64.to_be_bytes(), &[7]].concat();
+        let key = [KEY, &h, &(entropy.len() as u64).to_be_bytes(), tail].concat();
+        assert_eq!(
+            [KEY, &h, &(entropy.len() as u64).to_be_bytes(), &entropy].concat(),
+            [&key[..], &h, &1u64.to_be_bytes(), &[7]].concat()
+        );
+        assert_ne!(hedged_blinding(KEY, &inputs, &aux, 0, &entropy), hedged_blinding(&key, &inputs, &aux, 0, &[7]));
+    }
+
+    #[test]
+    fn deterministic_proof_verifies() {
+        let fixture = cube_fixture(3);
+        let (r, s) = deterministic_blinding(KEY, &fixture.inputs, &fixture.aux, 7);
+
+        let proof = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+    }
+}
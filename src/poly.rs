@@ -1,13 +1,21 @@
-use ff::PrimeField;
+use ff::{ Field, PrimeField };
+use pairing::group::Group;
+
 #[cfg(not(any(test, feature = "std")))]
 use alloc::vec::Vec;
 
-pub fn fft_params<S: PrimeField>(l: usize) -> (S, usize, u32) {
-    let mut m = 1;
+// Root of unity, size and log2 size of the smallest domain of at least `l`
+// points, or None if that is larger than 2^S. Squaring the root of unity
+// only lowers its order, so such a domain has no root to fall back on.
+pub fn fft_params<S: PrimeField>(l: usize) -> Option<(S, usize, u32)> {
+    let mut m: usize = 1;
     let mut exp = 0;
 
     while m < l {
-        m = m << 1;
+        if exp == S::S {
+            return None;
+        }
+        m = m.checked_mul(2)?;
         exp += 1;
     }
 
@@ -16,19 +24,29 @@ pub fn fft_params<S: PrimeField>(l: usize) -> (S, usize, u32) {
         omega = omega.square();
     }
 
-    (omega, m, exp)
+    Some((omega, m, exp))
 }
 
-pub fn fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
-    fn bitreverse(mut n: u32, l: u32) -> u32 {
-        let mut r = 0;
-        for _ in 0..l {
-            r = (r << 1) | (n & 1);
-            n >>= 1;
-        }
-        r
+pub fn bitreverse(mut n: u32, l: u32) -> u32 {
+    let mut r = 0;
+    for _ in 0..l {
+        r = (r << 1) | (n & 1);
+        n >>= 1;
     }
+    r
+}
 
+// Single radix-2 butterfly on a[i] and a[i + m] with twiddle factor w
+pub fn butterfly<S: PrimeField>(a: &mut [S], i: usize, m: usize, w: &S) {
+    let mut t = a[i + m];
+    t.mul_assign(w);
+    let mut tmp = a[i];
+    tmp.sub_assign(&t);
+    a[i + m] = tmp;
+    a[i].add_assign(&t);
+}
+
+pub fn fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
     let n = a.len() as u32;
     assert_eq!(n, 1 << exp);
 
@@ -41,18 +59,13 @@ pub fn fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
 
     let mut m = 1;
     for _ in 0..exp {
-        let w_m = omega.pow_vartime(&[u64::from(n / (2 * m))]);
+        let w_m = omega.pow_vartime([u64::from(n / (2 * m))]);
 
         let mut k = 0;
         while k < n {
             let mut w = S::one();
             for j in 0..m {
-                let mut t = a[(k + j + m) as usize];
-                t.mul_assign(&w);
-                let mut tmp = a[(k + j) as usize];
-                tmp.sub_assign(&t);
-                a[(k + j + m) as usize] = tmp;
-                a[(k + j) as usize].add_assign(&t);
+                butterfly(a, (k + j) as usize, m as usize, &w);
                 w.mul_assign(&w_m);
             }
             k += 2 * m;
@@ -62,7 +75,51 @@ pub fn fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
     }
 }
 
+// Same transform over a group, with the scalars as twiddle factors
+pub fn group_fft<G: Group>(a: &mut [G], omega: &G::Scalar, exp: u32) {
+    let n = a.len() as u32;
+    assert_eq!(n, 1 << exp);
+
+    for k in 0..n {
+        let rk = bitreverse(k, exp);
+        if k < rk {
+            a.swap(rk as usize, k as usize);
+        }
+    }
+
+    let mut m = 1;
+    for _ in 0..exp {
+        let w_m = omega.pow_vartime([u64::from(n / (2 * m))]);
+
+        let mut k = 0;
+        while k < n {
+            let mut w = G::Scalar::one();
+            for j in 0..m {
+                let (i, m) = ((k + j) as usize, m as usize);
+                let t = a[i + m] * w;
+                a[i + m] = a[i] - t;
+                a[i] += t;
+                w *= w_m;
+            }
+            k += 2 * m;
+        }
+
+        m *= 2;
+    }
+}
+
+// The `_with` variants below take the forward transform to use, e.g. that
+// of a `Backend`
+pub trait Fft<S>: Fn(&mut [S], &S, u32) {}
+
+impl<S, F: Fn(&mut [S], &S, u32)> Fft<S> for F {}
+
+#[cfg(test)]
 pub fn ifft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
+    ifft_with(a, omega, exp, &fft)
+}
+
+pub fn ifft_with<S: PrimeField>(a: &mut [S], omega: &S, exp: u32, fft: &impl Fft<S>) {
     fft(a, &omega.invert().unwrap(), exp);
     let minv = S::from(a.len() as u64).invert().unwrap();
     for i in a {
@@ -70,14 +127,19 @@ pub fn ifft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
     }
 }
 
-pub fn sub_eval_domain<S: PrimeField>(a: &mut Vec<S>, b: Vec<S>) {
+pub fn sub_eval_domain<S: PrimeField>(a: &mut [S], b: &[S]) {
     assert_eq!(a.len(), b.len());
     for (x, y) in a.iter_mut().zip(b.iter()) {
         x.sub_assign(y);
     }
 }
 
+#[cfg(test)]
 pub fn coset_fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
+    coset_fft_with(a, omega, exp, &fft)
+}
+
+pub fn coset_fft_with<S: PrimeField>(a: &mut [S], omega: &S, exp: u32, fft: &impl Fft<S>) {
     let g = S::multiplicative_generator();
     let mut u = S::one();
     for x in a.iter_mut() {
@@ -87,8 +149,13 @@ pub fn coset_fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
     fft(a, omega, exp)
 }
 
+#[cfg(test)]
 pub fn icoset_fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
-    ifft(a, omega, exp);
+    icoset_fft_with(a, omega, exp, &fft)
+}
+
+pub fn icoset_fft_with<S: PrimeField>(a: &mut [S], omega: &S, exp: u32, fft: &impl Fft<S>) {
+    ifft_with(a, omega, exp, fft);
     let g = S::multiplicative_generator().invert().unwrap();
     let mut u = S::one();
     for x in a.iter_mut() {
@@ -97,23 +164,126 @@ pub fn icoset_fft<S: PrimeField>(a: &mut [S], omega: &S, exp: u32) {
     }
 }
 
-pub fn coset_mul_assign<S: PrimeField>(a: &mut Vec<S>, mut b: Vec<S>) {
+pub fn coset_mul_assign_with<S: PrimeField>(a: &mut [S], b: &mut [S], fft: &impl Fft<S>) {
     // let (omega, m, exp): (S, usize, u32) = fft_params(a.len() + b.len());
     // a.resize(m, S::zero());
     // b.resize(m, S::zero());
     assert_eq!(a.len(), b.len());
-    let (omega, _, exp): (S, usize, u32) = fft_params(a.len());
-    ifft(a.as_mut_slice(), &omega, exp);
-    ifft(b.as_mut_slice(), &omega, exp);
+    // The callers size a and b to a domain they got from fft_params
+    let (omega, _, exp): (S, usize, u32) = fft_params(a.len()).unwrap();
+    ifft_with(a, &omega, exp, fft);
+    ifft_with(b, &omega, exp, fft);
 
-    coset_fft(a.as_mut_slice(), &omega, exp);
-    coset_fft(b.as_mut_slice(), &omega, exp);
+    coset_fft_with(a, &omega, exp, fft);
+    coset_fft_with(b, &omega, exp, fft);
 
     for (x, y) in a.iter_mut().zip(b.iter()) {
         x.mul_assign(y);
     }
 }
 
+// Precomputed twiddle factors, coset powers and constants of a domain, for
+// transforms over it one butterfly at a time, see `task`
+pub struct Domain<S: PrimeField> {
+    pub m: usize,
+    pub exp: u32,
+    // omega^j and omega^-j for j < m / 2
+    pub omegas: Vec<S>,
+    pub omegas_inv: Vec<S>,
+    // g^i and g^-i / m for i < m
+    pub coset: Vec<S>,
+    pub coset_inv: Vec<S>,
+    pub minv: S,
+    // Inverse of the vanishing polynomial on the coset
+    pub zinv: S,
+}
+
+fn powers<S: PrimeField>(x: S, scale: S, n: usize) -> Vec<S> {
+    let mut u = scale;
+    (0..n).map(|_| {
+        let t = u;
+        u.mul_assign(&x);
+        t
+    }).collect()
+}
+
+impl<S: PrimeField> Domain<S> {
+    pub fn new(l: usize) -> Option<Self> {
+        let (omega, m, exp): (S, usize, u32) = fft_params(l)?;
+        let g = S::multiplicative_generator();
+        let minv = S::from(m as u64).invert().unwrap();
+
+        let mut zinv = g.pow_vartime([m as u64]);
+        zinv.sub_assign(&S::one());
+
+        Some(Domain {
+            m,
+            exp,
+            omegas: powers(omega, S::one(), m / 2),
+            omegas_inv: powers(omega.invert().unwrap(), S::one(), m / 2),
+            coset: powers(g, S::one(), m),
+            coset_inv: powers(g.invert().unwrap(), minv, m),
+            minv,
+            zinv: zinv.invert().unwrap(),
+        })
+    }
+}
+
+// Whole transforms from the tables, which the tests check against the
+// functions above
+#[cfg(test)]
+impl<S: PrimeField> Domain<S> {
+    fn transform(&self, a: &mut [S], omegas: &[S]) {
+        let n = a.len() as u32;
+        assert_eq!(n, 1 << self.exp);
+
+        for k in 0..n {
+            let rk = bitreverse(k, self.exp);
+            if k < rk {
+                a.swap(rk as usize, k as usize);
+            }
+        }
+
+        let mut m = 1;
+        for _ in 0..self.exp {
+            let stride = (n / (2 * m)) as usize;
+            let mut k = 0;
+            while k < n {
+                for j in 0..m {
+                    butterfly(a, (k + j) as usize, m as usize, &omegas[j as usize * stride]);
+                }
+                k += 2 * m;
+            }
+            m *= 2;
+        }
+    }
+
+    pub fn fft(&self, a: &mut [S]) {
+        self.transform(a, &self.omegas);
+    }
+
+    pub fn ifft(&self, a: &mut [S]) {
+        self.transform(a, &self.omegas_inv);
+        for x in a.iter_mut() {
+            x.mul_assign(&self.minv);
+        }
+    }
+
+    pub fn coset_fft(&self, a: &mut [S]) {
+        for (x, u) in a.iter_mut().zip(self.coset.iter()) {
+            x.mul_assign(u);
+        }
+        self.fft(a);
+    }
+
+    pub fn icoset_fft(&self, a: &mut [S]) {
+        self.transform(a, &self.omegas_inv);
+        for (x, u) in a.iter_mut().zip(self.coset_inv.iter()) {
+            x.mul_assign(u);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::ops::{AddAssign, MulAssign};
@@ -128,7 +298,7 @@ mod tests {
     use super::*;
 
     pub fn mul_coefficient_domain<S: PrimeField>(a: &mut Vec<S>, b: &mut Vec<S>) {
-        let (omega, m, exp): (S, usize, u32) = fft_params(a.len() + b.len());
+        let (omega, m, exp): (S, usize, u32) = fft_params(a.len() + b.len()).unwrap();
         a.resize(m, S::zero());
         b.resize(m, S::zero());
         fft(a.as_mut_slice(), &omega, exp);
@@ -153,7 +323,7 @@ mod tests {
         let mut x: Vec<BlsScalar> = domain.as_ref().iter().map(|t| t.0).collect();
 
         // fft with local functions
-        let (omega, _, exp): (BlsScalar, usize, u32) = fft_params(a.len());
+        let (omega, _, exp): (BlsScalar, usize, u32) = fft_params(a.len()).unwrap();
         fft(a2.as_mut_slice(), &omega, exp);
 
         // FFT outputs match up with the bellman evaluation domain
@@ -180,16 +350,57 @@ mod tests {
         let mut naive = vec![BlsScalar::zero(); 64];
         for (i, x) in a.iter().enumerate() {
             for (j, y) in b.iter().enumerate() {
-                let mut prod = x.clone();
+                let mut prod = *x;
                 prod.mul_assign(y);
                 naive[i+j].add_assign(prod)
             }
         }
 
         mul_coefficient_domain(&mut a, &mut b);
-        let (omega, _, exp): (BlsScalar, usize, u32) = fft_params(a.len());
+        let (omega, _, exp): (BlsScalar, usize, u32) = fft_params(a.len()).unwrap();
         ifft(&mut a, &omega, exp);
 
         assert_eq!(naive, a);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn domain_matches_transforms() {
+        let mut rng = thread_rng();
+        let a: Vec<BlsScalar> = (0..32).map(|_| BlsScalar::random(&mut rng)).collect();
+        let (omega, _, exp): (BlsScalar, usize, u32) = fft_params(a.len()).unwrap();
+        let domain = Domain::<BlsScalar>::new(a.len()).unwrap();
+
+        type Transform = fn(&mut [BlsScalar], &BlsScalar, u32);
+        type DomainTransform = fn(&Domain<BlsScalar>, &mut [BlsScalar]);
+
+        let transforms: [(Transform, DomainTransform); 4] = [
+            (fft, Domain::fft),
+            (ifft, Domain::ifft),
+            (coset_fft, Domain::coset_fft),
+            (icoset_fft, Domain::icoset_fft),
+        ];
+        for (f, g) in transforms {
+            let (mut x, mut y) = (a.clone(), a.clone());
+            f(&mut x, &omega, exp);
+            g(&domain, &mut y);
+            assert_eq!(x, y);
+        }
+    }
+
+    #[test]
+    fn bn254_domain_limits() {
+        use crate::bn254::Fr;
+
+        let (omega, m, exp): (Fr, usize, u32) = fft_params(1 << 28).unwrap();
+        assert_eq!((m, exp), (1 << 28, 28));
+        assert_eq!(Field::pow_vartime(&omega, [1 << 27]), -Fr::one());
+        assert_eq!(Field::pow_vartime(&omega, [1 << 28]), Fr::one());
+
+        let (omega, _, exp): (Fr, usize, u32) = fft_params(5).unwrap();
+        assert_eq!(exp, 3);
+        assert_eq!(omega.square().square(), -Fr::one());
+
+        assert!(fft_params::<Fr>((1 << 28) + 1).is_none());
+        assert!(fft_params::<Fr>(usize::MAX).is_none());
+    }
+}
@@ -1,130 +1,498 @@
-use core::ops::{SubAssign, MulAssign, AddAssign, Mul, Add};
+use core::ops::{MulAssign, AddAssign, Mul};
 
 use pairing::Engine;
 use pairing::group::Group;
-use crate::{ Proof, Parameters, poly::*, QAP };
+use crate::{ Proof, VerificationKey, poly::* };
+use crate::backend::{ Backend, Software, MSM_BATCH };
+use crate::fixed::FixedBase;
+use crate::source::{ ParameterSource, Query };
+use crate::view::{ Constraints, Poly };
 use ff::{Field, PrimeField};
+use rand_core::RngCore;
+
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::{ wipe, wipe_slice };
 
 #[cfg(not(any(test, feature = "std")))]
-use alloc::vec::Vec;
+use alloc::vec;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProvingError<T> {
+    // The parameter source failed to provide its points
+    Source(T),
+    // The QAP has more constraints than the largest FFT domain of the
+    // scalar field, 2^S points
+    DomainTooLarge,
+    // The scratch region is shorter than `required_scratch`
+    ScratchTooSmall,
+    // The parameters, the QAP and the assignment disagree on the number of
+    // variables or the length of a query
+    Mismatch,
+}
+
+impl<T> From<T> for ProvingError<T> {
+    fn from(e: T) -> Self {
+        ProvingError::Source(e)
+    }
+}
 
-pub fn create_proof<E: Engine>(
-    params: Parameters<E>,
+// `params` and `qap` are either the owned `Parameters` and `QAP` or
+// zero-copy views of them, see `view`. Errors of the source, such as a
+// view finding a point it cannot decode, come back as `Source`.
+pub fn create_proof<E: Engine, P: ParameterSource<E>>(
+    params: P,
     inputs: &[E::Fr],
     aux: &[E::Fr],
     r: E::Fr,
     s: E::Fr,
-    qap: QAP<E::Fr>,
+    qap: impl Constraints<E::Fr>,
     num_constraints: usize
-) -> Proof<E>
+) -> Result<Proof<E>, ProvingError<P::Error>>
+{
+    create_proof_with_backend(&Software, params, inputs, aux, r, s, qap, num_constraints)
+}
+
+// Number of field elements `create_proof_with_scratch` needs for `qap`,
+// None if the field has no domain for its constraints
+pub fn required_scratch<S: PrimeField>(qap: &impl Constraints<S>) -> Option<usize> {
+    let (_, m, _): (S, usize, u32) = fft_params(qap.num_constraints())?;
+    Some(2 * m)
+}
+
+// Same as `create_proof`, but the polynomial buffers, the only allocation
+// that grows with the domain, live in the caller's scratch region.
+// `scratch` must hold at least `required_scratch(qap)` elements, its
+// contents are overwritten.
+#[allow(clippy::too_many_arguments)]
+pub fn create_proof_with_scratch<E: Engine, P: ParameterSource<E>>(
+    mut params: P,
+    inputs: &[E::Fr],
+    aux: &[E::Fr],
+    r: E::Fr,
+    s: E::Fr,
+    qap: &impl Constraints<E::Fr>,
+    num_constraints: usize,
+    scratch: &mut [E::Fr]
+) -> Result<Proof<E>, ProvingError<P::Error>>
+{
+    prove(&Software, &mut params, inputs, aux, r, s, qap, num_constraints, scratch)
+}
+
+// Same as `create_proof_with_scratch`, with the query points read from a
+// `ParameterSource` one chunk at a time instead of from memory
+#[allow(clippy::too_many_arguments)]
+pub fn create_proof_from_source<E: Engine, P: ParameterSource<E>>(
+    source: &mut P,
+    inputs: &[E::Fr],
+    aux: &[E::Fr],
+    r: E::Fr,
+    s: E::Fr,
+    qap: &impl Constraints<E::Fr>,
+    num_constraints: usize,
+    scratch: &mut [E::Fr]
+) -> Result<Proof<E>, ProvingError<P::Error>>
+{
+    prove(&Software, source, inputs, aux, r, s, qap, num_constraints, scratch)
+}
+
+// Same as `create_proof`, with the MSMs, FFTs and scalar multiplications
+// performed by `backend`
+#[allow(clippy::too_many_arguments)]
+pub fn create_proof_with_backend<E: Engine, B: Backend<E>, P: ParameterSource<E>>(
+    backend: &B,
+    mut params: P,
+    inputs: &[E::Fr],
+    aux: &[E::Fr],
+    r: E::Fr,
+    s: E::Fr,
+    qap: impl Constraints<E::Fr>,
+    num_constraints: usize
+) -> Result<Proof<E>, ProvingError<P::Error>>
+{
+    let (_, m, _): (E::Fr, usize, u32) = fft_params(num_constraints).ok_or(ProvingError::DomainTooLarge)?;
+    let mut scratch = vec![E::Fr::zero(); 2 * m];
+    prove(backend, &mut params, inputs, aux, r, s, &qap, num_constraints, &mut scratch)
+}
+
+// Reads a G1 query chunk by chunk and hands it to `f` in runs of at most
+// `MSM_BATCH` points, together with the index of the first point of each
+pub(crate) fn for_each_g1<E, P>(
+    source: &mut P,
+    query: Query,
+    mut f: impl FnMut(usize, &[E::G1Affine])
+) -> Result<(), P::Error>
+where
+    E: Engine,
+    P: ParameterSource<E>,
+{
+    let n = source.len(query);
+    let mut i = 0;
+    while i < n {
+        let chunk = source.g1(query, i)?;
+        assert!(!chunk.is_empty());
+        for bases in chunk[..chunk.len().min(n - i)].chunks(MSM_BATCH) {
+            f(i, bases);
+            i += bases.len();
+        }
+    }
+    Ok(())
+}
+
+// Same as `for_each_g1`, for the B query in G2
+pub(crate) fn for_each_g2<E, P>(source: &mut P, mut f: impl FnMut(usize, &[E::G2Affine])) -> Result<(), P::Error>
+where
+    E: Engine,
+    P: ParameterSource<E>,
+{
+    let n = source.len(Query::BG2);
+    let mut i = 0;
+    while i < n {
+        let chunk = source.g2(i)?;
+        assert!(!chunk.is_empty());
+        for bases in chunk[..chunk.len().min(n - i)].chunks(MSM_BATCH) {
+            f(i, bases);
+            i += bases.len();
+        }
+    }
+    Ok(())
+}
+
+// Sum of p_i * scalar(i) over a whole query, read in chunks and handed to
+// the backend in batches, or looked up in the source's fixed-base tables
+// if it has them for the query
+pub(crate) fn msm_g1<E, B, P>(
+    backend: &B,
+    source: &mut P,
+    query: Query,
+    scalar: impl Fn(usize) -> E::Fr
+) -> Result<E::G1, P::Error>
+where
+    E: Engine,
+    B: Backend<E>,
+    P: ParameterSource<E>,
 {
+    let mut acc = E::G1::identity();
 
-    fn eval<S: PrimeField>(
-        input_assignment: &[S],
-        aux_assignment: &[S],
-        output: &mut [S],
-        input: Vec<(usize, Vec<(S, usize)>)>,
-        p: usize,
-    ) {
-        for (i, v) in input.into_iter() {
-            if i < p {
-                for (mut x, u) in v.into_iter() {
-                    x.mul_assign(input_assignment[i]);
-                    output[u].add_assign(x);
-                }
-            } else {
-                for (mut x, u) in v.into_iter() {
-                    x.mul_assign(aux_assignment[i - p]);
-                    output[u].add_assign(x);
-                }
-            }
+    if let Some(tables) = source.fixed_base().filter(|t| t.covers(query)) {
+        for i in 0..source.len(query) {
+            acc.add_assign(tables.mul_query(query, i, &scalar(i)));
         }
+        return Ok(acc);
     }
 
-    let h = {
-        let (omega, m, exp): (E::Fr, usize, u32) = fft_params(num_constraints);
-        let mut at = vec![E::Fr::zero(); m];
-        let mut bt = vec![E::Fr::zero(); m];
-        let mut ct = vec![E::Fr::zero(); m];
-    
-        eval(inputs, aux, &mut at, qap.a, inputs.len());
-        eval(inputs, aux, &mut bt, qap.b, inputs.len());
-        eval(inputs, aux, &mut ct, qap.c, inputs.len());
-    
-        coset_mul_assign(&mut at, bt);
-        ifft(&mut ct, &omega, exp);
-        coset_fft(&mut ct, &omega, exp);
-        sub_eval_domain(&mut at, ct);
-
-        let zinv = {
-            let mut t = <E::Fr as PrimeField>::multiplicative_generator();
-            t = t.pow_vartime(&[at.len() as u64]);
-            t.sub_assign(&E::Fr::one());
-            t.invert().unwrap()
-        };
-
-        for x in at.iter_mut() {
-            x.mul_assign(&zinv);
+    let mut scalars = [E::Fr::zero(); MSM_BATCH];
+    for_each_g1(source, query, |i, bases| {
+        for (j, x) in scalars[..bases.len()].iter_mut().enumerate() {
+            *x = scalar(i + j);
         }
+        acc.add_assign(backend.msm_g1(bases, &scalars[..bases.len()]));
+    })?;
 
-        icoset_fft(&mut at, &omega, exp);
+    #[cfg(any(test, feature = "zeroize"))]
+    wipe_slice(&mut scalars, E::Fr::zero());
 
-        let mut acc = E::G1::identity();
+    Ok(acc)
+}
 
-        at.truncate(at.len() - 1);
-        for (i, x) in at.iter().enumerate() {
-            let t = params.h[i].mul(x);
-            acc.add_assign(t);
+pub(crate) fn msm_g2<E, B, P>(backend: &B, source: &mut P, scalar: impl Fn(usize) -> E::Fr) -> Result<E::G2, P::Error>
+where
+    E: Engine,
+    B: Backend<E>,
+    P: ParameterSource<E>,
+{
+    let mut acc = E::G2::identity();
+    let mut scalars = [E::Fr::zero(); MSM_BATCH];
+    for_each_g2(source, |i, bases| {
+        for (j, x) in scalars[..bases.len()].iter_mut().enumerate() {
+            *x = scalar(i + j);
         }
+        acc.add_assign(backend.msm_g2(bases, &scalars[..bases.len()]));
+    })?;
+
+    #[cfg(any(test, feature = "zeroize"))]
+    wipe_slice(&mut scalars, E::Fr::zero());
+
+    Ok(acc)
+}
+
+#[allow(unused_mut, clippy::too_many_arguments)]
+fn prove<E: Engine, B: Backend<E>, P: ParameterSource<E>>(
+    backend: &B,
+    source: &mut P,
+    inputs: &[E::Fr],
+    aux: &[E::Fr],
+    mut r: E::Fr,
+    mut s: E::Fr,
+    qap: &impl Constraints<E::Fr>,
+    num_constraints: usize,
+    scratch: &mut [E::Fr]
+) -> Result<Proof<E>, ProvingError<P::Error>>
+{
+    let witness = |i: usize| if i < inputs.len() { inputs[i] } else { aux[i - inputs.len()] };
+
+    let eval = |output: &mut [E::Fr], poly: Poly| {
+        qap.for_each_term(poly, &mut |i, mut x, u| {
+            x.mul_assign(witness(i));
+            output[u].add_assign(x);
+        });
+    };
+
+    let (omega, m, exp): (E::Fr, usize, u32) = fft_params(num_constraints).ok_or(ProvingError::DomainTooLarge)?;
+    if scratch.len() < 2 * m {
+        return Err(ProvingError::ScratchTooSmall);
+    }
+    let (at, bt) = scratch[..2 * m].split_at_mut(m);
+
+    let mut h = {
+        at.fill(E::Fr::zero());
+        bt.fill(E::Fr::zero());
+        eval(at, Poly::A);
+        eval(bt, Poly::B);
+        let fft = |a: &mut [E::Fr], omega: &E::Fr, exp| backend.fft(a, omega, exp);
+        coset_mul_assign_with(at, bt, &fft);
+
+        // B is no longer needed, its half of the scratch holds C from here on
+        let ct = bt;
+        ct.fill(E::Fr::zero());
+        eval(ct, Poly::C);
+        divide_by_z(at, ct, &omega, exp, &fft);
 
-        acc
+        // h(X) has degree m - 2, the top coefficient is zero
+        assert!(source.len(Query::H) >= m - 1);
+        let at = &*at;
+        msm_g1(backend, source, Query::H, |i| if i < m - 1 { at[i] } else { E::Fr::zero() })?
     };
 
-    assert_eq!(aux.len(), params.l.len());
-    let l = params.l.iter()
-        .zip(aux.iter())
-        .fold(E::G1::identity(), |acc, (x, y)| acc.add(x.mul(y)));
+    #[cfg(any(test, feature = "zeroize"))]
+    wipe_slice(&mut scratch[..2 * m], E::Fr::zero());
 
-    let augmented_inputs: Vec<&E::Fr> = inputs.iter().chain(aux.iter()).collect();
+    assert_eq!(aux.len(), source.len(Query::L));
+    let mut l = msm_g1(backend, source, Query::L, |i| aux[i])?;
 
-    assert_eq!(params.a_g1.len(), qap.a_constraints.len());
-    let at_g1 = params.a_g1.iter()
-        .zip(qap.a_constraints.iter())
-        .fold(E::G1::identity(), |acc, (x, y)| acc.add(x.mul(augmented_inputs[*y])));
+    assert_eq!(source.len(Query::A), qap.num_variables(Poly::A));
+    let mut at_g1 = msm_g1(backend, source, Query::A, |i| witness(qap.variable(Poly::A, i)))?;
 
-    assert_eq!(params.b_g1.len(), qap.b_constraints.len());
-    let bt_g1 = params.b_g1.iter()
-        .zip(qap.b_constraints.iter())
-        .fold(E::G1::identity(), |acc, (x, y)| acc.add(x.mul(augmented_inputs[*y])));
-    
-    let bt_g2 = params.b_g2.iter()
-        .zip(qap.b_constraints.iter())
-        .fold(E::G2::identity(), |acc, (x, y)| acc.add(x.mul(augmented_inputs[*y])));
+    assert_eq!(source.len(Query::BG1), qap.num_variables(Poly::B));
+    let mut bt_g1 = msm_g1(backend, source, Query::BG1, |i| witness(qap.variable(Poly::B, i)))?;
+
+    assert_eq!(source.len(Query::BG2), qap.num_variables(Poly::B));
+    let mut bt_g2 = msm_g2(backend, source, |i| witness(qap.variable(Poly::B, i)))?;
+
+    let proof = assemble(backend, source.vk(), source.fixed_base(), h, l, at_g1, bt_g1, bt_g2, &r, &s);
+
+    #[cfg(any(test, feature = "zeroize"))]
+    {
+        wipe(&mut r, E::Fr::zero());
+        wipe(&mut s, E::Fr::zero());
+        for p in [&mut h, &mut l, &mut at_g1, &mut bt_g1] {
+            wipe(p, E::G1::identity());
+        }
+        wipe(&mut bt_g2, E::G2::identity());
+    }
+
+    Ok(proof)
+}
+
+// Turns A * B evaluated on the coset, in `at`, and C evaluated on the
+// domain, in `ct`, into the coefficients of h(X) = (A * B - C) / Z(X) in
+// `at`. `ct` is overwritten.
+pub(crate) fn divide_by_z<S: PrimeField>(at: &mut [S], ct: &mut [S], omega: &S, exp: u32, fft: &impl Fft<S>) {
+    ifft_with(ct, omega, exp, fft);
+    coset_fft_with(ct, omega, exp, fft);
+    sub_eval_domain(at, ct);
+
+    let zinv = {
+        let mut t = S::multiplicative_generator();
+        t = t.pow_vartime([at.len() as u64]);
+        t.sub_assign(&S::one());
+        t.invert().unwrap()
+    };
+
+    for x in at.iter_mut() {
+        x.mul_assign(&zinv);
+    }
+
+    icoset_fft_with(at, omega, exp, fft);
+}
+
+// Combines the MSM results with the verification key and r and s
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn assemble<E: Engine, B: Backend<E>>(
+    backend: &B,
+    vk: &VerificationKey<E>,
+    fixed: Option<&dyn FixedBase<E>>,
+    h: E::G1,
+    l: E::G1,
+    at_g1: E::G1,
+    bt_g1: E::G1,
+    bt_g2: E::G2,
+    r: &E::Fr,
+    s: &E::Fr
+) -> Proof<E>
+{
+    let delta_g1 = |x: &E::Fr| fixed.map_or_else(|| backend.mul_g1(vk.delta_g1.into(), x), |t| t.mul_delta_g1(x));
+    let delta_g2 = |x: &E::Fr| fixed.map_or_else(|| backend.mul_g2(vk.delta_g2.into(), x), |t| t.mul_delta_g2(x));
+    let beta_g1 = |x: &E::Fr| fixed.map_or_else(|| backend.mul_g1(vk.beta_g1.into(), x), |t| t.mul_beta_g1(x));
 
     let mut a = E::G1::identity();
-    a.add_assign(params.vk.alpha_g1);
+    a.add_assign(vk.alpha_g1);
     a.add_assign(at_g1);
-    a.add_assign(params.vk.delta_g1.mul(r));
+    a.add_assign(delta_g1(r));
 
     let mut b = E::G2::identity();
-    b.add_assign(params.vk.beta_g2);
+    b.add_assign(vk.beta_g2);
     b.add_assign(bt_g2);
-    b.add_assign(params.vk.delta_g2.mul(s));
+    b.add_assign(delta_g2(s));
 
-    let mut c = E::G1::identity();
-    c.add_assign(bt_g1);
-    c.add_assign(params.vk.delta_g1.mul(s));
-    c.add_assign(params.vk.beta_g1);
-    c.mul_assign(r);
+    // C = r * (beta_g1 + bt_g1 + s * delta_g1) + h + l + s * A - r * s * delta_g1,
+    // where the two delta_g1 terms cancel
+    let mut c = backend.mul_g1(bt_g1, r);
+    c.add_assign(beta_g1(r));
     c.add_assign(h);
     c.add_assign(l);
-    c.add_assign(a.mul(s));
-    c.sub_assign(params.vk.delta_g1.mul(r.mul(s)));
-    
-    
+    c.add_assign(backend.mul_g1(a, s));
+
+    Proof {
+        a: a.into(),
+        b: b.into(),
+        c: c.into(),
+    }
+}
+
+// Re-randomizes a proof using only the verification key. For random
+// t != 0 and u, (A/t, t*B + t*u*delta, C + u*A) is a valid proof for the
+// same public inputs, distributed independently of the original.
+pub fn rerandomize_proof<E: Engine, R: RngCore>(
+    vk: &VerificationKey<E>,
+    proof: &Proof<E>,
+    mut rng: R,
+) -> Proof<E>
+{
+    let t = loop {
+        let t = E::Fr::random(&mut rng);
+        if !bool::from(t.is_zero()) {
+            break t;
+        }
+    };
+    let u = E::Fr::random(&mut rng);
+
+    let a = proof.a.mul(t.invert().unwrap());
+
+    let mut b = proof.b.mul(t);
+    b.add_assign(vk.delta_g2.mul(t.mul(u)));
+
+    let mut c: E::G1 = proof.c.into();
+    c.add_assign(proof.a.mul(u));
+
     Proof {
         a: a.into(),
         b: b.into(),
         c: c.into(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+    use bls12_381::{ Bls12, Scalar };
+
+    use crate::QAP;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn rerandomized_proof_verifies() {
+        let fixture = cube_fixture(3);
+        let proof = fixture.prove();
+        let vk = &fixture.params.vk;
+
+        let rerandomized = rerandomize_proof(vk, &proof, thread_rng());
+        assert_ne!(proof.a, rerandomized.a);
+        assert_ne!(proof.b, rerandomized.b);
+        assert_ne!(proof.c, rerandomized.c);
+
+        assert!(verify_proof(&rerandomized, fixture.public_inputs(), vk.clone()).is_ok());
+
+        let again = rerandomize_proof(vk, &rerandomized, thread_rng());
+        assert!(verify_proof(&again, fixture.public_inputs(), vk.clone()).is_ok());
+    }
+
+    #[test]
+    fn scratch_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        assert_eq!(fixture.qap.num_constraints(), fixture.num_constraints);
+        let mut scratch = vec![Scalar::from(7); required_scratch(&fixture.qap).unwrap()];
+        let proof = create_proof_with_scratch::<Bls12, _>(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        ).unwrap();
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+    }
+
+    #[test]
+    fn domain_too_large() {
+        let fixture = cube_fixture(3);
+        let (params, qap) = (&fixture.params, &fixture.qap);
+        let (one, n) = (Scalar::one(), usize::MAX);
+        let huge = QAP { c: vec![(0, vec![(one, n - 1)])], ..QAP::default() };
+        assert_eq!(required_scratch(&huge), None);
+        assert_eq!(create_proof::<Bls12, _>(params, &fixture.inputs, &fixture.aux, one, one, qap, n).err(), Some(ProvingError::DomainTooLarge));
+        let mut scratch = vec![Scalar::zero(); required_scratch(qap).unwrap()];
+        assert_eq!(
+            create_proof_with_scratch::<Bls12, _>(params, &fixture.inputs, &fixture.aux, one, one, qap, n, &mut scratch).err(),
+            Some(ProvingError::DomainTooLarge)
+        );
+        assert!(crate::task::ProverTask::new(params, &fixture.inputs, &fixture.aux, one, one, qap, n).is_err());
+    }
+
+    #[test]
+    fn scratch_too_small() {
+        let fixture = cube_fixture(3);
+        let mut scratch = vec![Scalar::zero(); required_scratch(&fixture.qap).unwrap() - 1];
+        let proof = create_proof_with_scratch::<Bls12, _>(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::one(),
+            Scalar::one(),
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        );
+        assert_eq!(proof.err(), Some(ProvingError::ScratchTooSmall));
+    }
+
+    #[test]
+    fn rerandomized_proof_keeps_statement() {
+        let fixture = cube_fixture(3);
+        let proof = fixture.prove();
+        let vk = &fixture.params.vk;
+
+        let rerandomized = rerandomize_proof(vk, &proof, thread_rng());
+        assert!(verify_proof(&rerandomized, &[Scalar::from(36)], vk.clone()).is_err());
+    }
+}
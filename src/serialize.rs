@@ -0,0 +1,341 @@
+// Serde support for the public types, behind the `serde` feature.
+//
+// Points are their compressed `GroupEncoding` and field elements their
+// `PrimeField` representation, so both have a single canonical form. In
+// human-readable formats such as JSON these are lowercase hex strings, in
+// binary formats byte strings. Deserializing accepts only lowercase hex,
+// two digits per byte, and rejects points that are not in the prime-order
+// subgroup and field elements that are not below the modulus. Indices are
+// serialized as u64.
+//
+// A deserialized `QAP` is checked by `QAP::new`. `Parameters` come apart
+// from their QAP, so they are checked against it with `Parameters::check`
+// before proving.
+//
+// The derives in the crate root use the modules below through
+// `#[serde(with = ...)]`, as the curve types do not implement serde
+// themselves. `Trapdoor` has no serde support on purpose.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use ff::PrimeField;
+use pairing::group::GroupEncoding;
+use serde::de::{ self, Deserialize, Deserializer, SeqAccess, Visitor };
+use serde::ser::{ Serialize, Serializer };
+
+use crate::QAP;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ string::String, vec::Vec };
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+fn write_bytes<S: Serializer>(b: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    if s.is_human_readable() {
+        let hex: String = b.iter().flat_map(|x| [HEX[(x >> 4) as usize] as char, HEX[(x & 15) as usize] as char]).collect();
+        s.serialize_str(&hex)
+    } else {
+        s.serialize_bytes(b)
+    }
+}
+
+// Bytes of a representation of fixed length, as hex or raw
+struct ReprVisitor<R>(PhantomData<R>);
+
+impl<'de, R: Default + AsMut<[u8]>> Visitor<'de> for ReprVisitor<R> {
+    type Value = R;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bytes, raw or in hex", R::default().as_mut().len())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<R, E> {
+        let mut repr = R::default();
+        let out = repr.as_mut();
+        if v.len() != 2 * out.len() {
+            return Err(E::invalid_length(v.len() / 2, &self));
+        }
+        let digit = |d: &u8| HEX.iter().position(|h| h == d).map(|i| i as u8);
+        for (x, pair) in out.iter_mut().zip(v.as_bytes().chunks(2)) {
+            match (digit(&pair[0]), digit(&pair[1])) {
+                (Some(hi), Some(lo)) => *x = hi << 4 | lo,
+                _ => return Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+        Ok(repr)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<R, E> {
+        let mut repr = R::default();
+        if v.len() != repr.as_mut().len() {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        repr.as_mut().copy_from_slice(v);
+        Ok(repr)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<R, A::Error> {
+        let mut repr = R::default();
+        let out = repr.as_mut();
+        let len = out.len();
+        for (i, x) in out.iter_mut().enumerate() {
+            *x = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(len + 1, &self));
+        }
+        Ok(repr)
+    }
+}
+
+fn read_repr<'de, R: Default + AsMut<[u8]>, D: Deserializer<'de>>(d: D) -> Result<R, D::Error> {
+    if d.is_human_readable() {
+        d.deserialize_str(ReprVisitor(PhantomData))
+    } else {
+        d.deserialize_bytes(ReprVisitor(PhantomData))
+    }
+}
+
+struct Point<G>(G);
+
+impl<G: GroupEncoding> Serialize for Point<&G> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        write_bytes(self.0.to_bytes().as_ref(), s)
+    }
+}
+
+impl<'de, G: GroupEncoding> Deserialize<'de> for Point<G> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let repr = read_repr(d)?;
+        Option::from(G::from_bytes(&repr)).map(Point).ok_or_else(|| de::Error::custom("invalid point"))
+    }
+}
+
+struct Scalar<S>(S);
+
+impl<F: PrimeField> Serialize for Scalar<&F> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        write_bytes(self.0.to_repr().as_ref(), s)
+    }
+}
+
+impl<'de, F: PrimeField> Deserialize<'de> for Scalar<F> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let repr = read_repr(d)?;
+        Option::from(F::from_repr(repr)).map(Scalar).ok_or_else(|| de::Error::custom("invalid field element"))
+    }
+}
+
+pub(crate) mod point {
+    use super::*;
+
+    pub fn serialize<G: GroupEncoding, S: Serializer>(p: &G, s: S) -> Result<S::Ok, S::Error> {
+        Point(p).serialize(s)
+    }
+
+    pub fn deserialize<'de, G: GroupEncoding, D: Deserializer<'de>>(d: D) -> Result<G, D::Error> {
+        Point::deserialize(d).map(|p| p.0)
+    }
+}
+
+pub(crate) mod points {
+    use super::*;
+
+    pub fn serialize<G: GroupEncoding, S: Serializer>(v: &[G], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(v.iter().map(Point))
+    }
+
+    pub fn deserialize<'de, G: GroupEncoding, D: Deserializer<'de>>(d: D) -> Result<Vec<G>, D::Error> {
+        Vec::<Point<G>>::deserialize(d).map(|v| v.into_iter().map(|p| p.0).collect())
+    }
+}
+
+// The queries of a QAP: (variable, [(coefficient, constraint)])
+pub(crate) mod query {
+    use super::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn serialize<F: PrimeField, S: Serializer>(v: &[(usize, Vec<(F, usize)>)], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(v.iter().map(|(i, terms)| {
+            (*i, terms.iter().map(|(x, j)| (Scalar(x), *j)).collect::<Vec<_>>())
+        }))
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn deserialize<'de, F: PrimeField, D: Deserializer<'de>>(d: D) -> Result<Vec<(usize, Vec<(F, usize)>)>, D::Error> {
+        Vec::<(usize, Vec<(Scalar<F>, usize)>)>::deserialize(d)
+            .map(|v| v.into_iter().map(|(i, terms)| (i, terms.into_iter().map(|(x, j)| (x.0, j)).collect())).collect())
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename = "QAP", bound = "")]
+#[allow(clippy::type_complexity)]
+struct QAPParts<S: PrimeField> {
+    #[serde(with = "query")]
+    a: Vec<(usize, Vec<(S, usize)>)>,
+    #[serde(with = "query")]
+    b: Vec<(usize, Vec<(S, usize)>)>,
+    #[serde(with = "query")]
+    c: Vec<(usize, Vec<(S, usize)>)>,
+    a_constraints: Vec<usize>,
+    b_constraints: Vec<usize>,
+}
+
+impl<'de, S: PrimeField> Deserialize<'de> for QAP<S> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let q = QAPParts::deserialize(d)?;
+        QAP::new(q.a, q.b, q.c, q.a_constraints, q.b_constraints).map_err(|_| de::Error::custom("inconsistent QAP"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, G1Affine, Scalar as BlsScalar };
+    use pairing::group::GroupEncoding;
+    use serde::{ Deserialize, Serialize };
+    use serde_test::{ assert_ser_tokens, assert_tokens, Configure, Token };
+
+    use crate::{ DecodingError, Parameters, Proof, VerificationKey, QAP };
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+
+    #[test]
+    fn json_roundtrip_verifies() {
+        let fixture = cube_fixture(3);
+        let proof = fixture.prove();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        // The compressed generator of G1, as in the bls12_381 specification
+        let generator = serde_json::to_string(&Proof::<Bls12> { a: G1Affine::generator(), ..proof.clone() }).unwrap();
+        assert!(generator.starts_with("{\"a\":\"97f1d3a7"));
+        let proof: Proof<Bls12> = serde_json::from_str(&json).unwrap();
+
+        let json = serde_json::to_string(&fixture.params).unwrap();
+        let params: Parameters<Bls12> = serde_json::from_str(&json).unwrap();
+        assert_eq!(params.h, fixture.params.h);
+        assert_eq!(params.b_g2, fixture.params.b_g2);
+        let vk: VerificationKey<Bls12> = serde_json::from_str(&serde_json::to_string(&params.vk).unwrap()).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), vk).is_ok());
+
+        let qap: QAP<BlsScalar> = serde_json::from_str(&serde_json::to_string(&fixture.qap).unwrap()).unwrap();
+        assert_eq!(qap.a, fixture.qap.a);
+        assert_eq!(qap.c, fixture.qap.c);
+        assert_eq!(qap.b_constraints, fixture.qap.b_constraints);
+    }
+
+    #[test]
+    fn binary_formats_get_raw_bytes() {
+        let qap = QAP {
+            a: vec![(0, vec![(BlsScalar::from(2), 1)])],
+            ..QAP::default()
+        };
+        let mut two = [0; 32];
+        two[0] = 2;
+        let two: &'static [u8] = Box::leak(Box::new(two));
+        let empty = [Token::Seq { len: Some(0) }, Token::SeqEnd];
+        assert_ser_tokens(&qap.compact(), &[
+            &[
+                Token::Struct { name: "QAP", len: 5 },
+                Token::Str("a"),
+                Token::Seq { len: Some(1) },
+                Token::Tuple { len: 2 },
+                Token::U64(0),
+                Token::Seq { len: Some(1) },
+                Token::Tuple { len: 2 },
+                Token::Bytes(two),
+                Token::U64(1),
+                Token::TupleEnd,
+                Token::SeqEnd,
+                Token::TupleEnd,
+                Token::SeqEnd,
+            ][..],
+            &[Token::Str("b")], &empty,
+            &[Token::Str("c")], &empty,
+            &[Token::Str("a_constraints")], &empty,
+            &[Token::Str("b_constraints")], &empty,
+            &[Token::StructEnd],
+        ].concat());
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Point(#[serde(with = "super::point")] G1Affine);
+        let g = G1Affine::generator();
+        let bytes: &'static [u8] = Box::leak(g.to_bytes().as_ref().into());
+        assert_tokens(&Point(g).compact(), &[Token::NewtypeStruct { name: "Point" }, Token::Bytes(bytes)]);
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let fixture = cube_fixture(3);
+        let json = serde_json::to_string(&fixture.prove()).unwrap();
+        let a = &json[6..6 + 96];
+
+        // Not on the curve, wrong length, not hex
+        let mut x = a.as_bytes().to_vec();
+        x[95] = if x[95] == b'0' { b'1' } else { b'0' };
+        let bad = json.replace(a, core::str::from_utf8(&x).unwrap());
+        assert!(serde_json::from_str::<Proof<Bls12>>(&bad).is_err());
+        assert!(serde_json::from_str::<Proof<Bls12>>(&json.replace(a, &a[2..])).is_err());
+        assert!(serde_json::from_str::<Proof<Bls12>>(&json.replace(a, &a.replace(&a[..2], "zz"))).is_err());
+
+        // Field elements must be below the modulus
+        let qap = QAP { a: vec![(0, vec![(BlsScalar::from(2), 1)])], a_constraints: vec![0], ..QAP::default() };
+        let json = serde_json::to_string(&qap).unwrap();
+        let two = format!("\"02{}\"", "0".repeat(62));
+        assert!(json.contains(&two));
+        assert!(serde_json::from_str::<QAP<BlsScalar>>(&json).is_ok());
+        let bad = json.replace(&two, &format!("\"{}\"", "f".repeat(64)));
+        assert!(serde_json::from_str::<QAP<BlsScalar>>(&bad).is_err());
+
+        // Exactly two lowercase hex digits per byte
+        let ten = format!("\"0a{}\"", "0".repeat(62));
+        for digits in ["0A", "+2", "-2", " 2"] {
+            let bad = json.replace(&two, &two.replacen("02", digits, 1));
+            assert!(serde_json::from_str::<QAP<BlsScalar>>(&bad).is_err());
+        }
+        assert!(serde_json::from_str::<QAP<BlsScalar>>(&json.replace(&two, &ten)).is_ok());
+        assert!(serde_json::from_str::<QAP<BlsScalar>>(&json.replace(&two, &ten.to_uppercase())).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_qap_and_parameters() {
+        let fixture = cube_fixture(3);
+        let qap = &fixture.qap;
+        let params = &fixture.params;
+        assert!(params.check(qap).is_ok());
+        let parse = |q: &QAP<BlsScalar>| serde_json::from_str::<QAP<BlsScalar>>(&serde_json::to_string(q).unwrap());
+        assert!(parse(qap).is_ok());
+
+        // A variable list out of order, one short of a variable of A, one
+        // with a variable B does not have, and a variable twice in C
+        let mut reversed = qap.clone();
+        reversed.a_constraints.reverse();
+        let mut short = qap.clone();
+        short.a_constraints.pop();
+        let mut extra = qap.clone();
+        extra.b_constraints.push(qap.a_constraints[qap.a_constraints.len() - 1] + 100);
+        let mut twice = qap.clone();
+        twice.c.push(qap.c[0].clone());
+        for bad in [&reversed, &short, &extra, &twice] {
+            assert!(parse(bad).is_err());
+            assert_eq!(
+                QAP::new(bad.a.clone(), bad.b.clone(), bad.c.clone(), bad.a_constraints.clone(), bad.b_constraints.clone()).err(),
+                Some(DecodingError::Mismatch)
+            );
+        }
+
+        // Parameters for another QAP
+        let mut few = params.clone();
+        few.a_g1.pop();
+        let mut no_h = params.clone();
+        no_h.h.clear();
+        let mut no_l = params.clone();
+        no_l.l.pop();
+        let mut b_g2 = params.clone();
+        b_g2.b_g2.push(b_g2.b_g2[0]);
+        for bad in [&few, &no_h, &no_l, &b_g2] {
+            assert_eq!(bad.check(qap), Err(DecodingError::Mismatch));
+        }
+    }
+}
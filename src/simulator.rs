@@ -0,0 +1,183 @@
+use core::fmt;
+use core::ops::{AddAssign, Mul, MulAssign, SubAssign};
+
+use pairing::Engine;
+use pairing::group::Group;
+use ff::Field;
+use rand_core::RngCore;
+
+use crate::{ Proof, VerificationKey };
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::wipe;
+
+// Toxic waste of a setup. Anyone holding it can forge proofs for any
+// statement, so it is only meant for tests, and compiled only with the
+// `simulator` feature. It deliberately implements neither Clone nor any
+// kind of (de)serialization, its Debug output is redacted and with the
+// `zeroize` feature it is wiped on drop.
+pub struct Trapdoor<E: Engine> {
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+}
+
+impl<E: Engine> Trapdoor<E> {
+    pub fn new(alpha: E::Fr, beta: E::Fr, gamma: E::Fr, delta: E::Fr, tau: E::Fr) -> Self {
+        Trapdoor { alpha, beta, gamma, delta, tau }
+    }
+
+    pub fn random<R: RngCore>(mut rng: R) -> Self {
+        Trapdoor {
+            alpha: E::Fr::random(&mut rng),
+            beta: E::Fr::random(&mut rng),
+            gamma: E::Fr::random(&mut rng),
+            delta: E::Fr::random(&mut rng),
+            tau: E::Fr::random(&mut rng),
+        }
+    }
+
+    pub fn alpha(&self) -> &E::Fr {
+        &self.alpha
+    }
+
+    pub fn beta(&self) -> &E::Fr {
+        &self.beta
+    }
+
+    pub fn gamma(&self) -> &E::Fr {
+        &self.gamma
+    }
+
+    pub fn delta(&self) -> &E::Fr {
+        &self.delta
+    }
+
+    pub fn tau(&self) -> &E::Fr {
+        &self.tau
+    }
+}
+
+impl<E: Engine> fmt::Debug for Trapdoor<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Trapdoor { .. }")
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<E: Engine> zeroize::Zeroize for Trapdoor<E> {
+    fn zeroize(&mut self) {
+        wipe(&mut self.alpha, E::Fr::zero());
+        wipe(&mut self.beta, E::Fr::zero());
+        wipe(&mut self.gamma, E::Fr::zero());
+        wipe(&mut self.delta, E::Fr::zero());
+        wipe(&mut self.tau, E::Fr::zero());
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<E: Engine> Drop for Trapdoor<E> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<E: Engine> zeroize::ZeroizeOnDrop for Trapdoor<E> {}
+
+// Produces a proof for arbitrary public inputs without a witness.
+// Picks A = a*G1 and B = b*G2 at random and solves the verification
+// equation for C:
+// C = ((a*b - alpha*beta)/delta)*G1 - (gamma/delta)*IC
+// The verification key must come from a setup over the standard generators
+// with the same trapdoor.
+pub fn simulate_proof<E: Engine, R: RngCore>(
+    trapdoor: &Trapdoor<E>,
+    vk: &VerificationKey<E>,
+    public_inputs: &[E::Fr],
+    mut rng: R,
+) -> Proof<E>
+{
+    assert_eq!(public_inputs.len() + 1, vk.ic.len());
+
+    let a = E::Fr::random(&mut rng);
+    let b = E::Fr::random(&mut rng);
+    let delta_inv = trapdoor.delta.invert().unwrap();
+
+    let mut ic: E::G1 = vk.ic[0].into();
+    for (i, x) in public_inputs.iter().zip(vk.ic.iter().skip(1)) {
+        ic.add_assign(x.mul(i));
+    }
+
+    let mut s = a.mul(b);
+    s.sub_assign(trapdoor.alpha.mul(trapdoor.beta));
+    s.mul_assign(delta_inv);
+
+    let mut c = E::G1::generator().mul(s);
+    c.sub_assign(ic.mul(trapdoor.gamma.mul(delta_inv)));
+
+    Proof {
+        a: E::G1::generator().mul(a).into(),
+        b: E::G2::generator().mul(b).into(),
+        c: c.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+    use bls12_381::{ Bls12, Scalar };
+
+    use crate::assignments::generate_parameters_with_trapdoor;
+    use crate::tests::{ cube_fixture, CubeDemo };
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn simulated_proof_verifies() {
+        let (params, trapdoor) = generate_parameters_with_trapdoor::<Bls12, _, _>(
+            CubeDemo::<Scalar> { x: None },
+            thread_rng(),
+        ).unwrap();
+
+        // Any statement, true or not, can be simulated
+        for out in [35, 36, 0] {
+            let inputs = [Scalar::from(out)];
+            let proof = simulate_proof(&trapdoor, &params.vk, &inputs, thread_rng());
+            assert!(verify_proof(&proof, &inputs, params.vk.clone()).is_ok());
+        }
+    }
+
+    #[test]
+    fn trapdoor_parameters_are_sound() {
+        let (params, _) = generate_parameters_with_trapdoor::<Bls12, _, _>(
+            CubeDemo::<Scalar> { x: None },
+            thread_rng(),
+        ).unwrap();
+
+        let mut fixture = cube_fixture(3);
+        fixture.params = params;
+        let proof = fixture.prove();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+    }
+
+    #[test]
+    fn trapdoor_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut trapdoor = Trapdoor::<Bls12>::random(thread_rng());
+        trapdoor.zeroize();
+        for x in [trapdoor.alpha(), trapdoor.beta(), trapdoor.gamma(), trapdoor.delta(), trapdoor.tau()] {
+            assert!(bool::from(x.is_zero()));
+        }
+    }
+
+    #[test]
+    fn wrong_trapdoor_fails() {
+        let fixture = cube_fixture(3);
+        let trapdoor = Trapdoor::<Bls12>::random(thread_rng());
+        let proof = simulate_proof(&trapdoor, &fixture.params.vk, fixture.public_inputs(), thread_rng());
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_err());
+    }
+}
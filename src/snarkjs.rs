@@ -0,0 +1,914 @@
+// Groth16 keys of snarkjs, in its binary .zkey format.
+//
+// A .zkey is an iden3 container, see `circom`, with magic "zkey" and
+// version 1:
+//
+//   section 1: protocol u32, 1 for Groth16
+//   section 2: n8q u32 | q | n8r u32 | r | variables u32 | public inputs u32
+//     | domain size u32 | alpha_g1 | beta_g1 | beta_g2 | gamma_g2 | delta_g1
+//     | delta_g2
+//   section 3: ic
+//   section 4: number of coefficients u32, then for each
+//     matrix u32, 0 for A and 1 for B | constraint u32 | variable u32 | value
+//   sections 5 to 9: a_g1, b_g1 and b_g2 with a point per variable, l, h
+//   section 10: the contributions to the ceremony, not needed here
+//
+// Coordinates take n8q bytes each, little-endian in Montgomery form for
+// R = 2^(8 n8q). A G2 coordinate is c0 | c1, and the identity is all
+// zeros. The coefficients are in standard form, multiplied by R^2 for
+// R = 2^(8 n8r). Their public input constraints are those of `circom`.
+//
+// Three things differ from `Parameters`:
+// - a_g1, b_g1 and b_g2 have a point for every variable, the identity where
+//   the polynomial is zero. The coefficients tell which points to keep.
+// - h holds L_{2i+1}(tau) / delta for the Lagrange polynomials of the
+//   domain of size 2n, which commits to h(X) through its values on the odd
+//   powers x_i of a 2n-th root of unity. Point j of the H query here is
+//   tau^j Z(tau) / delta = -2 sum_i x_i^j L_{2i+1}(tau) / delta, a DFT over
+//   G1. `decode_zkey` computes it with n / 2 * log n scalar
+//   multiplications, once per key.
+// - snarkjs generates its domains by nqr^t for the smallest quadratic
+//   non-residue nqr and the odd part t of r - 1, where `poly` takes
+//   `PrimeField::root_of_unity`. Both span the same points in a different
+//   order, so `Zkey::qap` moves each constraint of a QAP to the index of
+//   the point snarkjs assigns it.
+//
+// Proofs, verification keys and public inputs also come as the JSON files
+// snarkjs writes, proof.json, verification_key.json and public.json. Field
+// elements are decimal strings and points are projective: [x, y, "1"] in
+// G1 and [[x0, x1], [y0, y1], ["1", "0"]] in G2, with z zero for the
+// identity. Only z of one or zero is accepted on decoding. The curve is
+// named after its base field, "bn128" or "bls12381", and other curves are
+// a `Mismatch` both ways. vk_alphabeta_12, the pairing of alpha and beta, is not
+// written as target group elements have no coordinates to write, and is
+// ignored when decoding. The format has no beta_g1 and delta_g1, which are
+// only needed to prove and are left as the identity.
+
+use core::ops::Neg;
+
+use ff::{ Field, PrimeField, PrimeFieldBits };
+use pairing::Engine;
+use pairing::group::Curve;
+use pairing::group::prime::PrimeCurveAffine;
+
+use crate::{ DecodingError, Parameters, Proof, VerificationKey, QAP };
+use crate::circom::{ check_prime, scalar_le, section, sections };
+use crate::coordinates::{ BLS12_381_FQ, BN254_FQ, Coordinates };
+use crate::encoding::Reader;
+use crate::json::{ self, Value };
+use crate::poly::{ fft_params, group_fft };
+use crate::uint;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ format, string::{ String, ToString }, vec, vec::Vec };
+
+const GROTH16: u32 = 1;
+
+const CURVES: [(&str, &[u8]); 2] = [("bn128", BN254_FQ), ("bls12381", BLS12_381_FQ)];
+
+type Terms<S> = Vec<(usize, Vec<(S, usize)>)>;
+
+// (constraint, variable, coefficient), sorted by constraint and variable
+type Coefficients<S> = Vec<(usize, usize, S)>;
+
+pub struct Zkey<E: Engine> {
+    pub params: Parameters<E>,
+    // Size of the evaluation domain, the number of constraints to prove with
+    pub domain_size: usize,
+    // Generator of the domain as picked by snarkjs
+    omega: E::Fr,
+    // Terms of A and of B
+    coefficients: [Coefficients<E::Fr>; 2],
+}
+
+fn read_fq<E: Coordinates>(b: &[u8], q: &[u64]) -> Result<Vec<u8>, DecodingError> {
+    let x = uint::from_le_bytes(b);
+    if uint::cmp(&x, q).is_ge() {
+        return Err(DecodingError::InvalidPoint);
+    }
+    Ok(uint::to_be_bytes(&uint::from_montgomery(&x, q), E::FQ_LEN))
+}
+
+fn read_g1<E: Coordinates>(rd: &mut Reader, q: &[u64]) -> Result<E::G1Affine, DecodingError> {
+    let b = rd.bytes(2 * E::FQ_LEN)?;
+    if b.iter().all(|x| *x == 0) {
+        return Ok(E::G1Affine::identity());
+    }
+    let (x, y) = b.split_at(E::FQ_LEN);
+    E::g1_from_coordinates(&read_fq::<E>(x, q)?, &read_fq::<E>(y, q)?).ok_or(DecodingError::InvalidPoint)
+}
+
+fn read_g2<E: Coordinates>(rd: &mut Reader, q: &[u64]) -> Result<E::G2Affine, DecodingError> {
+    let b = rd.bytes(4 * E::FQ_LEN)?;
+    if b.iter().all(|x| *x == 0) {
+        return Ok(E::G2Affine::identity());
+    }
+    let c = b.chunks(E::FQ_LEN).map(|c| read_fq::<E>(c, q)).collect::<Result<Vec<_>, _>>()?;
+    E::g2_from_coordinates([&c[0], &c[1]], [&c[2], &c[3]]).ok_or(DecodingError::InvalidPoint)
+}
+
+// Section `id` as n items and nothing else
+fn read_section<'a, T>(
+    sections: &[(u32, &'a [u8])],
+    id: u32,
+    n: usize,
+    mut read: impl FnMut(&mut Reader<'a>) -> Result<T, DecodingError>
+) -> Result<Vec<T>, DecodingError>
+{
+    let mut rd = Reader::new(section(sections, id)?);
+    let items = (0..n).map(|_| read(&mut rd)).collect::<Result<Vec<T>, _>>()?;
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(items)
+}
+
+// The points of the sorted `variables`, all others have to be the identity
+pub(crate) fn select<G: PrimeCurveAffine>(points: Vec<G>, variables: &[usize]) -> Result<Vec<G>, DecodingError> {
+    let mut selected = Vec::with_capacity(variables.len());
+    for (i, p) in points.into_iter().enumerate() {
+        if variables.binary_search(&i).is_ok() {
+            selected.push(p);
+        } else if !bool::from(p.is_identity()) {
+            return Err(DecodingError::Mismatch);
+        }
+    }
+    Ok(selected)
+}
+
+// Generator of the domain of size 2^exp that snarkjs uses, for the scalar
+// field with modulus r
+fn snarkjs_root<S: PrimeField>(r: &[u8], exp: u32) -> S {
+    let mut t = uint::from_le_bytes(r);
+    uint::sub_assign(&mut t, &[1]);
+    uint::shr_assign(&mut t, S::S);
+
+    let mut nqr = 2;
+    while bool::from(S::from(nqr).sqrt().is_some()) {
+        nqr += 1;
+    }
+
+    let mut omega = S::from(nqr).pow_vartime(&t);
+    for _ in exp..S::S {
+        omega = omega.square();
+    }
+    omega
+}
+
+// k with base^k = x, for base of order 2^exp, one bit at a time
+fn dlog<S: PrimeField>(base: &S, x: &S, exp: u32) -> u64 {
+    let inv = base.invert().unwrap();
+    let mut k = 0;
+    for j in 0..exp {
+        let mut y = *x * inv.pow_vartime([k]);
+        for _ in j + 1..exp {
+            y = y.square();
+        }
+        if y != S::one() {
+            k |= 1 << j;
+        }
+    }
+    k
+}
+
+// tau^j Z(tau) / delta for j < n - 1 from the points of snarkjs, where zeta
+// is its 2n-th root of unity
+fn h_query<E: Engine>(points: &[E::G1Affine], zeta: &E::Fr, exp: u32) -> Vec<E::G1Affine> {
+    let mut h: Vec<E::G1> = points.iter().map(|p| p.to_curve()).collect();
+    group_fft(&mut h, &zeta.square(), exp);
+
+    let mut scale = E::Fr::from(2).neg();
+    for p in h.iter_mut() {
+        *p *= scale;
+        scale *= zeta;
+    }
+    h.pop();
+
+    let mut affine = vec![E::G1Affine::identity(); h.len()];
+    E::G1::batch_normalize(&h, &mut affine);
+    affine
+}
+
+pub fn decode_zkey<E: Coordinates>(bytes: &[u8]) -> Result<Zkey<E>, DecodingError> {
+    let sections = sections(bytes, b"zkey", 1)?;
+    if Reader::new(section(&sections, 1)?).u32_le()? != GROTH16 {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    let q = uint::from_be_bytes(E::FQ_MODULUS);
+    let mut rd = Reader::new(section(&sections, 2)?);
+    let n8q = rd.u32_le()? as usize;
+    if n8q != E::FQ_LEN || uint::cmp(&uint::from_le_bytes(rd.bytes(n8q)?), &q).is_ne() {
+        return Err(DecodingError::Mismatch);
+    }
+    let n8r = rd.u32_le()? as usize;
+    let r = rd.bytes(n8r)?;
+    check_prime::<E::Fr>(r)?;
+
+    let num_vars = rd.u32_le()? as usize;
+    let num_public = rd.u32_le()? as usize;
+    let domain_size = rd.u32_le()? as usize;
+    let exp = domain_size.trailing_zeros();
+    if num_public >= num_vars || !domain_size.is_power_of_two() || exp >= E::Fr::S {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    let g1 = |rd: &mut Reader| read_g1::<E>(rd, &q);
+    let g2 = |rd: &mut Reader| read_g2::<E>(rd, &q);
+    let vk = VerificationKey {
+        alpha_g1: g1(&mut rd)?,
+        beta_g1: g1(&mut rd)?,
+        beta_g2: g2(&mut rd)?,
+        gamma_g2: g2(&mut rd)?,
+        delta_g1: g1(&mut rd)?,
+        delta_g2: g2(&mut rd)?,
+        ic: read_section(&sections, 3, num_public + 1, g1)?,
+    };
+
+    let r2_inv = E::Fr::from(2).pow_vartime([8 * n8r as u64]).square().invert().unwrap();
+    let mut rd = Reader::new(section(&sections, 4)?);
+    let mut coefficients = [Vec::new(), Vec::new()];
+    for _ in 0..rd.u32_le()? {
+        let matrix = rd.u32_le()? as usize;
+        let constraint = rd.u32_le()? as usize;
+        let variable = rd.u32_le()? as usize;
+        let value = scalar_le::<E::Fr>(rd.bytes(n8r)?, r)? * r2_inv;
+        if constraint >= domain_size || variable >= num_vars {
+            return Err(DecodingError::Mismatch);
+        }
+        coefficients.get_mut(matrix)
+            .ok_or(DecodingError::InvalidHeader)?
+            .push((constraint, variable, value));
+    }
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    let mut variables = [Vec::new(), Vec::new()];
+    for (terms, variables) in coefficients.iter_mut().zip(variables.iter_mut()) {
+        terms.sort_by_key(|(c, i, _)| (*c, *i));
+        *variables = terms.iter().map(|(_, i, _)| *i).collect();
+        variables.sort_unstable();
+        variables.dedup();
+    }
+
+    let a_g1 = select(read_section(&sections, 5, num_vars, g1)?, &variables[0])?;
+    let b_g1 = select(read_section(&sections, 6, num_vars, g1)?, &variables[1])?;
+    let b_g2 = select(read_section(&sections, 7, num_vars, g2)?, &variables[1])?;
+    let l = read_section(&sections, 8, num_vars - num_public - 1, g1)?;
+
+    let zeta = snarkjs_root::<E::Fr>(r, exp + 1);
+    let h = h_query::<E>(&read_section(&sections, 9, domain_size, g1)?, &zeta, exp);
+
+    Ok(Zkey {
+        params: Parameters { vk, h, l, a_g1, b_g1, b_g2 },
+        domain_size,
+        omega: zeta.square(),
+        coefficients,
+    })
+}
+
+impl<E: Engine> Zkey<E> {
+    // `qap` with each constraint moved to the index of the point snarkjs
+    // assigns it, after checking that its A and B are those of the key.
+    // Proves against `params` with `domain_size` as the number of
+    // constraints.
+    pub fn qap(&self, qap: &QAP<E::Fr>) -> Result<QAP<E::Fr>, DecodingError> {
+        for (poly, coefficients) in [&qap.a, &qap.b].into_iter().zip(self.coefficients.iter()) {
+            let mut terms: Coefficients<E::Fr> = poly.iter()
+                .flat_map(|(i, terms)| terms.iter().map(move |(x, c)| (*c, *i, *x)))
+                .collect();
+            terms.sort_by_key(|(c, i, _)| (*c, *i));
+            if terms != *coefficients {
+                return Err(DecodingError::Mismatch);
+            }
+        }
+        if qap.num_constraints() > self.domain_size {
+            return Err(DecodingError::Mismatch);
+        }
+
+        let (omega, _, exp) = fft_params::<E::Fr>(self.domain_size).ok_or(DecodingError::Mismatch)?;
+        let k = dlog(&omega, &self.omega, exp);
+        let n = self.domain_size as u64;
+        let remap = |poly: &Terms<E::Fr>| -> Terms<E::Fr> {
+            poly.iter()
+                .map(|(i, terms)| (*i, terms.iter().map(|(x, c)| (*x, (*c as u64 * k % n) as usize)).collect()))
+                .collect()
+        };
+
+        Ok(QAP {
+            a: remap(&qap.a),
+            b: remap(&qap.b),
+            c: remap(&qap.c),
+            a_constraints: qap.a_constraints.clone(),
+            b_constraints: qap.b_constraints.clone(),
+        })
+    }
+}
+
+fn curve_name<E: Coordinates>() -> Result<&'static str, DecodingError> {
+    CURVES.iter()
+        .find(|(_, q)| *q == E::FQ_MODULUS)
+        .map(|(name, _)| *name)
+        .ok_or(DecodingError::Mismatch)
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+fn fq_json(b: &[u8]) -> Value {
+    Value::String(uint::to_decimal(&uint::from_be_bytes(b)))
+}
+
+fn g1_json<E: Coordinates>(p: &E::G1Affine) -> Value {
+    Value::Array(match E::g1_coordinates(p) {
+        Some([x, y]) => vec![fq_json(&x), fq_json(&y), string("1")],
+        None => vec![string("0"), string("1"), string("0")],
+    })
+}
+
+fn g2_json<E: Coordinates>(p: &E::G2Affine) -> Value {
+    let pair = |c: [Value; 2]| Value::Array(c.to_vec());
+    Value::Array(match E::g2_coordinates(p) {
+        Some([x, y]) => vec![pair(x.map(|c| fq_json(&c))), pair(y.map(|c| fq_json(&c))), pair([string("1"), string("0")])],
+        None => vec![pair([string("0"), string("0")]), pair([string("1"), string("0")]), pair([string("0"), string("0")])],
+    })
+}
+
+// Array of exactly N strings
+fn strings<const N: usize>(v: &Value) -> Result<[&str; N], DecodingError> {
+    let items = v.as_array().filter(|a| a.len() == N).ok_or(DecodingError::InvalidHeader)?;
+    let mut out = [""; N];
+    for (s, item) in out.iter_mut().zip(items) {
+        *s = item.as_str().ok_or(DecodingError::InvalidHeader)?;
+    }
+    Ok(out)
+}
+
+fn fq_from_json<E: Coordinates>(s: &str) -> Result<Vec<u8>, DecodingError> {
+    let q = uint::from_be_bytes(E::FQ_MODULUS);
+    let x = uint::from_decimal(s, q.len()).ok_or(DecodingError::InvalidPoint)?;
+    if uint::cmp(&x, &q).is_ge() {
+        return Err(DecodingError::InvalidPoint);
+    }
+    Ok(uint::to_be_bytes(&x, E::FQ_LEN))
+}
+
+fn g1_from_json<E: Coordinates>(v: &Value) -> Result<E::G1Affine, DecodingError> {
+    match strings::<3>(v)? {
+        [_, _, "0"] => Ok(E::G1Affine::identity()),
+        [x, y, "1"] => E::g1_from_coordinates(&fq_from_json::<E>(x)?, &fq_from_json::<E>(y)?)
+            .ok_or(DecodingError::InvalidPoint),
+        _ => Err(DecodingError::InvalidPoint),
+    }
+}
+
+fn g2_from_json<E: Coordinates>(v: &Value) -> Result<E::G2Affine, DecodingError> {
+    let items = v.as_array().filter(|a| a.len() == 3).ok_or(DecodingError::InvalidHeader)?;
+    let [x, y] = [strings::<2>(&items[0])?, strings::<2>(&items[1])?];
+    match strings::<2>(&items[2])? {
+        ["0", "0"] => Ok(E::G2Affine::identity()),
+        ["1", "0"] => {
+            let [x0, x1, y0, y1] = [x[0], x[1], y[0], y[1]].map(fq_from_json::<E>);
+            E::g2_from_coordinates([&x0?, &x1?], [&y0?, &y1?]).ok_or(DecodingError::InvalidPoint)
+        }
+        _ => Err(DecodingError::InvalidPoint),
+    }
+}
+
+fn member<'a>(v: &'a Value, key: &str) -> Result<&'a Value, DecodingError> {
+    v.get(key).ok_or(DecodingError::InvalidHeader)
+}
+
+// The document, after checking it is Groth16 on the curve of E
+fn parse_groth16<E: Coordinates>(s: &str) -> Result<Value, DecodingError> {
+    let v = json::parse(s).ok_or(DecodingError::InvalidHeader)?;
+    let protocol = member(&v, "protocol")?.as_str().ok_or(DecodingError::InvalidHeader)?;
+    let curve = member(&v, "curve")?.as_str().ok_or(DecodingError::InvalidHeader)?;
+    if protocol != "groth16" || curve != curve_name::<E>()? {
+        return Err(DecodingError::Mismatch);
+    }
+    Ok(v)
+}
+
+pub fn encode_proof_json<E: Coordinates>(proof: &Proof<E>) -> Result<String, DecodingError> {
+    Ok(json::write(&Value::Object(vec![
+        ("pi_a".into(), g1_json::<E>(&proof.a)),
+        ("pi_b".into(), g2_json::<E>(&proof.b)),
+        ("pi_c".into(), g1_json::<E>(&proof.c)),
+        ("protocol".into(), string("groth16")),
+        ("curve".into(), string(curve_name::<E>()?)),
+    ])))
+}
+
+pub fn decode_proof_json<E: Coordinates>(s: &str) -> Result<Proof<E>, DecodingError> {
+    let v = parse_groth16::<E>(s)?;
+    Ok(Proof {
+        a: g1_from_json::<E>(member(&v, "pi_a")?)?,
+        b: g2_from_json::<E>(member(&v, "pi_b")?)?,
+        c: g1_from_json::<E>(member(&v, "pi_c")?)?,
+    })
+}
+
+pub fn encode_vk_json<E: Coordinates>(vk: &VerificationKey<E>) -> Result<String, DecodingError> {
+    Ok(json::write(&Value::Object(vec![
+        ("protocol".into(), string("groth16")),
+        ("curve".into(), string(curve_name::<E>()?)),
+        ("nPublic".into(), Value::Number(format!("{}", vk.ic.len().saturating_sub(1)))),
+        ("vk_alpha_1".into(), g1_json::<E>(&vk.alpha_g1)),
+        ("vk_beta_2".into(), g2_json::<E>(&vk.beta_g2)),
+        ("vk_gamma_2".into(), g2_json::<E>(&vk.gamma_g2)),
+        ("vk_delta_2".into(), g2_json::<E>(&vk.delta_g2)),
+        ("IC".into(), Value::Array(vk.ic.iter().map(g1_json::<E>).collect())),
+    ])))
+}
+
+pub fn decode_vk_json<E: Coordinates>(s: &str) -> Result<VerificationKey<E>, DecodingError> {
+    let v = parse_groth16::<E>(s)?;
+    let num_public: usize = member(&v, "nPublic")?.as_number()
+        .and_then(|n| n.parse().ok())
+        .ok_or(DecodingError::InvalidHeader)?;
+    let ic = member(&v, "IC")?.as_array()
+        .ok_or(DecodingError::InvalidHeader)?
+        .iter()
+        .map(g1_from_json::<E>)
+        .collect::<Result<Vec<_>, _>>()?;
+    if ic.len() != num_public + 1 {
+        return Err(DecodingError::Mismatch);
+    }
+
+    Ok(VerificationKey {
+        alpha_g1: g1_from_json::<E>(member(&v, "vk_alpha_1")?)?,
+        beta_g1: E::G1Affine::identity(),
+        beta_g2: g2_from_json::<E>(member(&v, "vk_beta_2")?)?,
+        gamma_g2: g2_from_json::<E>(member(&v, "vk_gamma_2")?)?,
+        delta_g1: E::G1Affine::identity(),
+        delta_g2: g2_from_json::<E>(member(&v, "vk_delta_2")?)?,
+        ic,
+    })
+}
+
+// Integer from little-endian bits
+fn from_bits(bits: impl Iterator<Item = bool>) -> Vec<u64> {
+    let mut x = Vec::new();
+    for (i, bit) in bits.enumerate() {
+        if i % 64 == 0 {
+            x.push(0);
+        }
+        x[i / 64] |= u64::from(bit) << (i % 64);
+    }
+    x
+}
+
+// The public inputs without the leading one, as `verify_proof` takes them
+pub fn encode_public_json<S: PrimeFieldBits>(inputs: &[S]) -> String {
+    json::write(&Value::Array(
+        inputs.iter()
+            .map(|x| Value::String(uint::to_decimal(&from_bits(x.to_le_bits().iter().map(|b| *b)))))
+            .collect()
+    ))
+}
+
+pub fn decode_public_json<S: PrimeFieldBits>(s: &str) -> Result<Vec<S>, DecodingError> {
+    let r = from_bits(S::char_le_bits().iter().map(|b| *b));
+    let r_bytes = uint::to_le_bytes(&r, 8 * r.len());
+    json::parse(s)
+        .as_ref()
+        .and_then(Value::as_array)
+        .ok_or(DecodingError::InvalidHeader)?
+        .iter()
+        .map(|v| {
+            let x = v.as_str().ok_or(DecodingError::InvalidHeader)?;
+            let x = uint::from_decimal(x, r.len()).ok_or(DecodingError::InvalidFieldElement)?;
+            scalar_le::<S>(&uint::to_le_bytes(&x, r_bytes.len()), &r_bytes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, G1Affine, G1Projective, G2Affine, G2Projective, Scalar };
+    use rand::thread_rng;
+
+    use crate::Proof;
+    use crate::bn254::{ Bn254, Fr };
+    use crate::circom::{ decode_r1cs, decode_wtns };
+    use crate::poly::{ fft, ifft };
+    use crate::prover::create_proof;
+    use crate::tests::{ container, cube_fixture, run_evm, scalar_modulus, unhex };
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    fn fq(x: &[u8]) -> Vec<u8> {
+        let q = uint::from_be_bytes(Bls12::FQ_MODULUS);
+        uint::to_le_bytes(&uint::to_montgomery(&uint::from_be_bytes(x), &q), Bls12::FQ_LEN)
+    }
+
+    fn write_g1(out: &mut Vec<u8>, p: &G1Affine) {
+        match Bls12::g1_coordinates(p) {
+            Some([x, y]) => [x, y].iter().for_each(|c| out.extend(fq(c))),
+            None => out.extend(vec![0; 2 * Bls12::FQ_LEN]),
+        }
+    }
+
+    fn write_g2(out: &mut Vec<u8>, p: &G2Affine) {
+        match Bls12::g2_coordinates(p) {
+            Some([x, y]) => [x, y].iter().flatten().for_each(|c| out.extend(fq(c))),
+            None => out.extend(vec![0; 4 * Bls12::FQ_LEN]),
+        }
+    }
+
+    fn g1s(points: &[G1Affine]) -> Vec<u8> {
+        let mut out = Vec::new();
+        points.iter().for_each(|p| write_g1(&mut out, p));
+        out
+    }
+
+    // Key as snarkjs sets it up for a QAP, with the raw h points
+    struct Setup {
+        zkey: Vec<u8>,
+        vk: VerificationKey<Bls12>,
+        a_g1: Vec<G1Affine>,
+        b_g1: Vec<G1Affine>,
+        b_g2: Vec<G2Affine>,
+        l: Vec<G1Affine>,
+        h: Vec<G1Affine>,
+    }
+
+    fn setup(qap: &QAP<Scalar>, num_vars: usize, num_public: usize) -> Setup {
+        let mut rng = thread_rng();
+        let [tau, alpha, beta, delta] = [(); 4].map(|_| Scalar::random(&mut rng));
+        let n = qap.num_constraints().next_power_of_two();
+        let exp = n.trailing_zeros();
+        let r = scalar_modulus();
+        let omega = snarkjs_root::<Scalar>(&r, exp);
+        let zeta = snarkjs_root::<Scalar>(&r, exp + 1);
+
+        // L_k(tau) over the domain of size m generated by w
+        let lagrange = |w: Scalar, m: usize, k: usize| {
+            let wk = Field::pow_vartime(&w, [k as u64]);
+            wk * (Field::pow_vartime(&tau, [m as u64]) - Scalar::one()) * (Scalar::from(m as u64) * (tau - wk)).invert().unwrap()
+        };
+        let eval = |poly: &Terms<Scalar>| {
+            let mut v = vec![Scalar::zero(); num_vars];
+            for (i, terms) in poly {
+                for (x, c) in terms {
+                    v[*i] += *x * lagrange(omega, n, *c);
+                }
+            }
+            v
+        };
+        let (a, b, c) = (eval(&qap.a), eval(&qap.b), eval(&qap.c));
+
+        let g1 = |x: Scalar| G1Affine::from(G1Projective::generator() * x);
+        let g2 = |x: Scalar| G2Affine::from(G2Projective::generator() * x);
+        let lc = |i: usize| beta * a[i] + alpha * b[i] + c[i];
+        let delta_inv = delta.invert().unwrap();
+
+        let vk = VerificationKey {
+            alpha_g1: g1(alpha),
+            beta_g1: g1(beta),
+            beta_g2: g2(beta),
+            gamma_g2: g2(Scalar::one()),
+            delta_g1: g1(delta),
+            delta_g2: g2(delta),
+            ic: (0..=num_public).map(|i| g1(lc(i))).collect(),
+        };
+        let setup = Setup {
+            a_g1: a.iter().map(|x| g1(*x)).collect(),
+            b_g1: b.iter().map(|x| g1(*x)).collect(),
+            b_g2: b.iter().map(|x| g2(*x)).collect(),
+            l: (num_public + 1..num_vars).map(|i| g1(lc(i) * delta_inv)).collect(),
+            h: (0..n).map(|i| g1(lagrange(zeta, 2 * n, 2 * i + 1) * delta_inv)).collect(),
+            vk,
+            zkey: Vec::new(),
+        };
+
+        let mut header = 48u32.to_le_bytes().to_vec();
+        header.extend(uint::to_le_bytes(&uint::from_be_bytes(Bls12::FQ_MODULUS), 48));
+        header.extend(32u32.to_le_bytes());
+        header.extend(&r);
+        for x in [num_vars, num_public, n] {
+            header.extend((x as u32).to_le_bytes());
+        }
+        let vk = &setup.vk;
+        write_g1(&mut header, &vk.alpha_g1);
+        write_g1(&mut header, &vk.beta_g1);
+        write_g2(&mut header, &vk.beta_g2);
+        write_g2(&mut header, &vk.gamma_g2);
+        write_g1(&mut header, &vk.delta_g1);
+        write_g2(&mut header, &vk.delta_g2);
+
+        let r2 = Field::pow_vartime(&Scalar::from(2), [256]).square();
+        let mut coefficients = Vec::new();
+        for (matrix, poly) in [&qap.a, &qap.b].into_iter().enumerate() {
+            for (i, terms) in poly {
+                for (x, c) in terms {
+                    for y in [matrix, *c, *i] {
+                        coefficients.extend((y as u32).to_le_bytes());
+                    }
+                    coefficients.extend((*x * r2).to_repr());
+                }
+            }
+        }
+        let count = (coefficients.len() / 44) as u32;
+        let mut section4 = count.to_le_bytes().to_vec();
+        section4.extend(coefficients);
+
+        let mut b_g2 = Vec::new();
+        setup.b_g2.iter().for_each(|p| write_g2(&mut b_g2, p));
+
+        Setup {
+            zkey: container(b"zkey", 1, &[
+                (1, GROTH16.to_le_bytes().to_vec()),
+                (2, header),
+                (3, g1s(&vk.ic)),
+                (4, section4),
+                (5, g1s(&setup.a_g1)),
+                (6, g1s(&setup.b_g1)),
+                (7, b_g2),
+                (8, g1s(&setup.l)),
+                (9, g1s(&setup.h)),
+            ]),
+            ..setup
+        }
+    }
+
+    // The proof of the snarkjs prover, which evaluates A, B and C on the
+    // odd powers of zeta in its domain ordering and uses the raw h points
+    fn snarkjs_proof(setup: &Setup, qap: &QAP<Scalar>, w: &[Scalar], num_public: usize, r: Scalar, s: Scalar) -> Proof<Bls12> {
+        let n = setup.h.len();
+        let exp = n.trailing_zeros();
+        let p = scalar_modulus();
+        let omega = snarkjs_root::<Scalar>(&p, exp);
+        let zeta = snarkjs_root::<Scalar>(&p, exp + 1);
+
+        let odd = |poly: &Terms<Scalar>| {
+            let mut v = vec![Scalar::zero(); n];
+            for (i, terms) in poly {
+                for (x, c) in terms {
+                    v[*c] += *x * w[*i];
+                }
+            }
+            ifft(&mut v, &omega, exp);
+            let mut z = Scalar::one();
+            for x in v.iter_mut() {
+                *x *= z;
+                z *= zeta;
+            }
+            fft(&mut v, &omega, exp);
+            v
+        };
+        let (a, b, c) = (odd(&qap.a), odd(&qap.b), odd(&qap.c));
+
+        let msm_g1 = |points: &[G1Affine], scalars: &[Scalar]| -> G1Projective {
+            points.iter().zip(scalars).map(|(p, x)| *p * x).sum()
+        };
+        let vk = &setup.vk;
+        let pa = vk.alpha_g1 + msm_g1(&setup.a_g1, w) + vk.delta_g1 * r;
+        let pb1 = vk.beta_g1 + msm_g1(&setup.b_g1, w) + vk.delta_g1 * s;
+        let pb2 = vk.beta_g2 + setup.b_g2.iter().zip(w).map(|(p, x)| *p * x).sum::<G2Projective>() + vk.delta_g2 * s;
+        let h: Vec<_> = (0..n).map(|i| a[i] * b[i] - c[i]).collect();
+        let pc = msm_g1(&setup.l, &w[num_public + 1..]) + msm_g1(&setup.h, &h) + pa * s + pb1 * r - vk.delta_g1 * (r * s);
+
+        Proof { a: pa.into(), b: pb2.into(), c: pc.into() }
+    }
+
+    #[test]
+    fn zkey_proofs_match_snarkjs() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let num_public = fixture.public_inputs().len();
+        let w: Vec<_> = fixture.inputs.iter().chain(fixture.aux.iter()).copied().collect();
+        let setup = setup(&fixture.qap, w.len(), num_public);
+
+        let zkey = decode_zkey::<Bls12>(&setup.zkey).unwrap();
+        assert_eq!(zkey.domain_size, 8);
+        assert_eq!(zkey.params.vk.ic, setup.vk.ic);
+        assert_eq!(zkey.params.vk.delta_g2, setup.vk.delta_g2);
+        assert_eq!(zkey.params.a_g1.len(), fixture.qap.a_constraints.len());
+        assert_eq!(zkey.params.h.len(), 7);
+
+        let qap = zkey.qap(&fixture.qap).unwrap();
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let proof = create_proof::<Bls12, _>(&zkey.params, &fixture.inputs, &fixture.aux, r, s, &qap, zkey.domain_size).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), zkey.params.vk.clone()).is_ok());
+
+        let expected = snarkjs_proof(&setup, &fixture.qap, &w, num_public, r, s);
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+        assert!(verify_proof(&expected, fixture.public_inputs(), zkey.params.vk.clone()).is_ok());
+
+        // Without moving the constraints the quotient is wrong
+        let proof = create_proof::<Bls12, _>(&zkey.params, &fixture.inputs, &fixture.aux, r, s, &fixture.qap, zkey.domain_size).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), zkey.params.vk.clone()).is_err());
+    }
+
+    #[test]
+    fn zkey_rejects_other_circuits() {
+        let fixture = cube_fixture(3);
+        let num_vars = fixture.inputs.len() + fixture.aux.len();
+        let setup = setup(&fixture.qap, num_vars, 1);
+        let zkey = decode_zkey::<Bls12>(&setup.zkey).unwrap();
+
+        let mut qap = fixture.qap.clone();
+        qap.b[0].1[0].0 += Scalar::one();
+        assert_eq!(zkey.qap(&qap).err(), Some(DecodingError::Mismatch));
+
+        let mut bytes = setup.zkey.clone();
+        // Protocol of section 1, after the container and section headers
+        bytes[24] = 2;
+        assert_eq!(decode_zkey::<Bls12>(&bytes).err(), Some(DecodingError::InvalidHeader));
+
+        // A coordinate of alpha_g1 changed
+        let mut bytes = setup.zkey.clone();
+        let alpha = 28 + 12 + 4 + 48 + 4 + 32 + 12;
+        bytes[alpha] ^= 1;
+        assert_eq!(decode_zkey::<Bls12>(&bytes).err(), Some(DecodingError::InvalidPoint));
+    }
+
+    // proof.json as snarkjs lays it out, for the generators of G1 and G2
+    // and the identity. snarkjs does not run here, so the file is written
+    // by hand after its output.
+    const PROOF_JSON: &str = r#"{
+ "pi_a": [
+  "3685416753713387016781088315183077757961620795782546409894578378688607592378376318836054947676345821548104185464507",
+  "1339506544944476473020471379941921221584933875938349620426543736416511423956333506472724655353366534992391756441569",
+  "1"
+ ],
+ "pi_b": [
+  [
+   "352701069587466618187139116011060144890029952792775240219908644239793785735715026873347600343865175952761926303160",
+   "3059144344244213709971259814753781636986470325476647558659373206291635324768958432433509563104347017837885763365758"
+  ],
+  [
+   "1985150602287291935568054521177171638300868978215655730859378665066344726373823718423869104263333984641494340347905",
+   "927553665492332455747201965776037880757740193453592970025027978793976877002675564980949289727957565575433344219582"
+  ],
+  [
+   "1",
+   "0"
+  ]
+ ],
+ "pi_c": [
+  "0",
+  "1",
+  "0"
+ ],
+ "protocol": "groth16",
+ "curve": "bls12381"
+}"#;
+
+    #[test]
+    fn json_matches_snarkjs_layout() {
+        let proof = decode_proof_json::<Bls12>(PROOF_JSON).unwrap();
+        assert_eq!(proof.a, G1Affine::generator());
+        assert_eq!(proof.b, G2Affine::generator());
+        assert_eq!(proof.c, G1Affine::identity());
+        assert_eq!(encode_proof_json(&proof).unwrap(), PROOF_JSON);
+
+        let public = decode_public_json::<Scalar>("[\n \"27\",\n \"0\"\n]").unwrap();
+        assert_eq!(public, [Scalar::from(27), Scalar::zero()]);
+        assert_eq!(encode_public_json(&public), "[\n \"27\",\n \"0\"\n]");
+        assert_eq!(encode_public_json(&[-Scalar::one()]), "[\n \"52435875175126190479447740508185965837690552500527637822603658699938581184512\"\n]");
+    }
+
+    #[test]
+    fn json_roundtrip_verifies() {
+        let fixture = cube_fixture(3);
+        let proof = decode_proof_json::<Bls12>(&encode_proof_json(&fixture.prove()).unwrap()).unwrap();
+        let vk_json = encode_vk_json(&fixture.params.vk).unwrap();
+        assert!(vk_json.contains("\"nPublic\": 1,"));
+        let vk = decode_vk_json::<Bls12>(&vk_json).unwrap();
+        assert_eq!(vk.ic, fixture.params.vk.ic);
+        assert_eq!(vk.beta_g1, G1Affine::identity());
+        let public = decode_public_json::<Scalar>(&encode_public_json(fixture.public_inputs())).unwrap();
+        assert_eq!(public, fixture.public_inputs());
+        assert!(verify_proof(&proof, &public, vk.clone()).is_ok());
+        assert!(verify_proof(&proof, &[Scalar::from(28)], vk).is_err());
+    }
+
+    #[test]
+    fn json_rejects_bad_files() {
+        let bad = |from: &str, to: &str| decode_proof_json::<Bls12>(&PROOF_JSON.replacen(from, to, 1)).err();
+        assert_eq!(bad("bls12381", "bn128"), Some(DecodingError::Mismatch));
+        assert_eq!(bad("groth16", "plonk"), Some(DecodingError::Mismatch));
+        assert_eq!(bad("\"pi_c\"", "\"pi_d\""), Some(DecodingError::InvalidHeader));
+        assert_eq!(bad("}", ""), Some(DecodingError::InvalidHeader));
+        // x of pi_a plus one, off the curve
+        assert_eq!(bad("464507", "464508"), Some(DecodingError::InvalidPoint));
+        assert_eq!(bad("\"3685", "\"93685"), Some(DecodingError::InvalidPoint));
+        assert_eq!(bad("\"1\"\n ]", "\"2\"\n ]"), Some(DecodingError::InvalidPoint));
+
+        let vk_json = encode_vk_json(&cube_fixture(3).params.vk).unwrap();
+        let vk = decode_vk_json::<Bls12>(&vk_json.replace("\"nPublic\": 1", "\"nPublic\": 2"));
+        assert_eq!(vk.err(), Some(DecodingError::Mismatch));
+
+        let r = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+        assert_eq!(decode_public_json::<Scalar>(&format!("[\"{}\"]", r)).err(), Some(DecodingError::InvalidFieldElement));
+        assert_eq!(decode_public_json::<Scalar>("[27]").err(), Some(DecodingError::InvalidHeader));
+    }
+
+    // Files written by snarkjs for the circuit c <== a * b of circom, with
+    // a and b private and c public. The proving key, its verification key
+    // and the .r1cs are from one setup, and verification_key.json,
+    // proof.json and public.json from another, with a = 3 and b = 11.
+    //
+    // multiplier.wtns holds the witness for a = 3 and b = 11 as computed by
+    // the circuit's .wasm from the same circom build, run by circom's
+    // witness calculator. snarkjs was not available to write the file, so
+    // its two sections were laid out the way `snarkjs wtns calculate` does.
+    const ZKEY: &[u8] = include_bytes!("testdata/snarkjs/multiplier.zkey");
+    const R1CS: &[u8] = include_bytes!("testdata/snarkjs/multiplier.r1cs");
+    const WTNS: &[u8] = include_bytes!("testdata/snarkjs/multiplier.wtns");
+    const ZKEY_VK_JSON: &str = include_str!("testdata/snarkjs/multiplier_vk.json");
+    const VK_JSON: &str = include_str!("testdata/snarkjs/verification_key.json");
+    const SNARKJS_PROOF_JSON: &str = include_str!("testdata/snarkjs/proof.json");
+    const PUBLIC_JSON: &str = include_str!("testdata/snarkjs/public.json");
+
+    #[test]
+    fn snarkjs_proof_verifies() {
+        let vk = decode_vk_json::<Bn254>(VK_JSON).unwrap();
+        let proof = decode_proof_json::<Bn254>(SNARKJS_PROOF_JSON).unwrap();
+        let public = decode_public_json::<Fr>(PUBLIC_JSON).unwrap();
+        assert_eq!(public, [Fr::from(33)]);
+        assert!(verify_proof(&proof, &public, vk.clone()).is_ok());
+        assert!(verify_proof(&proof, &[Fr::from(34)], vk).is_err());
+
+        let other = decode_vk_json::<Bn254>(ZKEY_VK_JSON).unwrap();
+        assert!(verify_proof(&proof, &public, other).is_err());
+    }
+
+    // Calls verify(input, proof, vk) of the TestVerifier contract of
+    // ark-circom 0.1.0, snarkjs's Solidity verifier template with the key
+    // passed in, as solc compiled it there. Its arguments are ABI encoded:
+    // the offset of input, the static proof, the offset of vk, then input
+    // and vk, whose ic is preceded by its offset within vk.
+    fn solidity_verifier_accepts(vk: &VerificationKey<Bn254>, proof: &Proof<Bn254>, inputs: &[Fr]) -> bool {
+        const CODE: &str = include_str!("testdata/snarkjs/test_verifier.hex");
+        let word = |x: usize| {
+            let mut w = [0u8; 32];
+            w[24..].copy_from_slice(&(x as u64).to_be_bytes());
+            w.to_vec()
+        };
+        let scalar = |x: &Fr| x.to_repr().iter().rev().copied().collect::<Vec<u8>>();
+
+        let mut data = unhex("9416c1ee");
+        data.extend(word(10 * 32));
+        data.extend(proof.a.to_uncompressed().as_ref());
+        data.extend(proof.b.to_uncompressed().as_ref());
+        data.extend(proof.c.to_uncompressed().as_ref());
+        data.extend(word((11 + inputs.len()) * 32));
+        data.extend(word(inputs.len()));
+        data.extend(inputs.iter().flat_map(scalar));
+        data.extend(vk.alpha_g1.to_uncompressed().as_ref());
+        for p in [&vk.beta_g2, &vk.gamma_g2, &vk.delta_g2] {
+            data.extend(p.to_uncompressed().as_ref());
+        }
+        data.extend(word(15 * 32));
+        data.extend(word(vk.ic.len()));
+        for p in vk.ic.iter() {
+            data.extend(p.to_uncompressed().as_ref());
+        }
+
+        let out = run_evm(&unhex(CODE.trim()), &data).unwrap();
+        assert_eq!(out.len(), 32);
+        out[31] == 1
+    }
+
+    #[test]
+    fn snarkjs_zkey_proves() {
+        let zkey = decode_zkey::<Bn254>(ZKEY).unwrap();
+        assert_eq!(zkey.domain_size, 4);
+        let vk = decode_vk_json::<Bn254>(ZKEY_VK_JSON).unwrap();
+        assert_eq!(zkey.params.vk.alpha_g1, vk.alpha_g1);
+        assert_eq!(zkey.params.vk.beta_g2, vk.beta_g2);
+        assert_eq!(zkey.params.vk.gamma_g2, vk.gamma_g2);
+        assert_eq!(zkey.params.vk.delta_g2, vk.delta_g2);
+        assert_eq!(zkey.params.vk.ic, vk.ic);
+
+        let r1cs = decode_r1cs::<Fr>(R1CS).unwrap();
+        let qap = zkey.qap(&r1cs.qap).unwrap();
+        let (inputs, aux) = decode_wtns(WTNS, &r1cs).unwrap();
+        assert_eq!(inputs, [Fr::one(), Fr::from(33)]);
+        assert_eq!(aux, [Fr::from(3), Fr::from(11)]);
+        let mut rng = thread_rng();
+        let (r, s) = (Fr::random(&mut rng), Fr::random(&mut rng));
+        let proof = create_proof::<Bn254, _>(&zkey.params, &inputs, &aux, r, s, &qap, zkey.domain_size).unwrap();
+        assert!(verify_proof(&proof, &inputs[1..], vk.clone()).is_ok());
+        assert!(verify_proof(&proof, &[Fr::from(34)], vk.clone()).is_err());
+
+        let json = encode_proof_json(&proof).unwrap();
+        assert_eq!(decode_proof_json::<Bn254>(&json).unwrap().b, proof.b);
+
+        // The same proof through the verifier snarkjs exports for Ethereum
+        assert!(solidity_verifier_accepts(&vk, &proof, &inputs[1..]));
+        assert!(!solidity_verifier_accepts(&vk, &proof, &[Fr::from(34)]));
+        let snarkjs_proof = decode_proof_json::<Bn254>(SNARKJS_PROOF_JSON).unwrap();
+        let snarkjs_vk = decode_vk_json::<Bn254>(VK_JSON).unwrap();
+        assert!(solidity_verifier_accepts(&snarkjs_vk, &snarkjs_proof, &inputs[1..]));
+    }
+}
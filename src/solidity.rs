@@ -0,0 +1,393 @@
+// On-chain verification of BN254 proofs, through the precompiles of
+// EIP-196 (ecAdd at 0x06, ecMul at 0x07) and EIP-197 (ecPairing at 0x08).
+//
+// `verifier_contract` writes a self-contained Solidity contract with the
+// verification key inlined as constants. Its entry point is
+//
+//   verifyProof(uint256[2] a, uint256[2][2] b, uint256[2] c, uint256[n] input)
+//
+// for n public inputs, without the input array when n is zero, as
+// Solidity has no arrays of length zero. The contract checks
+// e(-A, B) e(alpha, beta) e(x, gamma) e(C, delta) == 1 for
+// x = ic_0 + sum input_i ic_{i+1}, and rejects inputs not below r. A key
+// without ic_0 has no contract and is rejected with `Mismatch`.
+//
+// Points are laid out as the precompiles read them: 32-byte big-endian
+// words, G1 as x | y and G2 as x.c1 | x.c0 | y.c1 | y.c0, with all zeros
+// for the identity. That is the uncompressed encoding of the bn254
+// module, so the words are cut from it. `calldata` encodes a proof and its
+// public inputs as the ABI call of `verifyProof`: the function selector,
+// then a, b, c and the inputs, one word each, as all arrays are static.
+
+use core::fmt::Write;
+
+use ff::PrimeField;
+
+use crate::{ DecodingError, Proof, VerificationKey };
+use crate::bn254::{ Bn254, Fr, G1Affine, G2Affine };
+use crate::coordinates::BN254_FQ;
+use crate::keccak::keccak256;
+use crate::uint;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ format, string::String, vec::Vec };
+
+const WORD: usize = 32;
+
+fn signature(num_inputs: usize) -> String {
+    let mut s = String::from("verifyProof(uint256[2],uint256[2][2],uint256[2]");
+    if num_inputs > 0 {
+        s.push_str(&format!(",uint256[{}]", num_inputs));
+    }
+    s.push(')');
+    s
+}
+
+fn selector(num_inputs: usize) -> [u8; 4] {
+    let hash = keccak256(signature(num_inputs).as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn g1_words(p: &G1Affine) -> Vec<u8> {
+    p.to_uncompressed().as_ref().to_vec()
+}
+
+fn g2_words(p: &G2Affine) -> Vec<u8> {
+    p.to_uncompressed().as_ref().to_vec()
+}
+
+fn scalar_word(s: &Fr) -> Vec<u8> {
+    s.to_repr().iter().rev().copied().collect()
+}
+
+fn decimal(word: &[u8]) -> String {
+    uint::to_decimal(&uint::from_be_bytes(word))
+}
+
+// The constants NAME_X and NAME_Y of a G1 point
+fn g1_constants(out: &mut String, name: &str, p: &G1Affine) {
+    let words = g1_words(p);
+    for (suffix, word) in ["X", "Y"].iter().zip(words.chunks(WORD)) {
+        writeln!(out, "    uint256 constant {}_{} = {};", name, suffix, decimal(word)).unwrap();
+    }
+}
+
+// The constants NAME_X1, NAME_X0, NAME_Y1 and NAME_Y0 of a G2 point
+fn g2_constants(out: &mut String, name: &str, p: &G2Affine) {
+    let words = g2_words(p);
+    for (suffix, word) in ["X1", "X0", "Y1", "Y0"].iter().zip(words.chunks(WORD)) {
+        writeln!(out, "    uint256 constant {}_{} = {};", name, suffix, decimal(word)).unwrap();
+    }
+}
+
+const HELPERS: &str = "
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory r) {
+        uint256[4] memory i = [p1[0], p1[1], p2[0], p2[1]];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, i, 0x80, r, 0x40)
+        }
+        require(ok, \"ecAdd failed\");
+    }
+
+    function ecMul(uint256[2] memory p, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory i = [p[0], p[1], s];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, i, 0x60, r, 0x40)
+        }
+        require(ok, \"ecMul failed\");
+    }
+
+    function ecPairing(uint256[24] memory i) internal view returns (bool) {
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, i, 0x300, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+}
+";
+
+pub fn verifier_contract(vk: &VerificationKey<Bn254>) -> Result<String, DecodingError> {
+    if vk.ic.is_empty() {
+        return Err(DecodingError::Mismatch);
+    }
+    let n = vk.ic.len() - 1;
+    let mut out = String::new();
+    out.push_str("// SPDX-License-Identifier: MIT\n");
+    out.push_str("// Groth16 verifier for BN254, generated from a verification key\n");
+    out.push_str("pragma solidity ^0.8.0;\n\n");
+    out.push_str("contract Groth16Verifier {\n");
+    writeln!(out, "    // Scalar field modulus\n    uint256 constant R = {};", uint::to_decimal(&Fr::MODULUS)).unwrap();
+    writeln!(out, "    // Base field modulus\n    uint256 constant Q = {};\n", decimal(BN254_FQ)).unwrap();
+    out.push_str("    // G2 coordinates are c1 before c0, as the pairing precompile reads them\n");
+    g1_constants(&mut out, "ALPHA", &vk.alpha_g1);
+    g2_constants(&mut out, "BETA", &vk.beta_g2);
+    g2_constants(&mut out, "GAMMA", &vk.gamma_g2);
+    g2_constants(&mut out, "DELTA", &vk.delta_g2);
+    for (i, p) in vk.ic.iter().enumerate() {
+        g1_constants(&mut out, &format!("IC{}", i), p);
+    }
+
+    out.push_str("\n    function verifyProof(\n");
+    out.push_str("        uint256[2] calldata a,\n");
+    out.push_str("        uint256[2][2] calldata b,\n");
+    if n > 0 {
+        out.push_str("        uint256[2] calldata c,\n");
+        writeln!(out, "        uint256[{}] calldata input", n).unwrap();
+    } else {
+        out.push_str("        uint256[2] calldata c\n");
+    }
+    out.push_str("    ) public view returns (bool) {\n");
+    out.push_str("        uint256[2] memory x = [IC0_X, IC0_Y];\n");
+    for i in 0..n {
+        writeln!(out, "        require(input[{}] < R, \"input not in field\");", i).unwrap();
+        writeln!(out, "        x = ecAdd(x, ecMul([IC{}_X, IC{}_Y], input[{}]));", i + 1, i + 1, i).unwrap();
+    }
+
+    out.push_str("\n        // e(-A, B) e(alpha, beta) e(x, gamma) e(C, delta) == 1\n");
+    out.push_str("        require(a[1] < Q, \"proof not in field\");\n");
+    out.push_str("        uint256[24] memory p;\n");
+    let words = [
+        "a[0]", "(Q - a[1]) % Q", "b[0][0]", "b[0][1]", "b[1][0]", "b[1][1]",
+        "ALPHA_X", "ALPHA_Y", "BETA_X1", "BETA_X0", "BETA_Y1", "BETA_Y0",
+        "x[0]", "x[1]", "GAMMA_X1", "GAMMA_X0", "GAMMA_Y1", "GAMMA_Y0",
+        "c[0]", "c[1]", "DELTA_X1", "DELTA_X0", "DELTA_Y1", "DELTA_Y0",
+    ];
+    for (i, word) in words.iter().enumerate() {
+        writeln!(out, "        p[{}] = {};", i, word).unwrap();
+    }
+    out.push_str("        return ecPairing(p);\n");
+    out.push_str("    }\n");
+    out.push_str(HELPERS);
+    Ok(out)
+}
+
+pub fn calldata(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    let mut out = selector(public_inputs.len()).to_vec();
+    out.extend(g1_words(&proof.a));
+    out.extend(g2_words(&proof.b));
+    out.extend(g1_words(&proof.c));
+    for s in public_inputs {
+        out.extend(scalar_word(s));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pairing::group::Curve;
+    use rand::thread_rng;
+    use revm::precompile::bn128;
+
+    use crate::bn254::{ G1Projective, G2Projective };
+    use crate::coordinates::Coordinates;
+    use crate::simulator::{ simulate_proof, Trapdoor };
+    use crate::tests::{ cube_fixture_on, run_evm, unhex };
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    // Keys from fixed multiples of the generators, so that the source is
+    // the same on every run
+    fn fixed_vk() -> VerificationKey<Bn254> {
+        let g1 = |k: u64| (G1Projective::generator() * Fr::from(k)).to_affine();
+        let g2 = |k: u64| (G2Projective::generator() * Fr::from(k)).to_affine();
+        VerificationKey {
+            alpha_g1: g1(2),
+            beta_g1: g1(3),
+            beta_g2: g2(3),
+            gamma_g2: g2(5),
+            delta_g1: g1(7),
+            delta_g2: g2(7),
+            ic: vec![g1(11), g1(13)],
+        }
+    }
+
+    #[test]
+    fn contract_matches_golden_file() {
+        assert_eq!(verifier_contract(&fixed_vk()).unwrap(), include_str!("testdata/verifier.sol"));
+    }
+
+    // Compiles the golden contract with solc, from SOLC or the PATH, and
+    // runs its bytecode in revm on the calldata of proofs simulated for
+    // `fixed_vk`, whose trapdoor is the multiples of the generators. CI
+    // installs solc and runs the ignored tests.
+    #[test]
+    #[ignore = "needs solc"]
+    fn golden_contract_runs_on_the_evm() {
+        let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".into());
+        let out = std::process::Command::new(solc)
+            .args(["--optimize", "--combined-json", "bin"])
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/src/testdata/verifier.sol"))
+            .output()
+            .expect("solc not found");
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let json: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+        let (_, contract) = json["contracts"].as_object().unwrap()
+            .iter()
+            .find(|(name, _)| name.ends_with(":Groth16Verifier"))
+            .unwrap();
+        let code = unhex(contract["bin"].as_str().unwrap());
+
+        let vk = fixed_vk();
+        let trapdoor = Trapdoor::<Bn254>::new(Fr::from(2), Fr::from(3), Fr::from(5), Fr::from(7), Fr::from(1));
+        let inputs = [Fr::from(35)];
+        let proof = simulate_proof(&trapdoor, &vk, &inputs, thread_rng());
+        assert!(verify_proof(&proof, &inputs, vk.clone()).is_ok());
+
+        let result = |data: &[u8]| run_evm(&code, data).map(|out| out == word(&[u64::from(true)]));
+        assert_eq!(result(&calldata(&proof, &inputs)), Some(true));
+        assert_eq!(result(&calldata(&proof, &[Fr::from(36)])), Some(false));
+        let mut forged = proof.clone();
+        forged.c = proof.a;
+        assert_eq!(result(&calldata(&forged, &inputs)), Some(false));
+
+        // An input of r, not reduced, reverts
+        let mut data = calldata(&proof, &inputs);
+        data[4 + 8 * WORD..].copy_from_slice(&word(&Fr::MODULUS));
+        assert_eq!(result(&data), None);
+    }
+
+    #[test]
+    fn contract_rejects_key_without_ic() {
+        let vk = VerificationKey { ic: vec![], ..fixed_vk() };
+        assert_eq!(verifier_contract(&vk), Err(DecodingError::Mismatch));
+    }
+
+    type Word = [u8; WORD];
+
+    fn word(x: &[u64]) -> Word {
+        uint::to_be_bytes(x, WORD).try_into().unwrap()
+    }
+
+    fn call(output: &[u8]) -> [Word; 2] {
+        [output[..WORD].try_into().unwrap(), output[WORD..].try_into().unwrap()]
+    }
+
+    // Runs `verifyProof` of a generated contract on its calldata, with the
+    // precompiles of revm behind ecAdd, ecMul and ecPairing. The body is
+    // executed one statement at a time; only the statements the generator
+    // writes are understood. A failed `require` returns false.
+    fn run_contract(contract: &str, data: &[u8]) -> bool {
+        let mut vars = HashMap::<String, Word>::new();
+        for line in contract.lines().filter_map(|l| l.trim().strip_prefix("uint256 constant ")) {
+            let (name, value) = line.trim_end_matches(';').split_once(" = ").unwrap();
+            vars.insert(name.into(), word(&uint::from_decimal(value, 4).unwrap()));
+        }
+
+        let (_, rest) = contract.split_once("function verifyProof(").unwrap();
+        let (params, body) = rest.split_once(") public view returns (bool) {").unwrap();
+        let types = params.split(',').map(|p| p.split_whitespace().next().unwrap()).collect::<Vec<_>>();
+        let signature = format!("verifyProof({})", types.join(","));
+        assert_eq!(&data[..4], &keccak256(signature.as_bytes())[..4]);
+
+        let names = ["a[0]", "a[1]", "b[0][0]", "b[0][1]", "b[1][0]", "b[1][1]", "c[0]", "c[1]"];
+        for (i, w) in data[4..].chunks(WORD).enumerate() {
+            let name = names.get(i).map(|n| n.to_string()).unwrap_or_else(|| format!("input[{}]", i - names.len()));
+            vars.insert(name, w.try_into().unwrap());
+        }
+
+        let mut p = vec![[0u8; WORD]; 24];
+        for statement in body.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with("//")) {
+            let value = |vars: &HashMap<String, Word>, e: &str| match e.strip_prefix("(Q - ").and_then(|e| e.strip_suffix(") % Q")) {
+                Some(v) => {
+                    let v = uint::from_be_bytes(&vars[v]);
+                    let mut q = uint::from_be_bytes(&vars["Q"]);
+                    if v.iter().any(|l| *l != 0) {
+                        uint::sub_assign(&mut q, &v);
+                        word(&q)
+                    } else {
+                        [0; WORD]
+                    }
+                }
+                None => vars[e],
+            };
+
+            if let Some(s) = statement.strip_prefix("uint256[2] memory x = [") {
+                let (x, y) = s.trim_end_matches("];").split_once(", ").unwrap();
+                let (x, y) = (vars[x], vars[y]);
+                vars.insert("x[0]".into(), x);
+                vars.insert("x[1]".into(), y);
+            } else if let Some(s) = statement.strip_prefix("require(") {
+                let (l, r) = s.split_once(',').unwrap().0.split_once(" < ").unwrap();
+                if value(&vars, l) >= value(&vars, r) {
+                    return false;
+                }
+            } else if let Some(s) = statement.strip_prefix("x = ecAdd(x, ecMul([") {
+                let (point, scalar) = s.trim_end_matches("));").split_once("], ").unwrap();
+                let (px, py) = point.split_once(", ").unwrap();
+                let input = [vars[px], vars[py], vars[scalar]].concat();
+                let mul = bn128::run_mul(&input, 0, u64::MAX).unwrap().bytes;
+                let input = [&vars["x[0]"][..], &vars["x[1]"][..], &mul[..]].concat();
+                let [x, y] = call(&bn128::run_add(&input, 0, u64::MAX).unwrap().bytes);
+                vars.insert("x[0]".into(), x);
+                vars.insert("x[1]".into(), y);
+            } else if statement == "uint256[24] memory p;" {
+            } else if let Some(s) = statement.strip_prefix("p[") {
+                let (i, e) = s.trim_end_matches(';').split_once("] = ").unwrap();
+                p[i.parse::<usize>().unwrap()] = value(&vars, e);
+            } else if statement == "return ecPairing(p);" {
+                let output = bn128::run_pair(&p.concat(), 0, 0, u64::MAX).unwrap().bytes;
+                return output[..] == word(&[1])[..];
+            } else {
+                panic!("unexpected statement {}", statement);
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn contract_verifies_calldata_on_precompiles() {
+        let fixture = cube_fixture_on::<Bn254>(3);
+        let proof = fixture.prove();
+        let contract = verifier_contract(&fixture.params.vk).unwrap();
+        let inputs = fixture.public_inputs();
+        assert!(run_contract(&contract, &calldata(&proof, inputs)));
+
+        let wrong = [inputs[0] + Fr::from(1)];
+        assert!(!run_contract(&contract, &calldata(&proof, &wrong)));
+        let mut forged = proof.clone();
+        forged.c = proof.a;
+        assert!(!run_contract(&contract, &calldata(&forged, inputs)));
+
+        // input + r is the same scalar to ecMul but rejected by the contract
+        let mut data = calldata(&proof, inputs);
+        let mut input = uint::from_be_bytes(&data[4 + 8 * WORD..]);
+        let mut carry = 0;
+        for (l, r) in input.iter_mut().zip(Fr::MODULUS.iter()) {
+            let (sum, c1) = l.overflowing_add(*r);
+            let (sum, c2) = sum.overflowing_add(carry);
+            *l = sum;
+            carry = (c1 || c2) as u64;
+        }
+        data[4 + 8 * WORD..].copy_from_slice(&word(&input));
+        assert!(!run_contract(&contract, &data));
+    }
+
+    #[test]
+    fn calldata_layout() {
+        let fixture = cube_fixture_on::<Bn254>(3);
+        let proof = fixture.prove();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        let data = calldata(&proof, fixture.public_inputs());
+        assert_eq!(data.len(), 4 + 9 * WORD);
+        assert_eq!(&data[..4], &selector(1));
+        assert_eq!(signature(1), "verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[1])");
+        assert_eq!(signature(0), "verifyProof(uint256[2],uint256[2][2],uint256[2])");
+
+        let word = |i: usize| &data[4 + i * WORD..4 + (i + 1) * WORD];
+        let [x, y] = Bn254::g1_coordinates(&proof.a).unwrap();
+        assert_eq!((word(0), word(1)), (&x[..], &y[..]));
+        // b as [[x.c1, x.c0], [y.c1, y.c0]]
+        let [x, y] = Bn254::g2_coordinates(&proof.b).unwrap();
+        assert_eq!([word(2), word(3), word(4), word(5)], [&x[1][..], &x[0][..], &y[1][..], &y[0][..]]);
+        // out = 3^3 + 3 + 5
+        assert_eq!(word(8)[31], 35);
+        assert!(word(8)[..31].iter().all(|b| *b == 0));
+    }
+}
@@ -0,0 +1,709 @@
+// Query points for the prover, read one chunk at a time.
+//
+// `ParameterSource` abstracts over where the proving key lives. The prover
+// asks for a chunk starting at some offset of a query and consumes it
+// before asking for the next one, so a source only ever needs to hold one
+// chunk in memory. `&Parameters` is a source that hands out its vectors
+// directly. `StreamedParameters` decodes chunks of a key in the byte layout
+// below from anything implementing `ReadAt`, e.g. SPI flash or a file.
+//
+// Byte layout of an encoded proving key, integers big-endian:
+//
+//   magic "EGPK" | version u8 | flags u8 | 2 reserved bytes
+//   number of ic, h, l, a, b_g1 and b_g2 points, u32 each
+//   alpha_g1 | beta_g1 | beta_g2 | gamma_g2 | delta_g1 | delta_g2
+//   ic | h | l | a_g1 | b_g1 | b_g2
+//
+// The header is 32 bytes long. Bit 0 of the flags selects the point
+// encoding: uncompressed when clear, compressed when set. A compressed key
+// takes half the space, and each point then costs a square root when it is
+// decoded, i.e. once per proof. Decoding happens a chunk at a time through
+// `DecodePoints`, so the cost stays bounded by the chunk size in memory and
+// by the number of points in time. The group traits do not expose
+// coordinates, so by default each point is decoded on its own by the
+// curve's `GroupEncoding`. `bn254` batches G2: its square roots take two
+// exponentiations in the base field instead of two in the extension, plus
+// a division that a chunk shares as a single inversion.
+
+use core::convert::Infallible;
+
+use pairing::Engine;
+use pairing::group::prime::PrimeCurveAffine;
+use pairing::group::{ GroupEncoding, UncompressedEncoding };
+
+use crate::{ DecodingError, Parameters, VerificationKey };
+use crate::encoding::{ Reader, Writer };
+use crate::fixed::FixedBase;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+const MAGIC: &[u8; 4] = b"EGPK";
+const VERSION: u8 = 1;
+const COMPRESSED: u8 = 1;
+pub(crate) const HEADER_LEN: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Query {
+    H,
+    L,
+    A,
+    BG1,
+    BG2,
+}
+
+pub trait ParameterSource<E: Engine> {
+    type Error;
+
+    fn vk(&self) -> &VerificationKey<E>;
+
+    // Number of points in a query
+    fn len(&self, query: Query) -> usize;
+
+    // Points of a G1 query from `offset` on. Must return at least one point
+    // while `offset` is below the length of the query.
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], Self::Error>;
+
+    // Same as `g1`, for the B query in G2
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], Self::Error>;
+
+    // Fixed-base tables the prover uses instead of the query points where
+    // they are available, see `fixed`
+    fn fixed_base(&self) -> Option<&dyn FixedBase<E>> {
+        None
+    }
+}
+
+pub(crate) fn query_len<E: Engine>(params: &Parameters<E>, query: Query) -> usize {
+    match query {
+        Query::H => params.h.len(),
+        Query::L => params.l.len(),
+        Query::A => params.a_g1.len(),
+        Query::BG1 => params.b_g1.len(),
+        Query::BG2 => params.b_g2.len(),
+    }
+}
+
+pub(crate) fn query_g1<E: Engine>(params: &Parameters<E>, query: Query, offset: usize) -> &[E::G1Affine] {
+    match query {
+        Query::H => &params.h[offset..],
+        Query::L => &params.l[offset..],
+        Query::A => &params.a_g1[offset..],
+        Query::BG1 => &params.b_g1[offset..],
+        Query::BG2 => &[],
+    }
+}
+
+impl<E: Engine> ParameterSource<E> for Parameters<E> {
+    type Error = Infallible;
+
+    fn vk(&self) -> &VerificationKey<E> {
+        &self.vk
+    }
+
+    fn len(&self, query: Query) -> usize {
+        query_len(self, query)
+    }
+
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], Infallible> {
+        Ok(query_g1(self, query, offset))
+    }
+
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], Infallible> {
+        Ok(&self.b_g2[offset..])
+    }
+}
+
+impl<E: Engine> ParameterSource<E> for &Parameters<E> {
+    type Error = Infallible;
+
+    fn vk(&self) -> &VerificationKey<E> {
+        &self.vk
+    }
+
+    fn len(&self, query: Query) -> usize {
+        query_len(self, query)
+    }
+
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], Infallible> {
+        Ok(query_g1(self, query, offset))
+    }
+
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], Infallible> {
+        Ok(&self.b_g2[offset..])
+    }
+}
+
+// Random access to a byte store
+pub trait ReadAt {
+    type Error;
+
+    // Fills `buf` with the bytes starting at `offset`
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl ReadAt for &[u8] {
+    type Error = DecodingError;
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), DecodingError> {
+        let start = usize::try_from(offset).map_err(|_| DecodingError::UnexpectedEof)?;
+        let src = self.get(start..start + buf.len()).ok_or(DecodingError::UnexpectedEof)?;
+        buf.copy_from_slice(src);
+        Ok(())
+    }
+}
+
+// Adapter for `std::io` readers such as files
+#[cfg(any(test, feature = "std"))]
+pub struct IoReader<T>(pub T);
+
+#[cfg(any(test, feature = "std"))]
+impl<T: std::io::Read + std::io::Seek> ReadAt for IoReader<T> {
+    type Error = std::io::Error;
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), std::io::Error> {
+        self.0.seek(std::io::SeekFrom::Start(offset))?;
+        self.0.read_exact(buf)
+    }
+}
+
+#[derive(Debug)]
+pub enum SourceError<T> {
+    Read(T),
+    Decoding(DecodingError),
+}
+
+impl<T> From<DecodingError> for SourceError<T> {
+    fn from(e: DecodingError) -> Self {
+        SourceError::Decoding(e)
+    }
+}
+
+fn point_len<G: GroupEncoding + UncompressedEncoding>(compressed: bool) -> usize {
+    if compressed {
+        G::Repr::default().as_ref().len()
+    } else {
+        G::Uncompressed::default().as_ref().len()
+    }
+}
+
+pub(crate) fn g1_len<E: Engine>(compressed: bool) -> usize {
+    point_len::<E::G1Affine>(compressed)
+}
+
+pub(crate) fn g2_len<E: Engine>(compressed: bool) -> usize {
+    point_len::<E::G2Affine>(compressed)
+}
+
+// Number of points of each query, in the order of the layout
+pub(crate) struct Header {
+    pub compressed: bool,
+    pub ic: usize,
+    h: usize,
+    l: usize,
+    a: usize,
+    b_g1: usize,
+    b_g2: usize,
+}
+
+impl Header {
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        if rd.bytes(4)? != MAGIC || rd.u8()? != VERSION {
+            return Err(DecodingError::InvalidHeader);
+        }
+        let compressed = match rd.u8()? {
+            0 => false,
+            COMPRESSED => true,
+            _ => return Err(DecodingError::InvalidHeader),
+        };
+        rd.bytes(2)?;
+        Ok(Header {
+            compressed,
+            ic: rd.u32()? as usize,
+            h: rd.u32()? as usize,
+            l: rd.u32()? as usize,
+            a: rd.u32()? as usize,
+            b_g1: rd.u32()? as usize,
+            b_g2: rd.u32()? as usize,
+        })
+    }
+
+    // Byte offset of a query
+    pub fn offset<E: Engine>(&self, query: Query) -> u64 {
+        let (g1, g2) = (self.g1_len::<E>() as u64, self.g2_len::<E>() as u64);
+        let mut offset = HEADER_LEN as u64 + 3 * g1 + 3 * g2 + self.ic as u64 * g1;
+        for (q, n) in [(Query::H, self.h), (Query::L, self.l), (Query::A, self.a), (Query::BG1, self.b_g1)] {
+            if q == query {
+                return offset;
+            }
+            offset += n as u64 * g1;
+        }
+        offset
+    }
+
+    pub fn len(&self, query: Query) -> usize {
+        match query {
+            Query::H => self.h,
+            Query::L => self.l,
+            Query::A => self.a,
+            Query::BG1 => self.b_g1,
+            Query::BG2 => self.b_g2,
+        }
+    }
+
+    pub fn total<E: Engine>(&self) -> u64 {
+        self.offset::<E>(Query::BG2) + self.b_g2 as u64 * self.g2_len::<E>() as u64
+    }
+
+    // Size of an encoded point
+    pub fn g1_len<E: Engine>(&self) -> usize {
+        g1_len::<E>(self.compressed)
+    }
+
+    pub fn g2_len<E: Engine>(&self) -> usize {
+        g2_len::<E>(self.compressed)
+    }
+}
+
+fn encode_header<E: Engine>(w: &mut Writer, params: &Parameters<E>, compressed: bool) {
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    w.u8(if compressed { COMPRESSED } else { 0 });
+    w.bytes(&[0, 0]);
+    for n in [params.vk.ic.len(), params.h.len(), params.l.len(), params.a_g1.len(), params.b_g1.len(), params.b_g2.len()] {
+        w.u32(n as u32);
+    }
+}
+
+pub(crate) fn write_point<G: GroupEncoding + UncompressedEncoding>(w: &mut Writer, p: &G, compressed: bool) {
+    if compressed {
+        w.bytes(p.to_bytes().as_ref());
+    } else {
+        w.bytes(p.to_uncompressed().as_ref());
+    }
+}
+
+fn encode<E: Engine>(params: &Parameters<E>, compressed: bool) -> Vec<u8> {
+    let mut w = Writer::new();
+    encode_header(&mut w, params, compressed);
+
+    let vk = &params.vk;
+    for p in [vk.alpha_g1, vk.beta_g1] {
+        write_point(&mut w, &p, compressed);
+    }
+    for p in [vk.beta_g2, vk.gamma_g2] {
+        write_point(&mut w, &p, compressed);
+    }
+    write_point(&mut w, &vk.delta_g1, compressed);
+    write_point(&mut w, &vk.delta_g2, compressed);
+
+    for p in vk.ic.iter().chain(params.h.iter()).chain(params.l.iter()).chain(params.a_g1.iter()).chain(params.b_g1.iter()) {
+        write_point(&mut w, p, compressed);
+    }
+    for p in params.b_g2.iter() {
+        write_point(&mut w, p, compressed);
+    }
+
+    w.finish()
+}
+
+// Encodes a proving key in the layout described above
+pub fn encode_parameters<E: Engine>(params: &Parameters<E>) -> Vec<u8> {
+    encode(params, false)
+}
+
+// Encodes a proving key in the layout described above, with compressed points
+pub fn encode_parameters_compressed<E: Engine>(params: &Parameters<E>) -> Vec<u8> {
+    encode(params, true)
+}
+
+pub(crate) fn read_point<G: GroupEncoding + UncompressedEncoding>(
+    bytes: &[u8],
+    compressed: bool,
+    checked: bool,
+) -> Result<G, DecodingError> {
+    let p = if compressed {
+        let mut repr = G::Repr::default();
+        repr.as_mut().copy_from_slice(bytes);
+        if checked { G::from_bytes(&repr) } else { G::from_bytes_unchecked(&repr) }
+    } else {
+        let mut repr = G::Uncompressed::default();
+        repr.as_mut().copy_from_slice(bytes);
+        if checked { G::from_uncompressed(&repr) } else { G::from_uncompressed_unchecked(&repr) }
+    };
+    Option::from(p).ok_or(DecodingError::InvalidPoint)
+}
+
+// Decoding of consecutive points of a query, as `StreamedParameters` and
+// `ParametersRef` read them a chunk at a time
+pub trait DecodePoints: GroupEncoding + UncompressedEncoding {
+    // Decodes `out.len()` points from the start of `bytes`
+    fn decode_points(bytes: &[u8], out: &mut [Self], compressed: bool, checked: bool) -> Result<(), DecodingError> {
+        decode_each(bytes, out, compressed, checked)
+    }
+}
+
+// The default of `decode_points`, one point at a time
+pub fn decode_each<G: GroupEncoding + UncompressedEncoding>(
+    bytes: &[u8],
+    out: &mut [G],
+    compressed: bool,
+    checked: bool,
+) -> Result<(), DecodingError> {
+    let size = point_len::<G>(compressed);
+    for (p, b) in out.iter_mut().zip(bytes.chunks(size)) {
+        *p = read_point(b, compressed, checked)?;
+    }
+    Ok(())
+}
+
+// bls12_381 keeps its base field and the coordinates of its points private,
+// so a root can only be taken by `from_compressed`, one point at a time.
+// Sharing the inversions as BN254 G2 does would take a second
+// implementation of the 381-bit Fp and of Fp2 in this crate. A G1 root is a
+// single exponentiation, with nothing to share in any case.
+#[cfg(any(test, feature = "bls12_381"))]
+impl DecodePoints for bls12_381::G1Affine {}
+
+#[cfg(any(test, feature = "bls12_381"))]
+impl DecodePoints for bls12_381::G2Affine {}
+
+fn read_points<G: GroupEncoding + UncompressedEncoding>(
+    rd: &mut Reader,
+    n: usize,
+    compressed: bool,
+    checked: bool,
+) -> Result<Vec<G>, DecodingError> {
+    let size = point_len::<G>(compressed);
+    (0..n).map(|_| read_point(rd.bytes(size)?, compressed, checked)).collect()
+}
+
+pub(crate) fn decode_vk<E: Engine>(rd: &mut Reader, header: &Header) -> Result<VerificationKey<E>, DecodingError> {
+    let (g1, g2, c) = (header.g1_len::<E>(), header.g2_len::<E>(), header.compressed);
+    Ok(VerificationKey {
+        alpha_g1: read_point(rd.bytes(g1)?, c, true)?,
+        beta_g1: read_point(rd.bytes(g1)?, c, true)?,
+        beta_g2: read_point(rd.bytes(g2)?, c, true)?,
+        gamma_g2: read_point(rd.bytes(g2)?, c, true)?,
+        delta_g1: read_point(rd.bytes(g1)?, c, true)?,
+        delta_g2: read_point(rd.bytes(g2)?, c, true)?,
+        ic: read_points(rd, header.ic, c, true)?,
+    })
+}
+
+// Decodes a whole proving key, checking every point
+pub fn decode_parameters<E: Engine>(bytes: &[u8]) -> Result<Parameters<E>, DecodingError> {
+    let header = Header::decode(bytes.get(..HEADER_LEN).ok_or(DecodingError::UnexpectedEof)?)?;
+    if bytes.len() as u64 != header.total::<E>() {
+        return Err(DecodingError::InvalidHeader);
+    }
+
+    let mut rd = Reader::new(&bytes[HEADER_LEN..]);
+    let vk = decode_vk(&mut rd, &header)?;
+    let c = header.compressed;
+    Ok(Parameters {
+        vk,
+        h: read_points(&mut rd, header.h, c, true)?,
+        l: read_points(&mut rd, header.l, c, true)?,
+        a_g1: read_points(&mut rd, header.a, c, true)?,
+        b_g1: read_points(&mut rd, header.b_g1, c, true)?,
+        b_g2: read_points(&mut rd, header.b_g2, c, true)?,
+    })
+}
+
+// Proving key read from a byte store in chunks of a fixed number of points.
+// The verification key is loaded and checked up front, ic a chunk at a time
+// so that memory follows the points actually stored rather than the counts
+// in the header. Query points get the same curve and subgroup checks as in
+// `decode_parameters` on every read, so a corrupted store is an error and
+// not an invalid proof. A compressed key is decompressed one chunk per read.
+pub struct StreamedParameters<E: Engine, R: ReadAt> {
+    reader: R,
+    header: Header,
+    vk: VerificationKey<E>,
+    bytes: Vec<u8>,
+    g1: Vec<E::G1Affine>,
+    g2: Vec<E::G2Affine>,
+}
+
+impl<E: Engine, R: ReadAt> StreamedParameters<E, R>
+where
+    E::G1Affine: DecodePoints,
+    E::G2Affine: DecodePoints,
+{
+    pub fn new(mut reader: R, chunk: usize) -> Result<Self, SourceError<R::Error>> {
+        assert!(chunk > 0);
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_at(0, &mut header).map_err(SourceError::Read)?;
+        let header = Header::decode(&header)?;
+
+        let (g1, g2) = (header.g1_len::<E>(), header.g2_len::<E>());
+        let mut fixed = vec![0u8; 3 * g1 + 3 * g2];
+        reader.read_at(HEADER_LEN as u64, &mut fixed).map_err(SourceError::Read)?;
+        let mut vk = decode_vk(&mut Reader::new(&fixed), &Header { ic: 0, ..header })?;
+
+        let start = (HEADER_LEN + fixed.len()) as u64;
+        let mut bytes = vec![0u8; chunk * g2];
+        while vk.ic.len() < header.ic {
+            let (done, n) = (vk.ic.len(), chunk.min(header.ic - vk.ic.len()));
+            reader.read_at(start + done as u64 * g1 as u64, &mut bytes[..n * g1]).map_err(SourceError::Read)?;
+            vk.ic.resize(done + n, E::G1Affine::identity());
+            E::G1Affine::decode_points(&bytes, &mut vk.ic[done..], header.compressed, true)?;
+        }
+
+        Ok(StreamedParameters {
+            reader,
+            header,
+            vk,
+            bytes,
+            g1: vec![E::G1Affine::identity(); chunk],
+            g2: vec![E::G2Affine::identity(); chunk],
+        })
+    }
+
+    fn fill(&mut self, query: Query, offset: usize, size: usize) -> Result<usize, SourceError<R::Error>> {
+        let n = self.g1.len().min(self.header.len(query).saturating_sub(offset));
+        let start = self.header.offset::<E>(query) + (offset * size) as u64;
+        self.reader.read_at(start, &mut self.bytes[..n * size]).map_err(SourceError::Read)?;
+        Ok(n)
+    }
+}
+
+impl<E: Engine, R: ReadAt> ParameterSource<E> for StreamedParameters<E, R>
+where
+    E::G1Affine: DecodePoints,
+    E::G2Affine: DecodePoints,
+{
+    type Error = SourceError<R::Error>;
+
+    fn vk(&self) -> &VerificationKey<E> {
+        &self.vk
+    }
+
+    fn len(&self, query: Query) -> usize {
+        self.header.len(query)
+    }
+
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], Self::Error> {
+        let size = self.header.g1_len::<E>();
+        let n = self.fill(query, offset, size)?;
+        E::G1Affine::decode_points(&self.bytes, &mut self.g1[..n], self.header.compressed, true)?;
+        Ok(&self.g1[..n])
+    }
+
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], Self::Error> {
+        let size = self.header.g2_len::<E>();
+        let n = self.fill(Query::BG2, offset, size)?;
+        E::G2Affine::decode_points(&self.bytes, &mut self.g2[..n], self.header.compressed, true)?;
+        Ok(&self.g2[..n])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bls12_381::{ Bls12, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::prover::{ create_proof, create_proof_from_source, required_scratch, ProvingError };
+    use crate::tests::cube_fixture;
+    use super::*;
+
+    // Records the largest single read
+    struct Flash<'a> {
+        bytes: &'a [u8],
+        max_read: usize,
+    }
+
+    impl<'a> ReadAt for Flash<'a> {
+        type Error = DecodingError;
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), DecodingError> {
+            self.max_read = self.max_read.max(buf.len());
+            self.bytes.read_at(offset, buf)
+        }
+    }
+
+    #[test]
+    fn parameters_roundtrip() {
+        let fixture = cube_fixture(3);
+        let bytes = encode_parameters(&fixture.params);
+        let params = decode_parameters::<Bls12>(&bytes).unwrap();
+
+        assert_eq!(encode_parameters(&params), bytes);
+        assert_eq!(decode_parameters::<Bls12>(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::InvalidHeader));
+        assert_eq!(decode_parameters::<Bls12>(&bytes[1..]).err(), Some(DecodingError::InvalidHeader));
+    }
+
+    #[test]
+    fn compressed_parameters_roundtrip() {
+        let fixture = cube_fixture(3);
+        let bytes = encode_parameters(&fixture.params);
+        let mut compressed = encode_parameters_compressed(&fixture.params);
+
+        // Only the header is not halved
+        assert_eq!(compressed.len() - HEADER_LEN, (bytes.len() - HEADER_LEN) / 2);
+        let params = decode_parameters::<Bls12>(&compressed).unwrap();
+        assert_eq!(encode_parameters(&params), bytes);
+
+        compressed[5] = 2;
+        assert_eq!(decode_parameters::<Bls12>(&compressed).err(), Some(DecodingError::InvalidHeader));
+    }
+
+    #[test]
+    fn compressed_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let bytes = encode_parameters_compressed(&fixture.params);
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+
+        let mut scratch = vec![Scalar::zero(); required_scratch(&fixture.qap).unwrap()];
+        for chunk in [1, 4] {
+            let flash = Flash { bytes: &bytes, max_read: 0 };
+            let mut source = StreamedParameters::<Bls12, _>::new(flash, chunk).unwrap();
+            source.reader.max_read = 0;
+
+            let proof = create_proof_from_source(
+                &mut source,
+                &fixture.inputs,
+                &fixture.aux,
+                r,
+                s,
+                &fixture.qap,
+                fixture.num_constraints,
+                &mut scratch,
+            ).unwrap();
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+
+            assert!(source.reader.max_read <= chunk * g2_len::<Bls12>(true));
+        }
+    }
+
+    #[test]
+    fn streamed_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let bytes = encode_parameters(&fixture.params);
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+
+        let mut scratch = vec![Scalar::zero(); required_scratch(&fixture.qap).unwrap()];
+        for chunk in [1, 2, 64] {
+            let flash = Flash { bytes: &bytes, max_read: 0 };
+            let mut source = StreamedParameters::<Bls12, _>::new(flash, chunk).unwrap();
+            source.reader.max_read = 0;
+
+            let proof = create_proof_from_source(
+                &mut source,
+                &fixture.inputs,
+                &fixture.aux,
+                r,
+                s,
+                &fixture.qap,
+                fixture.num_constraints,
+                &mut scratch,
+            ).unwrap();
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+
+            // Reads never exceed one chunk of G2 points
+            assert!(source.reader.max_read <= chunk * g2_len::<Bls12>(false));
+        }
+
+        let mut source = StreamedParameters::<Bls12, _>::new(IoReader(Cursor::new(bytes)), 3).unwrap();
+        let proof = create_proof_from_source(
+            &mut source,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        ).unwrap();
+        assert_eq!(proof.c, expected.c);
+    }
+
+    #[test]
+    fn compressed_bn254_proof_verifies() {
+        use crate::bn254::{ Bn254, Fr };
+        use crate::tests::cube_fixture_on;
+        use crate::verifier::verify_proof;
+
+        // G2 points go through the batched decompression of `bn254`
+        let fixture = cube_fixture_on::<Bn254>(3);
+        let bytes = encode_parameters_compressed(&fixture.params);
+        let mut source = StreamedParameters::<Bn254, _>::new(&bytes[..], 2).unwrap();
+        let mut scratch = vec![Fr::zero(); required_scratch(&fixture.qap).unwrap()];
+        let proof = create_proof_from_source(
+            &mut source,
+            &fixture.inputs,
+            &fixture.aux,
+            Fr::random(thread_rng()),
+            Fr::random(thread_rng()),
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        ).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+    }
+
+    #[test]
+    fn streamed_rejects_bad_stores() {
+        let fixture = cube_fixture(3);
+        let mut bytes = encode_parameters(&fixture.params);
+
+        // The claimed number of ic points is not allocated up front, reading
+        // fails where the store ends or stops holding G1 points
+        let mut huge = bytes.clone();
+        huge[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(StreamedParameters::<Bls12, _>::new(&huge[..], 4).is_err());
+
+        // A point of the L query that is not on the curve
+        let header = Header::decode(&bytes[..HEADER_LEN]).unwrap();
+        let offset = header.offset::<Bls12>(Query::L) as usize;
+        bytes[offset + 40] ^= 1;
+        let mut source = StreamedParameters::<Bls12, _>::new(&bytes[..], 4).unwrap();
+        assert!(matches!(source.g1(Query::L, 0), Err(SourceError::Decoding(DecodingError::InvalidPoint))));
+
+        let mut scratch = vec![Scalar::zero(); required_scratch(&fixture.qap).unwrap()];
+        let proof = create_proof_from_source(
+            &mut source,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::one(),
+            Scalar::one(),
+            &fixture.qap,
+            fixture.num_constraints,
+            &mut scratch,
+        );
+        assert!(matches!(proof, Err(ProvingError::Source(SourceError::Decoding(DecodingError::InvalidPoint)))));
+    }
+}
@@ -0,0 +1,538 @@
+// Proving split into phases whose inputs and outputs can be serialized, so
+// that an integrator can run any of them on another worker, e.g. a host
+// with more compute than the device that holds the witness.
+//
+// The phases are:
+// - `evaluate`: A, B and C evaluated on the witness over the domain
+// - `quotient`: the coefficients of h(X) from those evaluations
+// - `run_msm`: one MSM against a query of the proving key, for a request
+//   built from the quotient or the witness
+// - `assemble`: the proof from the five MSM results, r and s
+//
+// Put together they compute the same proof as `prover::create_proof`.
+// Evaluations, quotients and MSM requests are derived from the witness
+// without any blinding, which only enters in `assemble`, so whoever runs
+// the other phases learns the witness and must be trusted with it.
+//
+// `quotient`, `run_msm` and `assemble` have `_with_backend` variants whose
+// FFTs, MSMs and scalar multiplications run on a `Backend`.
+//
+// Every message starts with the magic "EGSP", a version byte and a byte
+// for its kind, followed by the fields of the message in the codec of
+// `encoding`.
+
+use ff::{Field, PrimeField};
+use pairing::Engine;
+use pairing::group::Curve;
+
+use crate::{ DecodingError, Proof, VerificationKey };
+use crate::encoding::{ Reader, Writer };
+use crate::backend::{ Backend, Software };
+use crate::poly::{ coset_mul_assign_with, fft, fft_params, Fft };
+use crate::prover::{ assemble as assemble_proof, divide_by_z, msm_g1, msm_g2 };
+use crate::source::{ ParameterSource, Query };
+use crate::view::{ Constraints, Poly };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+#[cfg(any(test, feature = "zeroize"))]
+use pairing::group::Group;
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::{ wipe, wipe_slice };
+
+const MAGIC: &[u8; 4] = b"EGSP";
+const VERSION: u8 = 1;
+
+const EVALUATIONS: u8 = 0;
+const QUOTIENT: u8 = 1;
+const MSM_REQUEST: u8 = 2;
+const MSM_RESULT: u8 = 3;
+
+fn encode_header(w: &mut Writer, kind: u8) {
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    w.u8(kind);
+}
+
+fn decode_header(rd: &mut Reader, kind: u8) -> Result<(), DecodingError> {
+    if rd.bytes(4)? != MAGIC || rd.u8()? != VERSION || rd.u8()? != kind {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(())
+}
+
+fn decode_end(rd: &Reader) -> Result<(), DecodingError> {
+    if !rd.is_empty() {
+        return Err(DecodingError::InvalidHeader);
+    }
+    Ok(())
+}
+
+fn encode_query(w: &mut Writer, query: Query) {
+    w.u8(match query {
+        Query::H => 0,
+        Query::L => 1,
+        Query::A => 2,
+        Query::BG1 => 3,
+        Query::BG2 => 4,
+    });
+}
+
+fn decode_query(rd: &mut Reader) -> Result<Query, DecodingError> {
+    match rd.u8()? {
+        0 => Ok(Query::H),
+        1 => Ok(Query::L),
+        2 => Ok(Query::A),
+        3 => Ok(Query::BG1),
+        4 => Ok(Query::BG2),
+        _ => Err(DecodingError::InvalidHeader),
+    }
+}
+
+fn encode_scalars<S: PrimeField>(w: &mut Writer, scalars: &[S]) {
+    w.u32(scalars.len() as u32);
+    for x in scalars {
+        w.scalar(x);
+    }
+}
+
+fn decode_scalars<S: PrimeField>(rd: &mut Reader) -> Result<Vec<S>, DecodingError> {
+    let n = rd.u32()? as usize;
+    (0..n).map(|_| rd.scalar()).collect()
+}
+
+fn witness<S: PrimeField>(inputs: &[S], aux: &[S], i: usize) -> S {
+    if i < inputs.len() { inputs[i] } else { aux[i - inputs.len()] }
+}
+
+// A, B and C evaluated on the witness over the domain
+pub struct Evaluations<S: PrimeField> {
+    a: Vec<S>,
+    b: Vec<S>,
+    c: Vec<S>,
+}
+
+impl<S: PrimeField> Evaluations<S> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        encode_header(&mut w, EVALUATIONS);
+        for v in [&self.a, &self.b, &self.c] {
+            encode_scalars(&mut w, v);
+        }
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        decode_header(&mut rd, EVALUATIONS)?;
+        let a = decode_scalars(&mut rd)?;
+        let b = decode_scalars(&mut rd)?;
+        let c = decode_scalars(&mut rd)?;
+        decode_end(&rd)?;
+
+        // The domain must be one the field supports
+        let (_, m, _): (S, usize, u32) = fft_params(a.len()).ok_or(DecodingError::Mismatch)?;
+        if m != a.len() || b.len() != m || c.len() != m {
+            return Err(DecodingError::Mismatch);
+        }
+        Ok(Evaluations { a, b, c })
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<S: PrimeField> Drop for Evaluations<S> {
+    fn drop(&mut self) {
+        for v in [&mut self.a, &mut self.b, &mut self.c] {
+            wipe_slice(v, S::zero());
+        }
+    }
+}
+
+// Coefficients of h(X)
+pub struct Quotient<S: PrimeField> {
+    h: Vec<S>,
+}
+
+impl<S: PrimeField> Quotient<S> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        encode_header(&mut w, QUOTIENT);
+        encode_scalars(&mut w, &self.h);
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        decode_header(&mut rd, QUOTIENT)?;
+        let h = decode_scalars::<S>(&mut rd)?;
+        decode_end(&rd)?;
+
+        // One coefficient less than the domain has points
+        let m = h.len() + 1;
+        if !m.is_power_of_two() || m.trailing_zeros() > S::S {
+            return Err(DecodingError::Mismatch);
+        }
+        Ok(Quotient { h })
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<S: PrimeField> Drop for Quotient<S> {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.h, S::zero());
+    }
+}
+
+// The scalars of one MSM against a query of the proving key
+pub struct MsmRequest<S: PrimeField> {
+    query: Query,
+    scalars: Vec<S>,
+}
+
+impl<S: PrimeField> MsmRequest<S> {
+    // Against the H query, with the coefficients of h(X)
+    pub fn h(quotient: &Quotient<S>) -> Self {
+        MsmRequest { query: Query::H, scalars: quotient.h.clone() }
+    }
+
+    // Against the L query, with the auxiliary assignment
+    pub fn l(aux: &[S]) -> Self {
+        MsmRequest { query: Query::L, scalars: aux.to_vec() }
+    }
+
+    // Against the A query, with the witness values of the variables of A
+    pub fn a(qap: &impl Constraints<S>, inputs: &[S], aux: &[S]) -> Self {
+        Self::variables(Query::A, Poly::A, qap, inputs, aux)
+    }
+
+    // Against the B query in G1, with the witness values of the variables of B
+    pub fn b_g1(qap: &impl Constraints<S>, inputs: &[S], aux: &[S]) -> Self {
+        Self::variables(Query::BG1, Poly::B, qap, inputs, aux)
+    }
+
+    // Same as `b_g1`, against the B query in G2
+    pub fn b_g2(qap: &impl Constraints<S>, inputs: &[S], aux: &[S]) -> Self {
+        Self::variables(Query::BG2, Poly::B, qap, inputs, aux)
+    }
+
+    fn variables(query: Query, poly: Poly, qap: &impl Constraints<S>, inputs: &[S], aux: &[S]) -> Self {
+        let scalars = (0..qap.num_variables(poly))
+            .map(|i| witness(inputs, aux, qap.variable(poly, i)))
+            .collect();
+        MsmRequest { query, scalars }
+    }
+
+    pub fn query(&self) -> Query {
+        self.query
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        encode_header(&mut w, MSM_REQUEST);
+        encode_query(&mut w, self.query);
+        encode_scalars(&mut w, &self.scalars);
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        decode_header(&mut rd, MSM_REQUEST)?;
+        let query = decode_query(&mut rd)?;
+        let scalars = decode_scalars(&mut rd)?;
+        decode_end(&rd)?;
+        Ok(MsmRequest { query, scalars })
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<S: PrimeField> Drop for MsmRequest<S> {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.scalars, S::zero());
+    }
+}
+
+// The result of an `MsmRequest`
+pub enum MsmResult<E: Engine> {
+    G1(Query, E::G1),
+    G2(E::G2),
+}
+
+impl<E: Engine> MsmResult<E> {
+    pub fn query(&self) -> Query {
+        match self {
+            MsmResult::G1(query, _) => *query,
+            MsmResult::G2(_) => Query::BG2,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        encode_header(&mut w, MSM_RESULT);
+        encode_query(&mut w, self.query());
+        match self {
+            MsmResult::G1(_, p) => w.point(&p.to_affine()),
+            MsmResult::G2(p) => w.point(&p.to_affine()),
+        }
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut rd = Reader::new(bytes);
+        decode_header(&mut rd, MSM_RESULT)?;
+        let result = match decode_query(&mut rd)? {
+            Query::BG2 => MsmResult::G2(rd.point::<E::G2Affine>()?.into()),
+            query => MsmResult::G1(query, rd.point::<E::G1Affine>()?.into()),
+        };
+        decode_end(&rd)?;
+        Ok(result)
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<E: Engine> Drop for MsmResult<E> {
+    fn drop(&mut self) {
+        match self {
+            MsmResult::G1(_, p) => wipe(p, E::G1::identity()),
+            MsmResult::G2(p) => wipe(p, E::G2::identity()),
+        }
+    }
+}
+
+// Evaluates A, B and C on the witness over the domain of the QAP, or
+// returns `Mismatch` if the QAP is too large for the field
+pub fn evaluate<S: PrimeField>(qap: &impl Constraints<S>, inputs: &[S], aux: &[S]) -> Result<Evaluations<S>, DecodingError> {
+    let (_, m, _): (S, usize, u32) = fft_params(qap.num_constraints()).ok_or(DecodingError::Mismatch)?;
+
+    let eval = |poly: Poly| {
+        let mut output = vec![S::zero(); m];
+        qap.for_each_term(poly, &mut |i, mut x, u| {
+            x.mul_assign(witness(inputs, aux, i));
+            output[u].add_assign(x);
+        });
+        output
+    };
+
+    Ok(Evaluations {
+        a: eval(Poly::A),
+        b: eval(Poly::B),
+        c: eval(Poly::C),
+    })
+}
+
+// Computes the coefficients of h(X) from the evaluations
+pub fn quotient<S: PrimeField>(evals: Evaluations<S>) -> Quotient<S> {
+    quotient_with(evals, &fft)
+}
+
+// Same as `quotient`, with the FFTs performed by `backend`
+pub fn quotient_with_backend<E: Engine, B: Backend<E>>(backend: &B, evals: Evaluations<E::Fr>) -> Quotient<E::Fr> {
+    quotient_with(evals, &|a: &mut [E::Fr], omega: &E::Fr, exp| backend.fft(a, omega, exp))
+}
+
+fn quotient_with<S: PrimeField>(mut evals: Evaluations<S>, fft: &impl Fft<S>) -> Quotient<S> {
+    // `evaluate` and `from_bytes` only make evaluations over valid domains
+    let (omega, m, exp): (S, usize, u32) = fft_params(evals.a.len()).unwrap();
+    let Evaluations { a, b, c } = &mut evals;
+    coset_mul_assign_with(a, b, fft);
+    divide_by_z(a, c, &omega, exp, fft);
+
+    // h(X) has degree m - 2, the top coefficient is zero
+    Quotient { h: a[..m - 1].to_vec() }
+}
+
+#[derive(Debug)]
+pub enum MsmError<T> {
+    // The source failed to provide its points
+    Source(T),
+    // The request has more scalars than the query has points, or for other
+    // queries than H, a different number
+    Mismatch,
+}
+
+// Runs one MSM against the query points of a source, or against its
+// fixed-base tables if it has them for the query
+pub fn run_msm<E: Engine, P: ParameterSource<E>>(
+    source: &mut P,
+    request: &MsmRequest<E::Fr>
+) -> Result<MsmResult<E>, MsmError<P::Error>>
+{
+    run_msm_with_backend(&Software, source, request)
+}
+
+// Same as `run_msm`, with the MSM performed by `backend`
+pub fn run_msm_with_backend<E: Engine, B: Backend<E>, P: ParameterSource<E>>(
+    backend: &B,
+    source: &mut P,
+    request: &MsmRequest<E::Fr>
+) -> Result<MsmResult<E>, MsmError<P::Error>>
+{
+    let (query, scalars) = (request.query, &request.scalars);
+    let fits = if query == Query::H {
+        scalars.len() <= source.len(query)
+    } else {
+        scalars.len() == source.len(query)
+    };
+    if !fits {
+        return Err(MsmError::Mismatch);
+    }
+    let scalar = |i: usize| scalars.get(i).copied().unwrap_or_else(E::Fr::zero);
+
+    Ok(match query {
+        Query::BG2 => MsmResult::G2(msm_g2(backend, source, scalar).map_err(MsmError::Source)?),
+        query => MsmResult::G1(query, msm_g1(backend, source, query, scalar).map_err(MsmError::Source)?),
+    })
+}
+
+// Assembles the proof from the results of the H, L, A, BG1 and BG2 MSMs,
+// in any order
+pub fn assemble<E: Engine>(
+    vk: &VerificationKey<E>,
+    results: &[MsmResult<E>],
+    r: E::Fr,
+    s: E::Fr
+) -> Result<Proof<E>, DecodingError>
+{
+    assemble_with_backend(&Software, vk, results, r, s)
+}
+
+// Same as `assemble`, with the scalar multiplications performed by `backend`
+pub fn assemble_with_backend<E: Engine, B: Backend<E>>(
+    backend: &B,
+    vk: &VerificationKey<E>,
+    results: &[MsmResult<E>],
+    r: E::Fr,
+    s: E::Fr
+) -> Result<Proof<E>, DecodingError>
+{
+    let g1 = |query| match results.iter().filter(|x| x.query() == query).collect::<Vec<_>>()[..] {
+        [MsmResult::G1(_, p)] => Ok(*p),
+        _ => Err(DecodingError::Mismatch),
+    };
+    let g2 = match results.iter().filter(|x| x.query() == Query::BG2).collect::<Vec<_>>()[..] {
+        [MsmResult::G2(p)] => *p,
+        _ => return Err(DecodingError::Mismatch),
+    };
+
+    Ok(assemble_proof(
+        backend,
+        vk,
+        None,
+        g1(Query::H)?,
+        g1(Query::L)?,
+        g1(Query::A)?,
+        g1(Query::BG1)?,
+        g2,
+        &r,
+        &s,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+    use rand::thread_rng;
+
+    use crate::Parameters;
+    use crate::prover::create_proof;
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    // Stand-in for a remote worker, which only ever sees bytes
+    struct Worker {
+        params: Parameters<Bls12>,
+    }
+
+    impl Worker {
+        fn quotient(&self, message: &[u8]) -> Vec<u8> {
+            quotient(Evaluations::<Scalar>::from_bytes(message).unwrap()).to_bytes()
+        }
+
+        fn msm(&self, message: &[u8]) -> Vec<u8> {
+            let request = MsmRequest::from_bytes(message).unwrap();
+            run_msm::<Bls12, _>(&mut &self.params, &request).unwrap().to_bytes()
+        }
+    }
+
+    #[test]
+    fn split_proof_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let worker = Worker { params: fixture.params.clone() };
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let (qap, inputs, aux) = (&fixture.qap, &fixture.inputs, &fixture.aux);
+
+        let evals = evaluate(qap, inputs, aux).unwrap();
+        let quotient = Quotient::from_bytes(&worker.quotient(&evals.to_bytes())).unwrap();
+
+        let requests = [
+            MsmRequest::b_g2(qap, inputs, aux),
+            MsmRequest::h(&quotient),
+            MsmRequest::l(aux),
+            MsmRequest::a(qap, inputs, aux),
+            MsmRequest::b_g1(qap, inputs, aux),
+        ];
+        let results: Vec<MsmResult<Bls12>> = requests.iter()
+            .map(|x| MsmResult::from_bytes(&worker.msm(&x.to_bytes())).unwrap())
+            .collect();
+
+        let vk = &fixture.params.vk;
+        let proof = assemble(vk, &results, r, s).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), vk.clone()).is_ok());
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            inputs,
+            aux,
+            r,
+            s,
+            qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+
+        assert_eq!(assemble(vk, &results[1..], r, s).err(), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn messages_are_validated() {
+        let fixture = cube_fixture(3);
+        let evals = evaluate(&fixture.qap, &fixture.inputs, &fixture.aux).unwrap();
+
+        let bytes = evals.to_bytes();
+        assert!(Evaluations::<Scalar>::from_bytes(&bytes).is_ok());
+        assert_eq!(Quotient::<Scalar>::from_bytes(&bytes).err(), Some(DecodingError::InvalidHeader));
+        assert_eq!(Evaluations::<Scalar>::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::UnexpectedEof));
+
+        // Not a power of two
+        let evals = Evaluations { a: vec![Scalar::one(); 3], b: vec![Scalar::one(); 3], c: vec![Scalar::one(); 3] };
+        assert_eq!(Evaluations::<Scalar>::from_bytes(&evals.to_bytes()).err(), Some(DecodingError::Mismatch));
+        let quotient = Quotient { h: vec![Scalar::one(); 3] };
+        assert!(Quotient::<Scalar>::from_bytes(&quotient.to_bytes()).is_ok());
+        let quotient = Quotient { h: vec![Scalar::one(); 4] };
+        assert_eq!(Quotient::<Scalar>::from_bytes(&quotient.to_bytes()).err(), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn msm_requests_must_fit() {
+        let fixture = cube_fixture(3);
+        let (qap, inputs, aux) = (&fixture.qap, &fixture.inputs, &fixture.aux);
+        let mut source = &fixture.params;
+
+        let mut request = MsmRequest::l(aux);
+        assert!(run_msm::<Bls12, _>(&mut source, &request).is_ok());
+        request.scalars.push(Scalar::one());
+        assert!(matches!(run_msm::<Bls12, _>(&mut source, &request), Err(MsmError::Mismatch)));
+
+        // H takes fewer scalars than points, never more
+        let mut request = MsmRequest::h(&quotient(evaluate(qap, inputs, aux).unwrap()));
+        request.scalars.pop();
+        assert!(run_msm::<Bls12, _>(&mut source, &request).is_ok());
+        request.scalars.resize(fixture.params.h.len() + 1, Scalar::one());
+        assert!(matches!(run_msm::<Bls12, _>(&mut source, &request), Err(MsmError::Mismatch)));
+    }
+}
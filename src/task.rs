@@ -0,0 +1,895 @@
+// Resumable prover for firmware that cannot afford to block for the whole
+// duration of `create_proof`, e.g. because of a watchdog.
+//
+// A `ProverTask` performs the same computation as `prover::create_proof`,
+// but split into units of work: one QAP term while evaluating A, B and C,
+// one butterfly or element update while computing h(X), and one term of an
+// MSM. `step(budget)` performs at most `budget` units and returns
+// `Poll::Pending` until the proof is complete.
+//
+// A task made with `with_backend` hands the scalar multiplications of the
+// MSMs and of the final proof to a `Backend`. The FFTs stay here, one
+// butterfly at a time, since a backend only transforms whole vectors and
+// one transform would exceed any budget.
+//
+// The intermediate state can be saved with `to_bytes` at any point between
+// steps and restored with `resume`, given the same parameters, QAP and
+// assignment. Its header holds a Keccak-256 fingerprint of those three,
+// and `resume` rejects a state saved with different ones. The saved state
+// contains r, s and witness-dependent values, so it must be stored as
+// carefully as the witness itself.
+
+use core::convert::Infallible;
+use core::ops::{AddAssign, MulAssign, SubAssign};
+use core::task::Poll;
+
+use ff::{Field, PrimeField};
+use pairing::Engine;
+use pairing::group::{Curve, Group, GroupEncoding};
+
+use crate::{ DecodingError, Parameters, Proof, QAP };
+use crate::backend::{ Backend, Software };
+use crate::encoding::{ Reader, Writer };
+use crate::keccak::Keccak256;
+use crate::poly::{ bitreverse, butterfly, Domain };
+use crate::prover::{ assemble, ProvingError };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{boxed::Box, vec, vec::Vec};
+
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::{ wipe, wipe_slice };
+
+const MAGIC: &[u8; 4] = b"EGPT";
+const VERSION: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    // Evaluation of the A, B and C polynomials on the witness
+    Evaluate,
+    // FFTs and pointwise arithmetic for the coefficients of h(X)
+    Quotient,
+    // MSMs against the H, L, A and B queries
+    MsmH,
+    MsmL,
+    MsmA,
+    MsmB,
+    Done,
+}
+
+impl Phase {
+    fn from_u8(x: u8) -> Result<Self, DecodingError> {
+        match x {
+            0 => Ok(Phase::Evaluate),
+            1 => Ok(Phase::Quotient),
+            2 => Ok(Phase::MsmH),
+            3 => Ok(Phase::MsmL),
+            4 => Ok(Phase::MsmA),
+            5 => Ok(Phase::MsmB),
+            6 => Ok(Phase::Done),
+            _ => Err(DecodingError::InvalidHeader),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Phase::Evaluate => Phase::Quotient,
+            Phase::Quotient => Phase::MsmH,
+            Phase::MsmH => Phase::MsmL,
+            Phase::MsmL => Phase::MsmA,
+            Phase::MsmA => Phase::MsmB,
+            Phase::MsmB | Phase::Done => Phase::Done,
+        }
+    }
+}
+
+// Units of work done and total within the current phase
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Buf {
+    A,
+    B,
+    C,
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    // Forward or inverse FFT, without the 1/m scaling of the latter
+    Fft(Buf, bool),
+    // Multiply by 1/m
+    ScaleInvM(Buf),
+    // Multiply by the inverse of the vanishing polynomial on the coset
+    ScaleInvZ(Buf),
+    // Multiply element i by g^i, or g^-i / m
+    Coset(Buf, bool),
+    Mul(Buf, Buf),
+    Sub(Buf, Buf),
+}
+
+// Same sequence as the h(X) computation in `prover::create_proof`, with the
+// last 1/m folded into the inverse coset powers as `poly::Domain` does
+const QUOTIENT: [Op; 17] = [
+    Op::Fft(Buf::A, true),
+    Op::ScaleInvM(Buf::A),
+    Op::Fft(Buf::B, true),
+    Op::ScaleInvM(Buf::B),
+    Op::Coset(Buf::A, false),
+    Op::Fft(Buf::A, false),
+    Op::Coset(Buf::B, false),
+    Op::Fft(Buf::B, false),
+    Op::Mul(Buf::A, Buf::B),
+    Op::Fft(Buf::C, true),
+    Op::ScaleInvM(Buf::C),
+    Op::Coset(Buf::C, false),
+    Op::Fft(Buf::C, false),
+    Op::Sub(Buf::A, Buf::C),
+    Op::ScaleInvZ(Buf::A),
+    Op::Fft(Buf::A, true),
+    Op::Coset(Buf::A, true),
+];
+
+// Position within the current phase. Within Evaluate, `op` selects the
+// A, B or C query, `stage` the variable and `pos` the term. Within
+// Quotient, `op` indexes QUOTIENT, `stage` is 0 for the bit reversal of an
+// FFT and the layer plus one otherwise, and `pos` the element or butterfly.
+// The MSM phases only use `pos`.
+#[derive(Clone, Copy, Default)]
+struct Cursor {
+    op: usize,
+    stage: usize,
+    pos: usize,
+    done: usize,
+}
+
+// Keccak-256 of the key, the QAP and the assignment, so that a saved
+// state is only resumed against the ones it was started with
+fn fingerprint<E: Engine>(params: &Parameters<E>, qap: &QAP<E::Fr>, inputs: &[E::Fr], aux: &[E::Fr]) -> [u8; 32] {
+    let mut k = Keccak256::default();
+    let len = |k: &mut Keccak256, n: usize| k.update(&(n as u64).to_be_bytes());
+
+    let vk = &params.vk;
+    for p in [vk.alpha_g1, vk.beta_g1, vk.delta_g1] {
+        k.update(p.to_bytes().as_ref());
+    }
+    for p in [vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+        k.update(p.to_bytes().as_ref());
+    }
+    for query in [&vk.ic, &params.h, &params.l, &params.a_g1, &params.b_g1] {
+        len(&mut k, query.len());
+        for p in query.iter() {
+            k.update(p.to_bytes().as_ref());
+        }
+    }
+    len(&mut k, params.b_g2.len());
+    for p in params.b_g2.iter() {
+        k.update(p.to_bytes().as_ref());
+    }
+
+    for query in [&qap.a, &qap.b, &qap.c] {
+        len(&mut k, query.len());
+        for (i, terms) in query.iter() {
+            len(&mut k, *i);
+            len(&mut k, terms.len());
+            for (coeff, constraint) in terms.iter() {
+                k.update(coeff.to_repr().as_ref());
+                len(&mut k, *constraint);
+            }
+        }
+    }
+
+    for values in [inputs, aux] {
+        len(&mut k, values.len());
+        for x in values.iter() {
+            k.update(x.to_repr().as_ref());
+        }
+    }
+    k.finish()
+}
+
+pub struct ProverTask<'a, E: Engine, B: Backend<E> = Software> {
+    backend: &'a B,
+    params: &'a Parameters<E>,
+    qap: &'a QAP<E::Fr>,
+    inputs: &'a [E::Fr],
+    aux: &'a [E::Fr],
+    r: E::Fr,
+    s: E::Fr,
+
+    domain: Domain<E::Fr>,
+    fingerprint: [u8; 32],
+    phase: Phase,
+    cursor: Cursor,
+
+    at: Vec<E::Fr>,
+    bt: Vec<E::Fr>,
+    ct: Vec<E::Fr>,
+
+    h: E::G1,
+    l: E::G1,
+    a_g1: E::G1,
+    b_g1: E::G1,
+    b_g2: E::G2,
+
+    proof: Option<Proof<E>>,
+    on_progress: Option<Box<dyn FnMut(Progress) + 'a>>,
+}
+
+impl<'a, E: Engine> ProverTask<'a, E> {
+    // `Mismatch` unless the parameters fit the QAP and the domain of
+    // `num_constraints`, and the assignment fits the parameters
+    pub fn new(
+        params: &'a Parameters<E>,
+        inputs: &'a [E::Fr],
+        aux: &'a [E::Fr],
+        r: E::Fr,
+        s: E::Fr,
+        qap: &'a QAP<E::Fr>,
+        num_constraints: usize
+    ) -> Result<Self, ProvingError<Infallible>>
+    {
+        Self::with_backend(&Software, params, inputs, aux, r, s, qap, num_constraints)
+    }
+
+    // Restores a task saved with `to_bytes`. The remaining arguments must
+    // be the ones the task was created with, `Mismatch` otherwise.
+    pub fn resume(
+        params: &'a Parameters<E>,
+        inputs: &'a [E::Fr],
+        aux: &'a [E::Fr],
+        qap: &'a QAP<E::Fr>,
+        num_constraints: usize,
+        state: &[u8]
+    ) -> Result<Self, DecodingError>
+    {
+        Self::resume_with_backend(&Software, params, inputs, aux, qap, num_constraints, state)
+    }
+}
+
+impl<'a, E: Engine, B: Backend<E>> ProverTask<'a, E, B> {
+    // Same as `new`, with the scalar multiplications performed by `backend`
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_backend(
+        backend: &'a B,
+        params: &'a Parameters<E>,
+        inputs: &'a [E::Fr],
+        aux: &'a [E::Fr],
+        r: E::Fr,
+        s: E::Fr,
+        qap: &'a QAP<E::Fr>,
+        num_constraints: usize
+    ) -> Result<Self, ProvingError<Infallible>>
+    {
+        let domain = Domain::new(num_constraints).ok_or(ProvingError::DomainTooLarge)?;
+        let m = domain.m;
+
+        if params.check(qap).is_err()
+            || inputs.len() != params.vk.ic.len()
+            || aux.len() != params.l.len()
+            || qap.num_constraints() > m
+            || params.h.len() + 1 < m
+        {
+            return Err(ProvingError::Mismatch);
+        }
+
+        Ok(ProverTask {
+            backend,
+            params,
+            qap,
+            inputs,
+            aux,
+            r,
+            s,
+
+            domain,
+            fingerprint: fingerprint(params, qap, inputs, aux),
+            phase: Phase::Evaluate,
+            cursor: Cursor::default(),
+
+            at: vec![E::Fr::zero(); m],
+            bt: vec![E::Fr::zero(); m],
+            ct: vec![E::Fr::zero(); m],
+
+            h: E::G1::identity(),
+            l: E::G1::identity(),
+            a_g1: E::G1::identity(),
+            b_g1: E::G1::identity(),
+            b_g2: E::G2::identity(),
+
+            proof: None,
+            on_progress: None,
+        })
+    }
+
+    // Called with the current progress at the end of every step and
+    // whenever a phase completes
+    pub fn on_progress<F: FnMut(Progress) + 'a>(&mut self, f: F) {
+        self.on_progress = Some(Box::new(f));
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            phase: self.phase,
+            done: self.cursor.done,
+            total: self.total(self.phase),
+        }
+    }
+
+    // Performs at most `budget` units of work
+    pub fn step(&mut self, budget: usize) -> Poll<Proof<E>> {
+        let mut budget = budget;
+        while self.phase != Phase::Done && budget > 0 {
+            if self.cursor.done == self.total(self.phase) {
+                self.report();
+                self.phase = self.phase.next();
+                self.cursor = Cursor::default();
+                continue;
+            }
+
+            match self.phase {
+                Phase::Evaluate => self.evaluate(),
+                Phase::Quotient => self.quotient(),
+                Phase::MsmH => self.msm_h(),
+                Phase::MsmL => self.msm_l(),
+                Phase::MsmA => self.msm_a(),
+                Phase::MsmB => self.msm_b(),
+                Phase::Done => unreachable!(),
+            }
+            self.cursor.done += 1;
+            budget -= 1;
+        }
+        self.report();
+
+        if self.phase != Phase::Done {
+            return Poll::Pending;
+        }
+
+        if self.proof.is_none() {
+            self.proof = Some(self.assemble());
+        }
+        Poll::Ready(self.proof.clone().unwrap())
+    }
+
+    fn report(&mut self) {
+        let progress = self.progress();
+        if let Some(f) = self.on_progress.as_mut() {
+            f(progress);
+        }
+    }
+
+    fn total(&self, phase: Phase) -> usize {
+        let m = self.domain.m;
+        match phase {
+            Phase::Evaluate => [&self.qap.a, &self.qap.b, &self.qap.c].iter()
+                .flat_map(|q| q.iter())
+                .map(|(_, terms)| terms.len())
+                .sum(),
+            Phase::Quotient => QUOTIENT.iter()
+                .map(|op| match op {
+                    Op::Fft(..) => m + (self.domain.exp as usize) * m / 2,
+                    _ => m,
+                })
+                .sum(),
+            Phase::MsmH => m - 1,
+            Phase::MsmL => self.aux.len(),
+            Phase::MsmA => self.qap.a_constraints.len(),
+            Phase::MsmB => self.qap.b_constraints.len(),
+            Phase::Done => 0,
+        }
+    }
+
+    // Units of work in the current phase before the cursor, or None if it
+    // is out of bounds. A saved cursor is only consistent if this is its
+    // `done`. At the end of Quotient, `op` is one past the last operation.
+    fn units_before(&self) -> Option<usize> {
+        let Cursor { op, stage, pos, .. } = self.cursor;
+        let m = self.domain.m;
+        let exp = self.domain.exp as usize;
+        match self.phase {
+            Phase::Evaluate => {
+                let qap = self.qap;
+                let query = [&qap.a, &qap.b, &qap.c];
+                let terms = |q: &[_]| q.iter().map(|(_, t): &(usize, Vec<_>)| t.len()).sum::<usize>();
+                let q = query.get(op)?;
+                if stage > q.len() || pos > q.get(stage).map_or(0, |(_, t)| t.len()) {
+                    return None;
+                }
+                Some(query[..op].iter().map(|q| terms(q)).sum::<usize>() + terms(&q[..stage]) + pos)
+            },
+            Phase::Quotient => {
+                let before = QUOTIENT[..op.min(QUOTIENT.len())].iter()
+                    .map(|op| match op {
+                        Op::Fft(..) => m + exp * m / 2,
+                        _ => m,
+                    })
+                    .sum::<usize>();
+                match QUOTIENT.get(op) {
+                    None => (op == QUOTIENT.len() && stage == 0 && pos == 0).then_some(before),
+                    Some(Op::Fft(..)) if stage == 0 => (pos < m).then_some(before + pos),
+                    Some(Op::Fft(..)) => (stage <= exp && pos < m / 2).then_some(before + m + (stage - 1) * m / 2 + pos),
+                    Some(_) => (stage == 0 && pos < m).then_some(before + pos),
+                }
+            },
+            _ => (op == 0 && stage == 0 && pos == 0).then_some(self.cursor.done),
+        }
+    }
+
+    fn buf(&mut self, b: Buf) -> &mut Vec<E::Fr> {
+        match b {
+            Buf::A => &mut self.at,
+            Buf::B => &mut self.bt,
+            Buf::C => &mut self.ct,
+        }
+    }
+
+    fn witness(&self, i: usize) -> &E::Fr {
+        if i < self.inputs.len() {
+            &self.inputs[i]
+        } else {
+            &self.aux[i - self.inputs.len()]
+        }
+    }
+
+    fn evaluate(&mut self) {
+        let qap = self.qap;
+        let query = [&qap.a, &qap.b, &qap.c];
+
+        // Skip past exhausted variables and queries
+        let Cursor { mut op, mut stage, mut pos, .. } = self.cursor;
+        while stage == query[op].len() || pos == query[op][stage].1.len() {
+            if stage == query[op].len() {
+                op += 1;
+                stage = 0;
+            } else {
+                stage += 1;
+            }
+            pos = 0;
+        }
+
+        let (i, terms) = &query[op][stage];
+        let (coeff, constraint) = terms[pos];
+        let mut x = coeff;
+        x.mul_assign(self.witness(*i));
+        let buf = match op {
+            0 => Buf::A,
+            1 => Buf::B,
+            _ => Buf::C,
+        };
+        self.buf(buf)[constraint].add_assign(x);
+
+        self.cursor.op = op;
+        self.cursor.stage = stage;
+        self.cursor.pos = pos + 1;
+    }
+
+    fn quotient(&mut self) {
+        let m = self.domain.m;
+        let exp = self.domain.exp;
+        let Cursor { op, stage, pos, .. } = self.cursor;
+
+        let done = match QUOTIENT[op] {
+            Op::Fft(b, inverse) => {
+                if stage == 0 {
+                    let rk = bitreverse(pos as u32, exp) as usize;
+                    if pos < rk {
+                        self.buf(b).swap(rk, pos);
+                    }
+                    if pos + 1 < m {
+                        self.cursor.pos = pos + 1;
+                        false
+                    } else if exp == 0 {
+                        true
+                    } else {
+                        self.cursor.stage = 1;
+                        self.cursor.pos = 0;
+                        false
+                    }
+                } else {
+                    let half = 1 << (stage - 1);
+                    let omegas = if inverse { &self.domain.omegas_inv } else { &self.domain.omegas };
+                    let w = omegas[(pos % half) * (m / (2 * half))];
+                    let k = (pos / half) * 2 * half + pos % half;
+                    butterfly(self.buf(b), k, half, &w);
+
+                    if pos + 1 == m / 2 {
+                        self.cursor.stage = stage + 1;
+                        self.cursor.pos = 0;
+                        stage == exp as usize
+                    } else {
+                        self.cursor.pos = pos + 1;
+                        false
+                    }
+                }
+            },
+            Op::ScaleInvM(b) => {
+                let f = self.domain.minv;
+                self.buf(b)[pos].mul_assign(&f);
+                pos + 1 == m
+            },
+            Op::ScaleInvZ(b) => {
+                let f = self.domain.zinv;
+                self.buf(b)[pos].mul_assign(&f);
+                pos + 1 == m
+            },
+            Op::Coset(b, inverse) => {
+                let u = if inverse { self.domain.coset_inv[pos] } else { self.domain.coset[pos] };
+                self.buf(b)[pos].mul_assign(&u);
+                pos + 1 == m
+            },
+            Op::Mul(x, y) => {
+                let t = self.buf(y)[pos];
+                self.buf(x)[pos].mul_assign(&t);
+                pos + 1 == m
+            },
+            Op::Sub(x, y) => {
+                let t = self.buf(y)[pos];
+                self.buf(x)[pos].sub_assign(&t);
+                pos + 1 == m
+            },
+        };
+
+        if done {
+            self.cursor.op = op + 1;
+            self.cursor.stage = 0;
+            self.cursor.pos = 0;
+        } else if !matches!(QUOTIENT[op], Op::Fft(..)) {
+            self.cursor.pos = pos + 1;
+        }
+    }
+
+    fn msm_h(&mut self) {
+        let i = self.cursor.done;
+        self.h.add_assign(self.backend.mul_g1(self.params.h[i].into(), &self.at[i]));
+    }
+
+    fn msm_l(&mut self) {
+        let i = self.cursor.done;
+        self.l.add_assign(self.backend.mul_g1(self.params.l[i].into(), &self.aux[i]));
+    }
+
+    fn msm_a(&mut self) {
+        let i = self.cursor.done;
+        let x = *self.witness(self.qap.a_constraints[i]);
+        self.a_g1.add_assign(self.backend.mul_g1(self.params.a_g1[i].into(), &x));
+    }
+
+    fn msm_b(&mut self) {
+        let i = self.cursor.done;
+        let x = *self.witness(self.qap.b_constraints[i]);
+        self.b_g1.add_assign(self.backend.mul_g1(self.params.b_g1[i].into(), &x));
+        self.b_g2.add_assign(self.backend.mul_g2(self.params.b_g2[i].into(), &x));
+    }
+
+    fn assemble(&self) -> Proof<E> {
+        assemble(self.backend, &self.params.vk, None, self.h, self.l, self.a_g1, self.b_g1, self.b_g2, &self.r, &self.s)
+    }
+
+    // Saves the intermediate state
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u8(VERSION);
+        w.bytes(&self.fingerprint);
+        w.u32(self.domain.m as u32);
+        w.u32(self.inputs.len() as u32);
+        w.u32(self.aux.len() as u32);
+
+        w.u8(self.phase as u8);
+        w.u64(self.cursor.op as u64);
+        w.u64(self.cursor.stage as u64);
+        w.u64(self.cursor.pos as u64);
+        w.u64(self.cursor.done as u64);
+
+        w.scalar(&self.r);
+        w.scalar(&self.s);
+        for x in self.at.iter().chain(self.bt.iter()).chain(self.ct.iter()) {
+            w.scalar(x);
+        }
+
+        for p in [self.h, self.l, self.a_g1, self.b_g1] {
+            w.point(&p.to_affine());
+        }
+        w.point(&self.b_g2.to_affine());
+
+        w.finish()
+    }
+
+    // Same as `resume`, with the scalar multiplications performed by `backend`
+    pub fn resume_with_backend(
+        backend: &'a B,
+        params: &'a Parameters<E>,
+        inputs: &'a [E::Fr],
+        aux: &'a [E::Fr],
+        qap: &'a QAP<E::Fr>,
+        num_constraints: usize,
+        state: &[u8]
+    ) -> Result<Self, DecodingError>
+    {
+        let mut rd = Reader::new(state);
+        if rd.bytes(4)? != MAGIC || rd.u8()? != VERSION {
+            return Err(DecodingError::InvalidHeader);
+        }
+
+        let mut task = Self::with_backend(backend, params, inputs, aux, E::Fr::zero(), E::Fr::zero(), qap, num_constraints)
+            .map_err(|_| DecodingError::Mismatch)?;
+        if rd.bytes(32)? != task.fingerprint
+            || rd.u32()? as usize != task.domain.m
+            || rd.u32()? as usize != inputs.len()
+            || rd.u32()? as usize != aux.len()
+        {
+            return Err(DecodingError::Mismatch);
+        }
+
+        task.phase = Phase::from_u8(rd.u8()?)?;
+        task.cursor = Cursor {
+            op: rd.u64()? as usize,
+            stage: rd.u64()? as usize,
+            pos: rd.u64()? as usize,
+            done: rd.u64()? as usize,
+        };
+        let done = task.cursor.done;
+        if done > task.total(task.phase) || task.units_before() != Some(done) {
+            return Err(DecodingError::InvalidHeader);
+        }
+
+        task.r = rd.scalar()?;
+        task.s = rd.scalar()?;
+        for x in task.at.iter_mut().chain(task.bt.iter_mut()).chain(task.ct.iter_mut()) {
+            *x = rd.scalar()?;
+        }
+
+        let mut g1 = [E::G1::identity(); 4];
+        for p in g1.iter_mut() {
+            *p = rd.point::<E::G1Affine>()?.into();
+        }
+        [task.h, task.l, task.a_g1, task.b_g1] = g1;
+        task.b_g2 = rd.point::<E::G2Affine>()?.into();
+
+        if !rd.is_empty() {
+            return Err(DecodingError::InvalidHeader);
+        }
+
+        Ok(task)
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<'a, E: Engine, B: Backend<E>> Drop for ProverTask<'a, E, B> {
+    fn drop(&mut self) {
+        wipe(&mut self.r, E::Fr::zero());
+        wipe(&mut self.s, E::Fr::zero());
+        wipe_slice(&mut self.at, E::Fr::zero());
+        wipe_slice(&mut self.bt, E::Fr::zero());
+        wipe_slice(&mut self.ct, E::Fr::zero());
+        for p in [&mut self.h, &mut self.l, &mut self.a_g1, &mut self.b_g1] {
+            wipe(p, E::G1::identity());
+        }
+        wipe(&mut self.b_g2, E::G2::identity());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use bls12_381::{ Bls12, Scalar };
+    use rand::thread_rng;
+
+    use crate::poly::fft_params;
+    use crate::prover::create_proof;
+    use crate::tests::{ cube_fixture, sum_fixture };
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn task_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+
+        for budget in [1, 7, usize::MAX] {
+            let mut task = ProverTask::new(
+                &fixture.params,
+                &fixture.inputs,
+                &fixture.aux,
+                r,
+                s,
+                &fixture.qap,
+                fixture.num_constraints,
+            ).unwrap();
+
+            let proof = loop {
+                if let Poll::Ready(proof) = task.step(budget) {
+                    break proof;
+                }
+            };
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+        }
+    }
+
+    #[test]
+    fn task_reports_progress() {
+        let fixture = cube_fixture(3);
+        let log = RefCell::new(Vec::new());
+        let mut task = ProverTask::new(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::one(),
+            Scalar::one(),
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        task.on_progress(|p| log.borrow_mut().push(p));
+
+        assert!(task.step(3).is_pending());
+        assert_eq!(task.progress(), Progress { phase: Phase::Evaluate, done: 3, total: task.total(Phase::Evaluate) });
+        while task.step(5).is_pending() {}
+        drop(task);
+
+        let log = log.into_inner();
+        for phase in [Phase::Evaluate, Phase::Quotient, Phase::MsmH, Phase::MsmL, Phase::MsmA, Phase::MsmB] {
+            let last = log.iter().rfind(|p| p.phase == phase).unwrap();
+            assert_eq!(last.done, last.total);
+        }
+        assert!(log.windows(2).all(|w| w[0].phase != w[1].phase || w[0].done <= w[1].done));
+    }
+
+    #[test]
+    fn task_survives_restart() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let mut task = ProverTask::new(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::random(&mut rng),
+            Scalar::random(&mut rng),
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+
+        // Save and reload after every step, including in the middle of FFTs
+        let mut state = task.to_bytes();
+        let proof = loop {
+            task = ProverTask::resume(
+                &fixture.params,
+                &fixture.inputs,
+                &fixture.aux,
+                &fixture.qap,
+                fixture.num_constraints,
+                &state,
+            ).unwrap();
+
+            match task.step(3) {
+                Poll::Ready(proof) => break proof,
+                Poll::Pending => state = task.to_bytes(),
+            }
+        };
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        let resume = |n, state: &[u8]| {
+            ProverTask::resume(&fixture.params, &fixture.inputs, &fixture.aux, &fixture.qap, n, state).err()
+        };
+        assert_eq!(resume(fixture.num_constraints, &state[..state.len() - 1]), Some(DecodingError::UnexpectedEof));
+        assert_eq!(resume(fixture.num_constraints, &state[1..]), Some(DecodingError::InvalidHeader));
+        assert_eq!(resume(4 * fixture.num_constraints, &state), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn task_rejects_other_inputs() {
+        let fixture = cube_fixture(3);
+        let other = cube_fixture(5);
+        let new = |params, aux| {
+            ProverTask::new(params, &fixture.inputs, aux, Scalar::one(), Scalar::one(), &fixture.qap, fixture.num_constraints).err()
+        };
+
+        let mut short = fixture.params.clone();
+        short.h.truncate(1);
+        assert_eq!(new(&short, &fixture.aux), Some(ProvingError::Mismatch));
+        let mut short = fixture.params.clone();
+        short.b_g2.pop();
+        assert_eq!(new(&short, &fixture.aux), Some(ProvingError::Mismatch));
+        assert_eq!(new(&fixture.params, &fixture.aux[1..]), Some(ProvingError::Mismatch));
+
+        // Same shapes, different key or assignment
+        let task = ProverTask::new(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::one(),
+            Scalar::one(),
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        let state = task.to_bytes();
+        let n = fixture.num_constraints;
+        let resume = |params, inputs, aux| ProverTask::resume(params, inputs, aux, &fixture.qap, n, &state).err();
+        assert_eq!(resume(&fixture.params, &fixture.inputs, &fixture.aux), None);
+        assert_eq!(resume(&other.params, &fixture.inputs, &fixture.aux), Some(DecodingError::Mismatch));
+        assert_eq!(resume(&fixture.params, &other.inputs, &other.aux), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn task_resumes_within_long_queries() {
+        // More variables in the A query than FFT layers
+        let fixture = sum_fixture(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let exp = fft_params::<Scalar>(fixture.num_constraints).unwrap().2 as usize;
+        assert!(fixture.qap.a.len() > exp + 2);
+
+        let mut rng = thread_rng();
+        let mut task = ProverTask::new(
+            &fixture.params,
+            &fixture.inputs,
+            &fixture.aux,
+            Scalar::random(&mut rng),
+            Scalar::random(&mut rng),
+            &fixture.qap,
+            fixture.num_constraints,
+        ).unwrap();
+        let resume = |state: &[u8]| {
+            ProverTask::resume(&fixture.params, &fixture.inputs, &fixture.aux, &fixture.qap, fixture.num_constraints, state)
+        };
+
+        let mut state = task.to_bytes();
+        let proof = loop {
+            task = resume(&state).ok().unwrap();
+            match task.step(1) {
+                Poll::Ready(proof) => break proof,
+                Poll::Pending => state = task.to_bytes(),
+            }
+        };
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        // Cursors out of bounds or inconsistent with the work done are
+        // rejected rather than panicking later
+        let mut task = resume(&task.to_bytes()).ok().unwrap();
+        task.phase = Phase::Evaluate;
+        task.cursor = Cursor { op: 0, stage: exp + 2, pos: 1, done: 0 };
+        let terms = task.units_before().unwrap();
+        task.cursor.done = terms;
+        assert!(resume(&task.to_bytes()).is_ok());
+
+        let total = task.total(Phase::Evaluate);
+        for cursor in [
+            Cursor { op: 3, stage: 0, pos: 0, done: total },
+            Cursor { op: 0, stage: exp + 2, pos: 2, done: terms + 1 },
+            Cursor { op: 0, stage: exp + 2, pos: 1, done: terms - 1 },
+            Cursor { op: 2, stage: fixture.qap.c.len(), pos: 0, done: 0 },
+        ] {
+            task.cursor = cursor;
+            assert_eq!(resume(&task.to_bytes()).err(), Some(DecodingError::InvalidHeader));
+        }
+
+        let m = task.domain.m;
+        task.phase = Phase::Quotient;
+        for cursor in [
+            Cursor { op: QUOTIENT.len(), stage: 1, pos: 0, done: task.total(Phase::Quotient) },
+            Cursor { op: 1, stage: 0, pos: m, done: 2 * m + exp * m / 2 },
+            Cursor { op: 0, stage: exp + 1, pos: 0, done: m + exp * m / 2 },
+        ] {
+            task.cursor = cursor;
+            assert_eq!(resume(&task.to_bytes()).err(), Some(DecodingError::InvalidHeader));
+        }
+        task.cursor = Cursor { op: 1, stage: 0, pos: m - 1, done: 2 * m - 1 + exp * m / 2 };
+        assert!(resume(&task.to_bytes()).is_ok());
+    }
+}
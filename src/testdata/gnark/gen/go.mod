@@ -0,0 +1,5 @@
+module gnarkgen
+
+go 1.21
+
+require github.com/consensys/gnark v0.10.0
@@ -0,0 +1,124 @@
+// Writes Groth16 proofs and verifying keys of gnark for x * x = y, and
+// checks those of the Rust crate with gnark's verifier.
+//
+//	go run . write DIR   gnark's files for BN254 and BLS12-381 with x = 3
+//	go run . verify DIR  ours_*.bin, the BLS12-381 cube fixture with y = 35
+//
+// Neither circuit has commitments.
+package main
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+
+	"github.com/consensys/gnark-crypto/ecc"
+	"github.com/consensys/gnark/backend/groth16"
+	"github.com/consensys/gnark/frontend"
+	"github.com/consensys/gnark/frontend/cs/r1cs"
+)
+
+type Square struct {
+	X frontend.Variable
+	Y frontend.Variable `gnark:",public"`
+}
+
+func (c *Square) Define(api frontend.API) error {
+	api.AssertIsEqual(api.Mul(c.X, c.X), c.Y)
+	return nil
+}
+
+func writeFile(path string, write func(io.Writer) (int64, error)) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	_, err = write(f)
+	return err
+}
+
+func readFile(path string, v io.ReaderFrom) error {
+	f, err := os.Open(path)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	_, err = v.ReadFrom(f)
+	return err
+}
+
+func write(dir string) error {
+	for name, id := range map[string]ecc.ID{"bn254": ecc.BN254, "bls12_381": ecc.BLS12_381} {
+		ccs, err := frontend.Compile(id.ScalarField(), r1cs.NewBuilder, &Square{})
+		if err != nil {
+			return err
+		}
+		pk, vk, err := groth16.Setup(ccs)
+		if err != nil {
+			return err
+		}
+		w, err := frontend.NewWitness(&Square{X: 3, Y: 9}, id.ScalarField())
+		if err != nil {
+			return err
+		}
+		proof, err := groth16.Prove(ccs, pk, w)
+		if err != nil {
+			return err
+		}
+		files := map[string]func(io.Writer) (int64, error){
+			"_proof.bin":     proof.WriteTo,
+			"_proof_raw.bin": proof.WriteRawTo,
+			"_vk.bin":        vk.WriteTo,
+			"_vk_raw.bin":    vk.WriteRawTo,
+		}
+		for suffix, f := range files {
+			if err := writeFile(filepath.Join(dir, name+suffix), f); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+func verify(dir string) error {
+	public, err := frontend.NewWitness(&Square{Y: 35}, ecc.BLS12_381.ScalarField(), frontend.PublicOnly())
+	if err != nil {
+		return err
+	}
+	for _, suffix := range []string{"", "_raw"} {
+		proof := groth16.NewProof(ecc.BLS12_381)
+		vk := groth16.NewVerifyingKey(ecc.BLS12_381)
+		if err := readFile(filepath.Join(dir, "ours_proof"+suffix+".bin"), proof); err != nil {
+			return err
+		}
+		if err := readFile(filepath.Join(dir, "ours_vk"+suffix+".bin"), vk); err != nil {
+			return err
+		}
+		if err := groth16.Verify(proof, vk, public); err != nil {
+			return fmt.Errorf("ours%s: %w", suffix, err)
+		}
+	}
+	return nil
+}
+
+func main() {
+	if len(os.Args) != 3 {
+		fmt.Fprintln(os.Stderr, "usage: gen write|verify DIR")
+		os.Exit(2)
+	}
+	var err error
+	switch os.Args[1] {
+	case "write":
+		err = write(os.Args[2])
+	case "verify":
+		err = verify(os.Args[2])
+	default:
+		err = fmt.Errorf("unknown command %q", os.Args[1])
+	}
+	if err != nil {
+		fmt.Fprintln(os.Stderr, err)
+		os.Exit(1)
+	}
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT
+// Groth16 verifier for BN254, generated from a verification key
+pragma solidity ^0.8.0;
+
+contract Groth16Verifier {
+    // Scalar field modulus
+    uint256 constant R = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    // Base field modulus
+    uint256 constant Q = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+    // G2 coordinates are c1 before c0, as the pairing precompile reads them
+    uint256 constant ALPHA_X = 1368015179489954701390400359078579693043519447331113978918064868415326638035;
+    uint256 constant ALPHA_Y = 9918110051302171585080402603319702774565515993150576347155970296011118125764;
+    uint256 constant BETA_X1 = 7273165102799931111715871471550377909735733521218303035754523677688038059653;
+    uint256 constant BETA_X0 = 2725019753478801796453339367788033689375851816420509565303521482350756874229;
+    uint256 constant BETA_Y1 = 957874124722006818841961785324909313781880061366718538693995380805373202866;
+    uint256 constant BETA_Y0 = 2512659008974376214222774206987427162027254181373325676825515531566330959255;
+    uint256 constant GAMMA_X1 = 4540444681147253467785307942530223364530218361853237193970751657229138047649;
+    uint256 constant GAMMA_X0 = 20954117799226682825035885491234530437475518021362091509513177301640194298072;
+    uint256 constant GAMMA_Y1 = 11631839690097995216017572651900167465857396346217730511548857041925508482915;
+    uint256 constant GAMMA_Y0 = 21508930868448350162258892668132814424284302804699005394342512102884055673846;
+    uint256 constant DELTA_X1 = 18551411094430470096460536606940536822990217226529861227533666875800903099477;
+    uint256 constant DELTA_X0 = 15512671280233143720612069991584289591749188907863576513414377951116606878472;
+    uint256 constant DELTA_Y1 = 1711576522631428957817575436337311654689480489843856945284031697403898093784;
+    uint256 constant DELTA_Y0 = 13376798835316611669264291046140500151806347092962367781523498857425536295743;
+    uint256 constant IC0_X = 19033251874843656108471242320417533909414939332036131356573128480367742634479;
+    uint256 constant IC0_Y = 20792135454608030201903199625673964159744755218442260092768620403349374102584;
+    uint256 constant IC1_X = 2672242651313367459976336264061690128665099451055893690004467838496751824703;
+    uint256 constant IC1_Y = 18247534626997477790812670345925575171672701304065784723769023620148097699216;
+
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[1] calldata input
+    ) public view returns (bool) {
+        uint256[2] memory x = [IC0_X, IC0_Y];
+        require(input[0] < R, "input not in field");
+        x = ecAdd(x, ecMul([IC1_X, IC1_Y], input[0]));
+
+        // e(-A, B) e(alpha, beta) e(x, gamma) e(C, delta) == 1
+        require(a[1] < Q, "proof not in field");
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (Q - a[1]) % Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = x[0];
+        p[13] = x[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+        return ecPairing(p);
+    }
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory r) {
+        uint256[4] memory i = [p1[0], p1[1], p2[0], p2[1]];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, i, 0x80, r, 0x40)
+        }
+        require(ok, "ecAdd failed");
+    }
+
+    function ecMul(uint256[2] memory p, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory i = [p[0], p[1], s];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, i, 0x60, r, 0x40)
+        }
+        require(ok, "ecMul failed");
+    }
+
+    function ecPairing(uint256[24] memory i) internal view returns (bool) {
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, i, 0x300, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+}
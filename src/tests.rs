@@ -0,0 +1,202 @@
+// Shared fixtures for the end-to-end tests in the other modules.
+// Parameters come from bellman, the proving data from `assignments`.
+
+use bellman::{Circuit, ConstraintSystem, SynthesisError};
+use bellman::groth16::generate_random_parameters;
+use bls12_381::{Bls12, Scalar};
+use ff::{Field, PrimeField};
+use pairing::Engine;
+use pairing::group::WnafGroup;
+use rand::thread_rng;
+
+use crate::{assignments, prover, Parameters, Proof, QAP};
+
+// x^3 + x + 5 == out, with x private and out public
+#[derive(Clone, Copy)]
+pub struct CubeDemo<S: PrimeField> {
+    pub x: Option<S>,
+}
+
+impl<S: PrimeField> Circuit<S> for CubeDemo<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let x_val = self.x;
+        let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let tmp_1_val = x_val.map(|e| e.square());
+        let tmp_1 = cs.alloc(|| "tmp_1", || tmp_1_val.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "tmp_1", |lc| lc + x, |lc| lc + x, |lc| lc + tmp_1);
+
+        let x_cubed_val = tmp_1_val.zip(x_val).map(|(t, x)| t * x);
+        let x_cubed = cs.alloc(|| "x_cubed", || x_cubed_val.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "x_cubed", |lc| lc + tmp_1, |lc| lc + x, |lc| lc + x_cubed);
+
+        let out = cs.alloc_input(|| "out", || {
+            let x_cubed = x_cubed_val.ok_or(SynthesisError::AssignmentMissing)?;
+            let x = x_val.ok_or(SynthesisError::AssignmentMissing)?;
+            Ok(x_cubed + x + S::from(5))
+        })?;
+        cs.enforce(
+            || "out",
+            |lc| lc + x_cubed + x + (S::from(5), CS::one()),
+            |lc| lc + CS::one(),
+            |lc| lc + out
+        );
+
+        Ok(())
+    }
+}
+
+// x_1 + ... + x_n == out, with the x_i private and out public. A single
+// constraint with many variables, unlike `CubeDemo`.
+#[derive(Clone)]
+pub struct SumDemo<S: PrimeField> {
+    pub xs: Vec<Option<S>>,
+}
+
+impl<S: PrimeField> Circuit<S> for SumDemo<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut xs = Vec::new();
+        for (i, x) in self.xs.iter().enumerate() {
+            xs.push(cs.alloc(|| format!("x_{}", i), || x.ok_or(SynthesisError::AssignmentMissing))?);
+        }
+        let out = cs.alloc_input(|| "out", || {
+            self.xs.iter().try_fold(S::zero(), |acc, x| Ok(acc + x.ok_or(SynthesisError::AssignmentMissing)?))
+        })?;
+        cs.enforce(
+            || "out",
+            |lc| xs.iter().fold(lc, |lc, x| lc + *x),
+            |lc| lc + CS::one(),
+            |lc| lc + out
+        );
+
+        Ok(())
+    }
+}
+
+pub struct Fixture<E: Engine = Bls12> {
+    pub params: Parameters<E>,
+    pub qap: QAP<E::Fr>,
+    pub inputs: Vec<E::Fr>,
+    pub aux: Vec<E::Fr>,
+    pub num_constraints: usize,
+}
+
+impl<E: Engine> Fixture<E> {
+    // Public inputs as passed to the verifier, without the leading one
+    pub fn public_inputs(&self) -> &[E::Fr] {
+        &self.inputs[1..]
+    }
+
+    pub fn prove(&self) -> Proof<E> {
+        let mut rng = thread_rng();
+        prover::create_proof::<E, _>(
+            self.params.clone(),
+            &self.inputs,
+            &self.aux,
+            E::Fr::random(&mut rng),
+            E::Fr::random(&mut rng),
+            self.qap.clone(),
+            self.num_constraints,
+        ).unwrap()
+    }
+}
+
+pub fn cube_fixture(x: u64) -> Fixture {
+    cube_fixture_on::<Bls12>(x)
+}
+
+pub fn cube_fixture_on<E: Engine>(x: u64) -> Fixture<E>
+where
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    fixture_for(CubeDemo::<E::Fr> { x: None }, CubeDemo { x: Some(E::Fr::from(x)) })
+}
+
+pub fn sum_fixture(xs: &[u64]) -> Fixture {
+    fixture_for(
+        SumDemo::<Scalar> { xs: vec![None; xs.len()] },
+        SumDemo { xs: xs.iter().map(|x| Some(Scalar::from(*x))).collect() },
+    )
+}
+
+// Random parameters from `blank` and the proving data from `circuit`
+pub fn fixture_for<E: Engine, C: Circuit<E::Fr>>(blank: C, circuit: C) -> Fixture<E>
+where
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    let mut rng = thread_rng();
+    let params = generate_random_parameters::<E, _, _>(blank, &mut rng).unwrap();
+
+    let cs = assignments::extract_assignments::<_, E>(circuit).unwrap();
+    let (inputs, aux) = cs.get_assignments();
+    let num_constraints = cs.num_constraints();
+
+    Fixture {
+        params: assignments::create_params(params),
+        qap: cs.qap(),
+        inputs,
+        aux,
+        num_constraints,
+    }
+}
+
+// Modulus of the scalar field, little-endian
+pub fn scalar_modulus() -> Vec<u8> {
+    // r - 1 ends in a zero byte
+    let mut p = (-Scalar::one()).to_repr().to_vec();
+    p[0] += 1;
+    p
+}
+
+// File in the iden3 container of circom and snarkjs, see `circom`
+pub fn container(magic: &[u8], version: u32, sections: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut out = magic.to_vec();
+    out.extend_from_slice(&version.to_le_bytes());
+    out.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    for (id, data) in sections {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+pub fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+// Deploys a contract from its creation bytecode on an empty chain in revm
+// and calls it with `calldata`. The return data, or None if the deployment
+// or the call reverts or halts.
+pub fn run_evm(code: &[u8], calldata: &[u8]) -> Option<Vec<u8>> {
+    use revm::db::{ CacheDB, EmptyDB };
+    use revm::primitives::{ ExecutionResult, Output, TxKind };
+    use revm::Evm;
+
+    let mut db = CacheDB::new(EmptyDB::default());
+    let mut run = |to: TxKind, data: &[u8]| {
+        let mut evm = Evm::builder()
+            .with_db(&mut db)
+            .modify_tx_env(|tx| {
+                tx.transact_to = to;
+                tx.data = data.to_vec().into();
+                tx.gas_limit = 30_000_000;
+            })
+            .build();
+        match evm.transact_commit().unwrap() {
+            ExecutionResult::Success { output, .. } => Some(output),
+            _ => None,
+        }
+    };
+
+    let address = match run(TxKind::Create, code)? {
+        Output::Create(_, address) => address?,
+        Output::Call(_) => return None,
+    };
+    match run(TxKind::Call(address), calldata)? {
+        Output::Call(data) => Some(data.to_vec()),
+        Output::Create(..) => None,
+    }
+}
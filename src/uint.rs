@@ -0,0 +1,193 @@
+// Unsigned integers as little-endian u64 limbs, for the coordinates and
+// field encodings of other tools. The field and group traits do not expose
+// the base field, so its elements are handled as plain integers here.
+
+use core::cmp::Ordering;
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ format, string::String, vec, vec::Vec };
+
+pub fn from_le_bytes(b: &[u8]) -> Vec<u64> {
+    b.chunks(8)
+        .map(|c| c.iter().rev().fold(0, |acc, x| (acc << 8) | u64::from(*x)))
+        .collect()
+}
+
+pub fn from_be_bytes(b: &[u8]) -> Vec<u64> {
+    let mut le = b.to_vec();
+    le.reverse();
+    from_le_bytes(&le)
+}
+
+// The low `len` bytes of x
+pub fn to_le_bytes(x: &[u64], len: usize) -> Vec<u8> {
+    let mut b: Vec<u8> = x.iter().flat_map(|l| l.to_le_bytes()).collect();
+    b.resize(len, 0);
+    b
+}
+
+pub fn to_be_bytes(x: &[u64], len: usize) -> Vec<u8> {
+    let mut b = to_le_bytes(x, len);
+    b.reverse();
+    b
+}
+
+fn limb(x: &[u64], i: usize) -> u64 {
+    x.get(i).copied().unwrap_or(0)
+}
+
+pub fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    (0..a.len().max(b.len())).rev()
+        .map(|i| limb(a, i).cmp(&limb(b, i)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+// a -= b, for a >= b
+pub fn sub_assign(a: &mut [u64], b: &[u64]) {
+    let mut borrow = false;
+    for (i, x) in a.iter_mut().enumerate() {
+        let (d, b1) = x.overflowing_sub(limb(b, i));
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        *x = d;
+        borrow = b1 || b2;
+    }
+    debug_assert!(!borrow);
+}
+
+pub fn shr_assign(a: &mut [u64], bits: u32) {
+    let (limbs, bits) = ((bits / 64) as usize, bits % 64);
+    for i in 0..a.len() {
+        let lo = limb(a, i + limbs);
+        let hi = limb(a, i + limbs + 1);
+        a[i] = if bits == 0 { lo } else { (lo >> bits) | (hi << (64 - bits)) };
+    }
+}
+
+// Decimal digits of x, without leading zeros
+pub fn to_decimal(x: &[u64]) -> String {
+    const BASE: u128 = 10_000_000_000_000_000_000;
+    let mut x = x.to_vec();
+    let mut parts = Vec::new();
+    while x.iter().any(|l| *l != 0) {
+        let mut rem = 0u128;
+        for l in x.iter_mut().rev() {
+            let cur = (rem << 64) | u128::from(*l);
+            *l = (cur / BASE) as u64;
+            rem = cur % BASE;
+        }
+        parts.push(rem as u64);
+    }
+
+    let mut s = format!("{}", parts.pop().unwrap_or(0));
+    for p in parts.iter().rev() {
+        s.push_str(&format!("{:019}", p));
+    }
+    s
+}
+
+// Integer of at most `limbs` limbs from its decimal digits
+pub fn from_decimal(s: &str, limbs: usize) -> Option<Vec<u64>> {
+    if s.is_empty() {
+        return None;
+    }
+    let mut x = vec![0u64; limbs];
+    for c in s.chars() {
+        let mut carry = u128::from(c.to_digit(10)?);
+        for l in x.iter_mut() {
+            let cur = u128::from(*l) * 10 + carry;
+            *l = cur as u64;
+            carry = cur >> 64;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(x)
+}
+
+// x / 2^(64 n) mod q for x < q and n the number of limbs of q, i.e. the
+// standard form of x in Montgomery form
+pub fn from_montgomery(x: &[u64], q: &[u64]) -> Vec<u64> {
+    let n = q.len();
+    // -q^-1 mod 2^64, by Newton iteration
+    let mut inv = 1u64;
+    for _ in 0..6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(q[0].wrapping_mul(inv)));
+    }
+    let inv = inv.wrapping_neg();
+
+    let mut t = vec![0u64; 2 * n + 1];
+    t[..x.len()].copy_from_slice(x);
+    for i in 0..n {
+        let u = t[i].wrapping_mul(inv);
+        let mut carry = 0u128;
+        for j in 0..n {
+            let s = u128::from(t[i + j]) + u128::from(u) * u128::from(q[j]) + carry;
+            t[i + j] = s as u64;
+            carry = s >> 64;
+        }
+        for x in t[i + n..].iter_mut() {
+            let s = u128::from(*x) + carry;
+            *x = s as u64;
+            carry = s >> 64;
+        }
+    }
+
+    let mut r = t[n..].to_vec();
+    if cmp(&r, q).is_ge() {
+        sub_assign(&mut r, q);
+    }
+    r.truncate(n);
+    r
+}
+
+// x * 2^(64 n) mod q, the inverse of `from_montgomery`
+#[cfg(test)]
+pub fn to_montgomery(x: &[u64], q: &[u64]) -> Vec<u64> {
+    let mut r = x.to_vec();
+    r.resize(q.len() + 1, 0);
+    for _ in 0..64 * q.len() {
+        for i in (1..r.len()).rev() {
+            r[i] = (r[i] << 1) | (r[i - 1] >> 63);
+        }
+        r[0] <<= 1;
+        if cmp(&r, q).is_ge() {
+            sub_assign(&mut r, q);
+        }
+    }
+    r.truncate(q.len());
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn montgomery_roundtrip() {
+        // 2^127 - 1
+        let q = [u64::MAX, u64::MAX >> 1];
+        let x = from_be_bytes(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x11]);
+        assert_eq!(to_be_bytes(&x, 10), [0, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x11]);
+
+        // 2^128 = 2 mod q
+        assert_eq!(to_montgomery(&[1], &q), [2, 0]);
+        assert_eq!(from_montgomery(&[2, 0], &q), [1, 0]);
+        let m = to_montgomery(&x, &q);
+        assert_eq!(cmp(&from_montgomery(&m, &q), &x), Ordering::Equal);
+
+        assert_eq!(to_decimal(&[]), "0");
+        assert_eq!(to_decimal(&[0, 1]), "18446744073709551616");
+        assert_eq!(from_decimal("18446744073709551616", 2), Some(vec![0, 1]));
+        assert_eq!(from_decimal("18446744073709551616", 1), None);
+        assert_eq!(from_decimal("12a", 1), None);
+        let d = to_decimal(&q);
+        assert_eq!(d, "170141183460469231731687303715884105727");
+        assert_eq!(from_decimal(&d, 2), Some(q.to_vec()));
+
+        let mut y = x.clone();
+        shr_assign(&mut y, 60);
+        assert_eq!(y, [0x123, 0]);
+    }
+}
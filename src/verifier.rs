@@ -5,30 +5,41 @@ use ff::Field;
 
 
 use crate::{ VerificationKey, VerificationError, Proof };
+use crate::backend::{ Backend, Software };
 
 pub fn verify_proof<E: Engine>(
     proof: &Proof<E>,
     public_inputs: &[E::Fr],
     vk: VerificationKey<E>,
+) -> Result<(), VerificationError> {
+    verify_proof_with_backend(&Software, proof, public_inputs, &vk)
+}
+
+// Same as `verify_proof`, with the input MSM and the pairings computed by
+// `backend`
+pub fn verify_proof_with_backend<E: Engine, B: Backend<E>>(
+    backend: &B,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+    vk: &VerificationKey<E>,
 ) -> Result<(), VerificationError> {
     if (public_inputs.len() + 1) != vk.ic.len() {
         return Err(VerificationError::InvalidVerifyingKey);
     }
 
     let mut acc: E::G1 = vk.ic[0].into();
-
-    for (i, b) in public_inputs.iter().zip(vk.ic.iter().skip(1)) {
-        acc.add_assign(&(*b * i));
-    }
+    acc.add_assign(backend.msm_g1(&vk.ic[1..], public_inputs));
     
-    let mut rhs = E::pairing(&proof.a, &proof.b.into());
-    rhs.add_assign(E::pairing(&acc.into(), &vk.gamma_g2.mul(E::Fr::one().neg()).into()));
-    rhs.add_assign(E::pairing(&proof.c, &vk.delta_g2.mul(E::Fr::one().neg()).into()));
+    let rhs = backend.pairing_product(&[
+        (proof.a, proof.b),
+        (acc.into(), vk.gamma_g2.mul(E::Fr::one().neg()).into()),
+        (proof.c, vk.delta_g2.mul(E::Fr::one().neg()).into()),
+    ]);
 
-    if E::pairing(&vk.alpha_g1, &vk.beta_g2) == rhs {
+    if backend.pairing_product(&[(vk.alpha_g1, vk.beta_g2)]) == rhs {
         Ok(())
     } else {
         Err(VerificationError::InvalidProof)
     }
 
-}
\ No newline at end of file
+}
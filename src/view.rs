@@ -0,0 +1,556 @@
+// Zero-copy views of proving keys and QAPs stored in a byte buffer, e.g.
+// memory-mapped flash or a file mapped on the host.
+//
+// `ParametersRef` reads the key layout documented in `source`, `QAPRef`
+// the QAP layout below. `QAPRef` validates the whole buffer when it is
+// created and afterwards decodes coefficients only when the prover reads
+// them. `ParametersRef` checks only the header, the length and the
+// verification key up front, and checks every query point as it decodes
+// it, so a bad point surfaces as an error of the read. Everything is
+// decoded bytewise, so the buffer needs no particular alignment.
+//
+// Byte layout of an encoded QAP, integers big-endian:
+//
+//   magic "EGQP" | version u8 | length of a field element repr u8 | 2 reserved bytes
+//   number of constraints u32
+//   number of variables and of terms of A, B and C, u32 each
+//   length of a_constraints and b_constraints, u32 each | 4 reserved bytes
+//   the variables of A, then B, then C, each as
+//     variable index u32 | number of terms u32 | (coefficient | constraint u32) per term
+//   a_constraints | b_constraints, u32 each
+//
+// The header is 48 bytes long.
+
+use core::marker::PhantomData;
+
+use ff::PrimeField;
+use pairing::Engine;
+use pairing::group::prime::PrimeCurveAffine;
+
+use crate::{ DecodingError, Parameters, QAP, VerificationKey };
+use crate::encoding::{ Reader, Writer };
+use crate::source::{ decode_vk, DecodePoints, Header, ParameterSource, Query, HEADER_LEN };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{ vec, vec::Vec };
+
+const QAP_MAGIC: &[u8; 4] = b"EGQP";
+const QAP_VERSION: u8 = 1;
+const QAP_HEADER_LEN: usize = 48;
+
+// Number of points decoded at a time
+const CHUNK: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Poly {
+    A,
+    B,
+    C,
+}
+
+// Read access to a QAP, as needed by the prover
+pub trait Constraints<S: PrimeField> {
+    fn num_constraints(&self) -> usize;
+
+    // Calls `f(variable, coefficient, constraint)` for every term of A, B or C
+    fn for_each_term(&self, poly: Poly, f: &mut dyn FnMut(usize, S, usize));
+
+    // Variable of the record at `pos` of A, B or C, None past the last.
+    // Each record holds the terms of one variable, the records come in
+    // increasing order of variable and the first is at position 0.
+    fn record_variable(&self, poly: Poly, pos: usize) -> Option<usize>;
+
+    // Calls `f(coefficient, constraint)` for every term of the record at
+    // `pos` and returns the position of the next record
+    fn for_each_record_term(&self, poly: Poly, pos: usize, f: &mut dyn FnMut(S, usize)) -> usize;
+
+    // Length of `a_constraints` or `b_constraints`
+    fn num_variables(&self, poly: Poly) -> usize;
+
+    // Entry i of `a_constraints` or `b_constraints`
+    fn variable(&self, poly: Poly, i: usize) -> usize;
+}
+
+impl<S: PrimeField> QAP<S> {
+    fn query(&self, poly: Poly) -> &[(usize, Vec<(S, usize)>)] {
+        match poly {
+            Poly::A => &self.a,
+            Poly::B => &self.b,
+            Poly::C => &self.c,
+        }
+    }
+}
+
+impl<S: PrimeField> Constraints<S> for QAP<S> {
+    fn num_constraints(&self) -> usize {
+        QAP::num_constraints(self)
+    }
+
+    fn for_each_term(&self, poly: Poly, f: &mut dyn FnMut(usize, S, usize)) {
+        for (i, terms) in self.query(poly).iter() {
+            for (coeff, constraint) in terms.iter() {
+                f(*i, *coeff, *constraint);
+            }
+        }
+    }
+
+    fn record_variable(&self, poly: Poly, pos: usize) -> Option<usize> {
+        self.query(poly).get(pos).map(|(i, _)| *i)
+    }
+
+    fn for_each_record_term(&self, poly: Poly, pos: usize, f: &mut dyn FnMut(S, usize)) -> usize {
+        for (coeff, constraint) in self.query(poly)[pos].1.iter() {
+            f(*coeff, *constraint);
+        }
+        pos + 1
+    }
+
+    fn num_variables(&self, poly: Poly) -> usize {
+        match poly {
+            Poly::A => self.a_constraints.len(),
+            Poly::B => self.b_constraints.len(),
+            Poly::C => 0,
+        }
+    }
+
+    fn variable(&self, poly: Poly, i: usize) -> usize {
+        match poly {
+            Poly::A => self.a_constraints[i],
+            Poly::B => self.b_constraints[i],
+            Poly::C => panic!("C has no query points"),
+        }
+    }
+}
+
+impl<S: PrimeField, T: Constraints<S>> Constraints<S> for &T {
+    fn num_constraints(&self) -> usize {
+        (**self).num_constraints()
+    }
+
+    fn for_each_term(&self, poly: Poly, f: &mut dyn FnMut(usize, S, usize)) {
+        (**self).for_each_term(poly, f)
+    }
+
+    fn record_variable(&self, poly: Poly, pos: usize) -> Option<usize> {
+        (**self).record_variable(poly, pos)
+    }
+
+    fn for_each_record_term(&self, poly: Poly, pos: usize, f: &mut dyn FnMut(S, usize)) -> usize {
+        (**self).for_each_record_term(poly, pos, f)
+    }
+
+    fn num_variables(&self, poly: Poly) -> usize {
+        (**self).num_variables(poly)
+    }
+
+    fn variable(&self, poly: Poly, i: usize) -> usize {
+        (**self).variable(poly, i)
+    }
+}
+
+// Encodes a QAP in the layout described above
+pub fn encode_qap<S: PrimeField>(qap: &QAP<S>) -> Vec<u8> {
+    let queries = [&qap.a, &qap.b, &qap.c];
+
+    let mut w = Writer::new();
+    w.bytes(QAP_MAGIC);
+    w.u8(QAP_VERSION);
+    w.u8(S::Repr::default().as_ref().len() as u8);
+    w.bytes(&[0, 0]);
+    w.u32(qap.num_constraints() as u32);
+    for q in queries {
+        w.u32(q.len() as u32);
+        w.u32(q.iter().map(|(_, terms)| terms.len()).sum::<usize>() as u32);
+    }
+    w.u32(qap.a_constraints.len() as u32);
+    w.u32(qap.b_constraints.len() as u32);
+    w.bytes(&[0; 4]);
+
+    for q in queries {
+        for (i, terms) in q.iter() {
+            w.u32(*i as u32);
+            w.u32(terms.len() as u32);
+            for (coeff, constraint) in terms.iter() {
+                w.scalar(coeff);
+                w.u32(*constraint as u32);
+            }
+        }
+    }
+    for i in qap.a_constraints.iter().chain(qap.b_constraints.iter()) {
+        w.u32(*i as u32);
+    }
+
+    w.finish()
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> usize {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_be_bytes(b) as usize
+}
+
+#[derive(Clone, Copy)]
+pub struct QAPRef<'a, S: PrimeField> {
+    bytes: &'a [u8],
+    num_constraints: usize,
+    // Byte offsets of the A, B and C records and of a_constraints and b_constraints
+    polys: [usize; 4],
+    a_len: usize,
+    b_len: usize,
+    _marker: PhantomData<S>,
+}
+
+impl<'a, S: PrimeField> QAPRef<'a, S> {
+    // Checks the header, the length of the buffer, every coefficient and
+    // every constraint index. The records of each polynomial must be in
+    // strictly increasing order of variable, and a_constraints and
+    // b_constraints hold exactly the variables of A and B.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodingError> {
+        let repr_len = S::Repr::default().as_ref().len();
+
+        let mut rd = Reader::new(bytes);
+        if rd.bytes(4)? != QAP_MAGIC || rd.u8()? != QAP_VERSION || rd.u8()? as usize != repr_len {
+            return Err(DecodingError::InvalidHeader);
+        }
+        rd.bytes(2)?;
+        let num_constraints = rd.u32()? as usize;
+
+        let mut polys = [QAP_HEADER_LEN; 4];
+        let mut offset = QAP_HEADER_LEN as u64;
+        for p in polys.iter_mut().skip(1) {
+            let vars = rd.u32()? as u64;
+            let terms = rd.u32()? as u64;
+            offset += 8 * vars + terms * (repr_len as u64 + 4);
+            *p = usize::try_from(offset).map_err(|_| DecodingError::InvalidHeader)?;
+        }
+        let a_len = rd.u32()? as usize;
+        let b_len = rd.u32()? as usize;
+        if offset + 4 * (a_len + b_len) as u64 != bytes.len() as u64 {
+            return Err(DecodingError::InvalidHeader);
+        }
+
+        let view = QAPRef {
+            bytes,
+            num_constraints,
+            polys,
+            a_len,
+            b_len,
+            _marker: PhantomData,
+        };
+
+        for (k, poly) in [Poly::A, Poly::B, Poly::C].into_iter().enumerate() {
+            let mut records = Vec::new();
+            let mut rd = Reader::new(&bytes[view.polys[k]..view.polys[k + 1]]);
+            while !rd.is_empty() {
+                records.push(rd.u32()? as usize);
+                for _ in 0..rd.u32()? {
+                    rd.scalar::<S>()?;
+                    if rd.u32()? as usize >= num_constraints {
+                        return Err(DecodingError::Mismatch);
+                    }
+                }
+            }
+
+            let variables = (0..view.num_variables(poly)).map(|i| view.variable(poly, i));
+            if records.windows(2).any(|w| w[0] >= w[1])
+                || (poly != Poly::C && !records.iter().copied().eq(variables))
+            {
+                return Err(DecodingError::Mismatch);
+            }
+        }
+
+        Ok(view)
+    }
+
+    pub fn to_owned(&self) -> QAP<S> {
+        let query = |k: usize| {
+            let mut rd = Reader::new(&self.bytes[self.polys[k]..self.polys[k + 1]]);
+            let mut out = Vec::new();
+            while !rd.is_empty() {
+                let i = rd.u32().unwrap() as usize;
+                let n = rd.u32().unwrap();
+                let terms = (0..n)
+                    .map(|_| (rd.scalar().unwrap(), rd.u32().unwrap() as usize))
+                    .collect();
+                out.push((i, terms));
+            }
+            out
+        };
+
+        QAP {
+            a: query(0),
+            b: query(1),
+            c: query(2),
+            a_constraints: (0..self.a_len).map(|i| self.variable(Poly::A, i)).collect(),
+            b_constraints: (0..self.b_len).map(|i| self.variable(Poly::B, i)).collect(),
+        }
+    }
+}
+
+impl<'a, S: PrimeField> Constraints<S> for QAPRef<'a, S> {
+    fn num_constraints(&self) -> usize {
+        self.num_constraints
+    }
+
+    fn for_each_term(&self, poly: Poly, f: &mut dyn FnMut(usize, S, usize)) {
+        let k = poly as usize;
+        // Validated in `new`
+        let mut rd = Reader::new(&self.bytes[self.polys[k]..self.polys[k + 1]]);
+        while !rd.is_empty() {
+            let i = rd.u32().unwrap() as usize;
+            for _ in 0..rd.u32().unwrap() {
+                let coeff = rd.scalar().unwrap();
+                let constraint = rd.u32().unwrap() as usize;
+                f(i, coeff, constraint);
+            }
+        }
+    }
+
+    fn record_variable(&self, poly: Poly, pos: usize) -> Option<usize> {
+        let k = poly as usize;
+        let offset = self.polys[k] + pos;
+        (offset < self.polys[k + 1]).then(|| be_u32(self.bytes, offset))
+    }
+
+    fn for_each_record_term(&self, poly: Poly, pos: usize, f: &mut dyn FnMut(S, usize)) -> usize {
+        let k = poly as usize;
+        // Validated in `new`
+        let mut rd = Reader::new(&self.bytes[self.polys[k] + pos + 4..self.polys[k + 1]]);
+        let n = rd.u32().unwrap() as usize;
+        for _ in 0..n {
+            let coeff = rd.scalar().unwrap();
+            let constraint = rd.u32().unwrap() as usize;
+            f(coeff, constraint);
+        }
+        pos + 8 + n * (S::Repr::default().as_ref().len() + 4)
+    }
+
+    fn num_variables(&self, poly: Poly) -> usize {
+        match poly {
+            Poly::A => self.a_len,
+            Poly::B => self.b_len,
+            Poly::C => 0,
+        }
+    }
+
+    fn variable(&self, poly: Poly, i: usize) -> usize {
+        let i = match poly {
+            Poly::A => i,
+            Poly::B => self.a_len + i,
+            Poly::C => panic!("C has no query points"),
+        };
+        be_u32(self.bytes, self.polys[3] + 4 * i)
+    }
+}
+
+pub struct ParametersRef<'a, E: Engine> {
+    bytes: &'a [u8],
+    header: Header,
+    vk: VerificationKey<E>,
+    g1: [E::G1Affine; CHUNK],
+    g2: [E::G2Affine; CHUNK],
+}
+
+impl<'a, E: Engine> ParametersRef<'a, E>
+where
+    E::G1Affine: DecodePoints,
+    E::G2Affine: DecodePoints,
+{
+    // Checks the header and the length of the buffer, and decodes and checks
+    // the verification key. Query points are left to the accessors.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodingError> {
+        let header = Header::decode(bytes.get(..HEADER_LEN).ok_or(DecodingError::UnexpectedEof)?)?;
+        if bytes.len() as u64 != header.total::<E>() {
+            return Err(DecodingError::InvalidHeader);
+        }
+        let vk = decode_vk(&mut Reader::new(&bytes[HEADER_LEN..]), &header)?;
+
+        Ok(ParametersRef {
+            bytes,
+            header,
+            vk,
+            g1: [E::G1Affine::identity(); CHUNK],
+            g2: [E::G2Affine::identity(); CHUNK],
+        })
+    }
+
+    // Bytes of the points i..i + n of a query
+    fn points(&self, query: Query, i: usize, n: usize, size: usize) -> &'a [u8] {
+        let start = self.header.offset::<E>(query) as usize + i * size;
+        &self.bytes[start..start + n * size]
+    }
+
+    pub fn to_owned(&self) -> Result<Parameters<E>, DecodingError> {
+        let c = self.header.compressed;
+        let g1 = |query| {
+            let mut out = vec![E::G1Affine::identity(); self.header.len(query)];
+            let bytes = self.points(query, 0, out.len(), self.header.g1_len::<E>());
+            E::G1Affine::decode_points(bytes, &mut out, c, true).map(|_| out)
+        };
+        let mut b_g2 = vec![E::G2Affine::identity(); self.header.len(Query::BG2)];
+        let bytes = self.points(Query::BG2, 0, b_g2.len(), self.header.g2_len::<E>());
+        E::G2Affine::decode_points(bytes, &mut b_g2, c, true)?;
+
+        Ok(Parameters {
+            vk: self.vk.clone(),
+            h: g1(Query::H)?,
+            l: g1(Query::L)?,
+            a_g1: g1(Query::A)?,
+            b_g1: g1(Query::BG1)?,
+            b_g2,
+        })
+    }
+}
+
+impl<'a, E: Engine> ParameterSource<E> for ParametersRef<'a, E>
+where
+    E::G1Affine: DecodePoints,
+    E::G2Affine: DecodePoints,
+{
+    type Error = DecodingError;
+
+    fn vk(&self) -> &VerificationKey<E> {
+        &self.vk
+    }
+
+    fn len(&self, query: Query) -> usize {
+        self.header.len(query)
+    }
+
+    fn g1(&mut self, query: Query, offset: usize) -> Result<&[E::G1Affine], DecodingError> {
+        let n = CHUNK.min(self.header.len(query).saturating_sub(offset));
+        let bytes = self.points(query, offset, n, self.header.g1_len::<E>());
+        E::G1Affine::decode_points(bytes, &mut self.g1[..n], self.header.compressed, true)?;
+        Ok(&self.g1[..n])
+    }
+
+    fn g2(&mut self, offset: usize) -> Result<&[E::G2Affine], DecodingError> {
+        let n = CHUNK.min(self.header.len(Query::BG2).saturating_sub(offset));
+        let bytes = self.points(Query::BG2, offset, n, self.header.g2_len::<E>());
+        E::G2Affine::decode_points(bytes, &mut self.g2[..n], self.header.compressed, true)?;
+        Ok(&self.g2[..n])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+    use ff::Field;
+    use rand::thread_rng;
+
+    use crate::prover::{ create_proof, create_proof_with_scratch, required_scratch };
+    use crate::source::{ encode_parameters, encode_parameters_compressed };
+    use crate::tests::cube_fixture;
+    use crate::verifier::verify_proof;
+    use super::*;
+
+    #[test]
+    fn views_roundtrip() {
+        let fixture = cube_fixture(3);
+
+        let bytes = encode_qap(&fixture.qap);
+        let qap = QAPRef::<Scalar>::new(&bytes).unwrap();
+        assert_eq!(qap.num_constraints(), fixture.num_constraints);
+        assert_eq!(encode_qap(&qap.to_owned()), bytes);
+
+        let bytes = encode_parameters(&fixture.params);
+        let params = ParametersRef::<Bls12>::new(&bytes).unwrap();
+        assert_eq!(encode_parameters(&params.to_owned().unwrap()), bytes);
+
+        let compressed = encode_parameters_compressed(&fixture.params);
+        let params = ParametersRef::<Bls12>::new(&compressed).unwrap();
+        assert_eq!(encode_parameters(&params.to_owned().unwrap()), bytes);
+    }
+
+    #[test]
+    fn views_are_validated() {
+        let fixture = cube_fixture(3);
+
+        let mut bytes = encode_qap(&fixture.qap);
+        assert_eq!(QAPRef::<Scalar>::new(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::InvalidHeader));
+        // First coefficient set to the field modulus, well above it in fact
+        bytes[QAP_HEADER_LEN + 8..QAP_HEADER_LEN + 40].fill(0xff);
+        assert_eq!(QAPRef::<Scalar>::new(&bytes).err(), Some(DecodingError::InvalidFieldElement));
+
+        let mut bytes = encode_parameters(&fixture.params);
+        assert_eq!(ParametersRef::<Bls12>::new(&bytes[..bytes.len() - 1]).err(), Some(DecodingError::InvalidHeader));
+        assert_eq!(ParametersRef::<Bls12>::new(&bytes[1..]).err(), Some(DecodingError::InvalidHeader));
+
+        // A point of the A query that is not on the curve is found when it
+        // is read, not when the view is created
+        let offset = Header::decode(&bytes[..HEADER_LEN]).unwrap().offset::<Bls12>(Query::A) as usize;
+        bytes[offset + 40] ^= 1;
+        let mut params = ParametersRef::<Bls12>::new(&bytes).unwrap();
+        assert_eq!(params.g1(Query::H, 0).map(|p| p.len()), Ok(fixture.params.h.len()));
+        assert_eq!(params.g1(Query::A, 0).err(), Some(DecodingError::InvalidPoint));
+        assert_eq!(params.to_owned().err(), Some(DecodingError::InvalidPoint));
+    }
+
+    #[test]
+    fn qap_variables_are_validated() {
+        let fixture = cube_fixture(3);
+        let qap = |f: &dyn Fn(&mut QAP<Scalar>)| {
+            let mut qap = fixture.qap.clone();
+            f(&mut qap);
+            QAPRef::<Scalar>::new(&encode_qap(&qap)).err()
+        };
+
+        assert_eq!(qap(&|_| ()), None);
+        assert_eq!(qap(&|q| q.a_constraints.reverse()), Some(DecodingError::Mismatch));
+        assert_eq!(qap(&|q| { q.b_constraints.pop(); }), Some(DecodingError::Mismatch));
+        assert_eq!(qap(&|q| q.a_constraints.push(1000)), Some(DecodingError::Mismatch));
+        assert_eq!(qap(&|q| q.a[0].0 = 1000), Some(DecodingError::Mismatch));
+        assert_eq!(qap(&|q| { let v = q.c[0].clone(); q.c.push(v); }), Some(DecodingError::Mismatch));
+        assert_eq!(qap(&|q| q.c.reverse()), Some(DecodingError::Mismatch));
+    }
+
+    #[test]
+    fn proof_from_views() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+
+        let param_bytes = encode_parameters(&fixture.params);
+        let qap_bytes = encode_qap(&fixture.qap);
+
+        let view = ParametersRef::new(&param_bytes).unwrap();
+        let qap_ref = QAPRef::new(&qap_bytes).unwrap();
+        let proof = create_proof::<Bls12, _>(
+            view,
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &qap_ref,
+            fixture.num_constraints,
+        ).unwrap();
+        assert!(verify_proof(&proof, fixture.public_inputs(), fixture.params.vk.clone()).is_ok());
+
+        let mut scratch = vec![Scalar::zero(); required_scratch(&qap_ref).unwrap()];
+        let from_scratch = create_proof_with_scratch::<Bls12, _>(
+            ParametersRef::new(&param_bytes).unwrap(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            &qap_ref,
+            fixture.num_constraints,
+            &mut scratch,
+        ).unwrap();
+        assert_eq!(from_scratch.c, proof.c);
+
+        let expected = create_proof::<Bls12, _>(
+            fixture.params.clone(),
+            &fixture.inputs,
+            &fixture.aux,
+            r,
+            s,
+            fixture.qap.clone(),
+            fixture.num_constraints,
+        ).unwrap();
+        assert_eq!(proof.a, expected.a);
+        assert_eq!(proof.b, expected.b);
+        assert_eq!(proof.c, expected.c);
+    }
+}
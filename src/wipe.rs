@@ -0,0 +1,57 @@
+// Volatile overwriting of secret data. Field and group elements of a
+// generic Engine do not implement `zeroize::Zeroize`, so they are reset to
+// a fixed value with volatile writes followed by a compiler fence, which
+// the optimizer may neither elide nor reorder past.
+//
+// Only memory owned by this crate can be wiped this way. Copies the
+// compiler leaves in registers or on the stack, and the caller's own
+// witness and parameter buffers, are out of reach.
+
+use core::ptr;
+use core::sync::atomic::{ compiler_fence, Ordering };
+
+pub fn wipe<T: Copy>(x: &mut T, value: T) {
+    // SAFETY: x is a valid, aligned and exclusive reference
+    unsafe { ptr::write_volatile(x, value) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+pub fn wipe_slice<T: Copy>(buf: &mut [T], value: T) {
+    for x in buf.iter_mut() {
+        // SAFETY: x is a valid, aligned and exclusive reference
+        unsafe { ptr::write_volatile(x, value) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{ Bls12, Scalar };
+    use ff::Field;
+    use zeroize::Zeroize;
+
+    use crate::assignments::extract_assignments;
+    use crate::tests::CubeDemo;
+    use super::*;
+
+    #[test]
+    fn wipe_resets_values() {
+        let mut buf = [Scalar::from(7); 16];
+        wipe_slice(&mut buf, Scalar::zero());
+        assert!(buf.iter().all(|x| bool::from(x.is_zero())));
+
+        let mut x = Scalar::from(7);
+        wipe(&mut x, Scalar::zero());
+        assert_eq!(x, Scalar::zero());
+    }
+
+    #[test]
+    fn analyze_circuit_zeroize() {
+        let mut cs = extract_assignments::<_, Bls12>(CubeDemo { x: Some(Scalar::from(3)) }).unwrap();
+        assert_eq!(cs.get_assignments().1.len(), 3);
+
+        cs.zeroize();
+        let (inputs, aux) = cs.get_assignments();
+        assert!(inputs.is_empty() && aux.is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+// Proving with the assignment read sequentially, for circuits whose
+// witness does not fit in memory.
+//
+// `create_proof_streaming` reads the assignment once, in order, inputs
+// first and then aux, one chunk at a time. While a chunk is in memory its
+// values are added into the evaluations of A, B and C and into the L, A
+// and B MSMs, whose query points are read sequentially alongside. After
+// the pass only h(X) and its MSM remain, which do not depend on the
+// assignment directly.
+//
+// The QAP is walked one record of A, B and C at a time, in the order of
+// the variables that `QAP::new` and `QAPRef::new` require. The L, A and B
+// points are multiplied in batches by the backend's MSM. The prover holds
+// the A, B and C evaluations, three vectors of the domain size, one chunk
+// of the assignment and one batch of points and scalars per query.
+
+use core::ops::AddAssign;
+
+use ff::{Field, PrimeField};
+use pairing::Engine;
+use pairing::group::Group;
+use pairing::group::prime::PrimeCurveAffine;
+
+use crate::{ DecodingError, Proof };
+use crate::backend::{ Backend, Software, MSM_BATCH };
+use crate::poly::{ coset_mul_assign_with, fft_params };
+use crate::prover::{ assemble, divide_by_z, msm_g1 };
+use crate::source::{ ParameterSource, Query };
+use crate::view::{ Constraints, Poly };
+
+#[cfg(any(test, feature = "std"))]
+use crate::source::{ IoReader, SourceError };
+
+#[cfg(not(any(test, feature = "std")))]
+use alloc::{vec, vec::Vec};
+
+#[cfg(any(test, feature = "zeroize"))]
+use crate::wipe::{ wipe, wipe_slice };
+
+const CHUNK: usize = 64;
+
+// Assignment read sequentially, inputs first and then aux
+pub trait WitnessReader<S> {
+    type Error;
+
+    // Fills `buf` with the next values of the assignment
+    fn read(&mut self, buf: &mut [S]) -> Result<(), Self::Error>;
+}
+
+impl<S: Copy> WitnessReader<S> for &[S] {
+    type Error = DecodingError;
+
+    fn read(&mut self, buf: &mut [S]) -> Result<(), DecodingError> {
+        if self.len() < buf.len() {
+            return Err(DecodingError::UnexpectedEof);
+        }
+        let (head, tail) = self.split_at(buf.len());
+        buf.copy_from_slice(head);
+        *self = tail;
+        Ok(())
+    }
+}
+
+// Adapter for iterators over the assignment, e.g. values computed on the fly
+pub struct IterWitness<I>(pub I);
+
+impl<S, I: Iterator<Item = S>> WitnessReader<S> for IterWitness<I> {
+    type Error = DecodingError;
+
+    fn read(&mut self, buf: &mut [S]) -> Result<(), DecodingError> {
+        for x in buf.iter_mut() {
+            *x = self.0.next().ok_or(DecodingError::UnexpectedEof)?;
+        }
+        Ok(())
+    }
+}
+
+// Reads field elements in their `PrimeField::Repr` from a `std::io` reader
+#[cfg(any(test, feature = "std"))]
+impl<S: PrimeField, T: std::io::Read> WitnessReader<S> for IoReader<T> {
+    type Error = SourceError<std::io::Error>;
+
+    fn read(&mut self, buf: &mut [S]) -> Result<(), Self::Error> {
+        for x in buf.iter_mut() {
+            let mut repr = S::Repr::default();
+            self.0.read_exact(repr.as_mut()).map_err(SourceError::Read)?;
+            *x = Option::from(S::from_repr(repr)).ok_or(DecodingError::InvalidFieldElement)?;
+        }
+        Ok(())
+    }
+}
+
+// Position of the next record of one polynomial to visit
+struct Records {
+    poly: Poly,
+    pos: usize,
+}
+
+impl Records {
+    fn new(poly: Poly) -> Self {
+        Records { poly, pos: 0 }
+    }
+
+    // Adds the terms of variable i, with value w, to the evaluation
+    fn eval<S: PrimeField>(&mut self, qap: &impl Constraints<S>, i: usize, w: &S, output: &mut [S]) {
+        if qap.record_variable(self.poly, self.pos) == Some(i) {
+            self.pos = qap.for_each_record_term(self.poly, self.pos, &mut |mut x, u| {
+                x.mul_assign(w);
+                output[u].add_assign(x);
+            });
+        }
+    }
+
+    // Whether every record has been visited, false if the records are out
+    // of order or have variables past the assignment
+    fn is_done<S: PrimeField>(&self, qap: &impl Constraints<S>) -> bool {
+        qap.record_variable(self.poly, self.pos).is_none()
+    }
+}
+
+// One MSM over a query whose points are read in order, a batch at a time,
+// each batch handed to the backend once all its scalars are known
+struct Msm<G: PrimeCurveAffine> {
+    bases: [G; MSM_BATCH],
+    scalars: [G::Scalar; MSM_BATCH],
+    // Points read into `bases`, and scalars set for them so far
+    loaded: usize,
+    len: usize,
+}
+
+impl<G: PrimeCurveAffine> Msm<G> {
+    fn new() -> Self {
+        Msm { bases: [G::identity(); MSM_BATCH], scalars: [G::Scalar::zero(); MSM_BATCH], loaded: 0, len: 0 }
+    }
+
+    // Sets the scalar of point j, the one after the previous. `fill(j, buf)`
+    // copies the points from j on into `buf` and returns how many, `flush`
+    // receives each complete batch.
+    fn add<T>(
+        &mut self,
+        j: usize,
+        s: &G::Scalar,
+        fill: impl FnOnce(usize, &mut [G]) -> Result<usize, T>,
+        flush: impl FnOnce(&[G], &[G::Scalar])
+    ) -> Result<(), T>
+    {
+        if self.loaded == 0 {
+            self.loaded = fill(j, &mut self.bases)?;
+            assert!(self.loaded > 0);
+        }
+        self.scalars[self.len] = *s;
+        self.len += 1;
+        if self.len == self.loaded {
+            self.finish(flush);
+        }
+        Ok(())
+    }
+
+    // Hands the last, partial batch to `flush`
+    fn finish(&mut self, flush: impl FnOnce(&[G], &[G::Scalar])) {
+        if self.len > 0 {
+            flush(&self.bases[..self.len], &self.scalars[..self.len]);
+        }
+        self.loaded = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<G: PrimeCurveAffine> Drop for Msm<G> {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.scalars, G::Scalar::zero());
+    }
+}
+
+fn copy_chunk<G: Copy>(chunk: &[G], buf: &mut [G]) -> usize {
+    let n = chunk.len().min(buf.len());
+    buf[..n].copy_from_slice(&chunk[..n]);
+    n
+}
+
+#[derive(Debug)]
+pub enum StreamError<P, W> {
+    // The parameter source failed to provide its points
+    Source(P),
+    // The assignment could not be read
+    Witness(W),
+    // The QAP has more constraints than the largest FFT domain of the
+    // scalar field
+    DomainTooLarge,
+    // The queries of the parameters do not fit the QAP, or its records are
+    // not in variable order
+    Mismatch,
+}
+
+// Witness-dependent values of a proof in progress
+struct State<E: Engine> {
+    at: Vec<E::Fr>,
+    bt: Vec<E::Fr>,
+    ct: Vec<E::Fr>,
+    buf: [E::Fr; CHUNK],
+    r: E::Fr,
+    s: E::Fr,
+    h: E::G1,
+    l: E::G1,
+    at_g1: E::G1,
+    bt_g1: E::G1,
+    bt_g2: E::G2,
+}
+
+#[cfg(any(test, feature = "zeroize"))]
+impl<E: Engine> Drop for State<E> {
+    fn drop(&mut self) {
+        for v in [&mut self.at, &mut self.bt, &mut self.ct] {
+            wipe_slice(v, E::Fr::zero());
+        }
+        wipe_slice(&mut self.buf, E::Fr::zero());
+        wipe(&mut self.r, E::Fr::zero());
+        wipe(&mut self.s, E::Fr::zero());
+        for p in [&mut self.h, &mut self.l, &mut self.at_g1, &mut self.bt_g1] {
+            wipe(p, E::G1::identity());
+        }
+        wipe(&mut self.bt_g2, E::G2::identity());
+    }
+}
+
+// Same as `prover::create_proof_from_source`, with the assignment read
+// once, in order, from `witness` instead of taken as slices. The numbers of
+// inputs and of aux variables are those of the parameters.
+pub fn create_proof_streaming<E: Engine, P: ParameterSource<E>, W: WitnessReader<E::Fr>>(
+    source: &mut P,
+    witness: W,
+    r: E::Fr,
+    s: E::Fr,
+    qap: &impl Constraints<E::Fr>
+) -> Result<Proof<E>, StreamError<P::Error, W::Error>>
+{
+    create_proof_streaming_with_backend(&Software, source, witness, r, s, qap)
+}
+
+// Same as `create_proof_streaming`, with the MSMs, FFTs and scalar
+// multiplications performed by `backend`
+pub fn create_proof_streaming_with_backend<E, B, P, W>(
+    backend: &B,
+    source: &mut P,
+    mut witness: W,
+    r: E::Fr,
+    s: E::Fr,
+    qap: &impl Constraints<E::Fr>
+) -> Result<Proof<E>, StreamError<P::Error, W::Error>>
+where
+    E: Engine,
+    B: Backend<E>,
+    P: ParameterSource<E>,
+    W: WitnessReader<E::Fr>,
+{
+    let num_inputs = source.vk().ic.len();
+    let num_aux = source.len(Query::L);
+    let (num_a, num_b) = (qap.num_variables(Poly::A), qap.num_variables(Poly::B));
+    let (omega, m, exp): (E::Fr, usize, u32) = fft_params(qap.num_constraints()).ok_or(StreamError::DomainTooLarge)?;
+
+    // h(X) has degree m - 2, the top coefficient is zero
+    if source.len(Query::A) != num_a
+        || source.len(Query::BG1) != num_b
+        || source.len(Query::BG2) != num_b
+        || source.len(Query::H) < m - 1
+    {
+        return Err(StreamError::Mismatch);
+    }
+    let mut st = State::<E> {
+        at: vec![E::Fr::zero(); m],
+        bt: vec![E::Fr::zero(); m],
+        ct: vec![E::Fr::zero(); m],
+        buf: [E::Fr::zero(); CHUNK],
+        r,
+        s,
+        h: E::G1::identity(),
+        l: E::G1::identity(),
+        at_g1: E::G1::identity(),
+        bt_g1: E::G1::identity(),
+        bt_g2: E::G2::identity(),
+    };
+    let (mut a_records, mut b_records, mut c_records) = (Records::new(Poly::A), Records::new(Poly::B), Records::new(Poly::C));
+
+    let (mut l_msm, mut a_msm, mut b_g1_msm) = (Msm::new(), Msm::new(), Msm::new());
+    let mut b_g2_msm = Msm::new();
+    let (mut ja, mut jb) = (0, 0);
+
+    let fixed = |query| source.fixed_base().is_some_and(|t| t.covers(query));
+    let (l_fixed, a_fixed) = (fixed(Query::L), fixed(Query::A));
+
+    let n = num_inputs + num_aux;
+    for start in (0..n).step_by(CHUNK) {
+        let len = CHUNK.min(n - start);
+        witness.read(&mut st.buf[..len]).map_err(StreamError::Witness)?;
+
+        for (i, w) in (start..).zip(st.buf[..len].iter()) {
+            a_records.eval(qap, i, w, &mut st.at);
+            b_records.eval(qap, i, w, &mut st.bt);
+            c_records.eval(qap, i, w, &mut st.ct);
+
+            if i >= num_inputs {
+                let j = i - num_inputs;
+                if l_fixed {
+                    st.l.add_assign(source.fixed_base().unwrap().mul_query(Query::L, j, w));
+                } else {
+                    l_msm.add(
+                        j,
+                        w,
+                        |o, buf| Ok(copy_chunk(source.g1(Query::L, o)?, buf)),
+                        |bases, scalars| st.l.add_assign(backend.msm_g1(bases, scalars)),
+                    ).map_err(StreamError::Source)?;
+                }
+            }
+
+            if ja < num_a && qap.variable(Poly::A, ja) == i {
+                if a_fixed {
+                    st.at_g1.add_assign(source.fixed_base().unwrap().mul_query(Query::A, ja, w));
+                } else {
+                    a_msm.add(
+                        ja,
+                        w,
+                        |o, buf| Ok(copy_chunk(source.g1(Query::A, o)?, buf)),
+                        |bases, scalars| st.at_g1.add_assign(backend.msm_g1(bases, scalars)),
+                    ).map_err(StreamError::Source)?;
+                }
+                ja += 1;
+            }
+
+            if jb < num_b && qap.variable(Poly::B, jb) == i {
+                b_g1_msm.add(
+                    jb,
+                    w,
+                    |o, buf| Ok(copy_chunk(source.g1(Query::BG1, o)?, buf)),
+                    |bases, scalars| st.bt_g1.add_assign(backend.msm_g1(bases, scalars)),
+                ).map_err(StreamError::Source)?;
+                b_g2_msm.add(
+                    jb,
+                    w,
+                    |o, buf| Ok(copy_chunk(source.g2(o)?, buf)),
+                    |bases, scalars| st.bt_g2.add_assign(backend.msm_g2(bases, scalars)),
+                ).map_err(StreamError::Source)?;
+                jb += 1;
+            }
+        }
+    }
+
+    l_msm.finish(|bases, scalars| st.l.add_assign(backend.msm_g1(bases, scalars)));
+    a_msm.finish(|bases, scalars| st.at_g1.add_assign(backend.msm_g1(bases, scalars)));
+    b_g1_msm.finish(|bases, scalars| st.bt_g1.add_assign(backend.msm_g1(bases, scalars)));
+    b_g2_msm.finish(|bases, scalars| st.bt_g2.add_assign(backend.msm_g2(bases, scalars)));
+
+    // Every variable and every record of the QAP has been visited
+    if ja != num_a || jb != num_b || !a_records.is_done(qap) || !b_records.is_done(qap) || !c_records.is_done(qap) {
+        return Err(StreamError::Mismatch);
+    }
+
+    let fft = |a: &mut [E::Fr], omega: &E::Fr, exp| backend.fft(a, omega, exp);
+    coset_mul_assign_with(&mut st.at, &mut st.bt, &fft);
+    divide_by_z(&mut st.at, &mut st.ct, &omega, exp, &fft);
+
+    let at = &st.at;
+    st.h = msm_g1(backend, source, Query::H, |i| if i < m - 1 { at[i] } else { E::Fr::zero() })
+        .map_err(StreamError::Source)?;
+
+    Ok(assemble(backend, source.vk(), source.fixed_base(), st.h, st.l, st.at_g1, st.bt_g1, st.bt_g2, &st.r, &st.s))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bls12_381::{ Bls12, Scalar };
+    use rand::thread_rng;
+
+    use crate::fixed::PrecomputedParameters;
+    use crate::prover::create_proof;
+    use crate::source::{ encode_parameters, Header, HEADER_LEN };
+    use crate::tests::cube_fixture;
+    use crate::view::{ encode_qap, ParametersRef, QAPRef };
+    use super::*;
+
+    #[test]
+    fn streamed_witness_matches_prover() {
+        let mut rng = thread_rng();
+        let fixture = cube_fixture(3);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let (qap, inputs, aux) = (&fixture.qap, &fixture.inputs, &fixture.aux);
+        let params = &fixture.params;
+
+        let expected = create_proof::<Bls12, _>(params, inputs, aux, r, s, qap, fixture.num_constraints).unwrap();
+        let witness: Vec<Scalar> = inputs.iter().chain(aux.iter()).copied().collect();
+        let bytes: Vec<u8> = witness.iter().flat_map(|x| x.to_bytes()).collect();
+        let precomputed = PrecomputedParameters::new(params.clone(), 2);
+
+        let encoded = encode_qap(qap);
+        let qap_ref = QAPRef::<Scalar>::new(&encoded).unwrap();
+
+        let proofs = [
+            create_proof_streaming::<Bls12, _, _>(&mut &*params, &witness[..], r, s, qap).unwrap(),
+            create_proof_streaming::<Bls12, _, _>(&mut &*params, IterWitness(inputs.iter().chain(aux.iter()).copied()), r, s, qap).unwrap(),
+            create_proof_streaming::<Bls12, _, _>(&mut &*params, IoReader(Cursor::new(bytes)), r, s, qap).unwrap(),
+            create_proof_streaming::<Bls12, _, _>(&mut &precomputed, &witness[..], r, s, qap).unwrap(),
+            create_proof_streaming::<Bls12, _, _>(&mut &*params, &witness[..], r, s, &qap_ref).unwrap(),
+        ];
+        for proof in proofs.iter() {
+            assert_eq!(proof.a, expected.a);
+            assert_eq!(proof.b, expected.b);
+            assert_eq!(proof.c, expected.c);
+        }
+
+        let short = &witness[..witness.len() - 1];
+        assert!(matches!(
+            create_proof_streaming::<Bls12, _, _>(&mut &*params, short, r, s, qap),
+            Err(StreamError::Witness(DecodingError::UnexpectedEof))
+        ));
+
+        // A bad query point of a view is reported as an error of the source
+        let mut encoded = encode_parameters(params);
+        let offset = Header::decode(&encoded[..HEADER_LEN]).unwrap().offset::<Bls12>(Query::L) as usize;
+        encoded[offset + 40] ^= 1;
+        let mut view = ParametersRef::<Bls12>::new(&encoded).unwrap();
+        assert!(matches!(
+            create_proof_streaming::<Bls12, _, _>(&mut view, &witness[..], r, s, qap),
+            Err(StreamError::Source(DecodingError::InvalidPoint))
+        ));
+    }
+
+    #[test]
+    fn unsorted_records_are_rejected() {
+        let fixture = cube_fixture(3);
+        let witness: Vec<Scalar> = fixture.inputs.iter().chain(fixture.aux.iter()).copied().collect();
+        let mut qap = fixture.qap.clone();
+        qap.c.reverse();
+
+        assert!(matches!(
+            create_proof_streaming::<Bls12, _, _>(&mut &fixture.params, &witness[..], Scalar::one(), Scalar::one(), &qap),
+            Err(StreamError::Mismatch)
+        ));
+    }
+}